@@ -0,0 +1,19 @@
+#!/bin/bash
+./build.sh
+near create-account ausdt.market.synchro.testnet \
+    --masterAccount market.synchro.testnet \
+    --initialBalance 5
+near deploy \
+    --accountId ausdt.market.synchro.testnet \
+    --wasmFile res/atoken.wasm \
+    --initFunction new \
+    --initArgs '{
+        "owner_id": "blockwave.testnet",
+        "market_contract": "market.synchro.testnet",
+        "metadata": {
+            "spec": "ft-1.0.0",
+            "name": "Synchro USDT Deposit",
+            "symbol": "aUSDT",
+            "decimals": 6
+        }
+    }'
@@ -0,0 +1,85 @@
+use crate::*;
+
+impl Contract {
+  pub(crate) fn assert_owner(&self) {
+    assert_eq!(
+      env::predecessor_account_id(),
+      self.config.owner_id,
+      "Can only be called by the owner"
+    );
+  }
+
+  pub(crate) fn assert_market(&self) {
+    assert_eq!(
+      env::predecessor_account_id(),
+      self.config.market_contract,
+      "Can only be called by the market"
+    );
+  }
+
+  /// the registration pays for the balance of an account
+  pub(crate) fn storage_bytes_of(&self, _account_id: &AccountId) -> StorageUsage {
+    STORAGE_BYTES_PER_ACCOUNT
+  }
+
+  pub(crate) fn internal_balance(&self, account_id: &AccountId) -> Balance {
+    self.collection.balance_map.get(account_id).unwrap_or(0)
+  }
+
+  /// credits a registered account, growing the total supply
+  pub(crate) fn internal_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+    self.assert_storage_covered(account_id);
+    let balance = self.internal_balance(account_id);
+    if let Some(new_balance) = balance.checked_add(amount) {
+      self.collection.balance_map.insert(account_id, &new_balance);
+      self.total_supply = self
+        .total_supply
+        .checked_add(amount)
+        .expect("Total supply overflow");
+    } else {
+      env::panic(b"Balance overflow");
+    }
+  }
+
+  /// debits an account, shrinking the total supply
+  pub(crate) fn internal_withdraw(&mut self, account_id: &AccountId, amount: Balance) {
+    let balance = self.internal_balance(account_id);
+    if let Some(new_balance) = balance.checked_sub(amount) {
+      self.collection.balance_map.insert(account_id, &new_balance);
+      self.total_supply = self
+        .total_supply
+        .checked_sub(amount)
+        .expect("Total supply overflow");
+    } else {
+      env::panic(b"The account doesn't have enough balance");
+    }
+  }
+
+  pub(crate) fn internal_transfer(
+    &mut self,
+    sender_id: &AccountId,
+    receiver_id: &AccountId,
+    amount: Balance,
+    memo: Option<String>,
+  ) {
+    assert_ne!(
+      sender_id, receiver_id,
+      "Sender and receiver should be different"
+    );
+    assert!(amount > 0, "The amount should be a positive number");
+
+    self.internal_withdraw(sender_id, amount);
+    self.internal_deposit(receiver_id, amount);
+
+    env::log(
+      format!(
+        "Transfer {} {} from {} to {}",
+        amount, self.metadata.symbol, sender_id, receiver_id
+      )
+      .as_bytes(),
+    );
+    if let Some(memo) = memo {
+      env::log(format!("Memo: {}", memo).as_bytes());
+    }
+  }
+}
@@ -0,0 +1,114 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::{Base64VecU8, ValidAccountId, U128};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, near_bindgen, AccountId, Balance, BorshStorageKey, Gas,
+    PanicOnDefault, Promise, PromiseOrValue, PromiseResult, StorageUsage,
+};
+
+use crate::metadata::FungibleTokenMetadata;
+use crate::state::{Collection, Config};
+
+mod internal;
+mod metadata;
+mod owner;
+mod state;
+#[path = "../../common/storage.rs"]
+mod storage;
+#[cfg(test)]
+mod testing;
+mod token;
+mod upgrade;
+
+const NO_DEPOSIT: Balance = 0;
+
+// the balance of an account with the longest account ID
+const STORAGE_BYTES_PER_ACCOUNT: StorageUsage = 125;
+// an account only ever has its balance
+const MAX_STORAGE_BYTES_PER_ACCOUNT: Option<StorageUsage> = Some(STORAGE_BYTES_PER_ACCOUNT);
+
+#[derive(BorshStorageKey, BorshSerialize)]
+pub(crate) enum StorageKey {
+    Balance,
+    StorageBalance,
+}
+
+/// Deposit receipt of a single reserve of the market. The market mints it for
+/// the stable asset deposited into the reserve and burns it on redemptions.
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    config: Config,
+    metadata: FungibleTokenMetadata,
+    total_supply: Balance,
+    collection: Collection,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(
+        owner_id: AccountId,
+        market_contract: AccountId,
+        metadata: FungibleTokenMetadata,
+    ) -> Self {
+        assert!(!env::state_exists(), "The contract is already initialized");
+        assert!(
+            env::is_valid_account_id(owner_id.as_bytes()),
+            "The owner account ID is invalid"
+        );
+        assert!(
+            env::is_valid_account_id(market_contract.as_bytes()),
+            "The market account ID is invalid"
+        );
+        metadata.assert_valid();
+
+        let config = Config {
+            owner_id,
+            market_contract,
+        };
+
+        let collection = Collection {
+            balance_map: LookupMap::new(StorageKey::Balance),
+            storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
+        };
+
+        Self::internal_write_state_version();
+
+        Self {
+            config,
+            metadata,
+            total_supply: 0,
+            collection,
+        }
+    }
+
+    /// Mints the aToken of a deposit to the depositor, who has to be registered
+    pub fn mint(&mut self, account_id: AccountId, amount: U128) {
+        self.assert_market();
+
+        self.internal_deposit(&account_id, amount.0);
+        env::log(
+            format!(
+                "Mint {} {} to {}",
+                amount.0, self.metadata.symbol, account_id
+            )
+            .as_bytes(),
+        );
+    }
+
+    /// Burns the aToken a redeemer exchanges for the stable asset
+    pub fn burn(&mut self, account_id: AccountId, amount: U128) {
+        self.assert_market();
+
+        self.internal_withdraw(&account_id, amount.0);
+        env::log(
+            format!(
+                "Burn {} {} from {}",
+                amount.0, self.metadata.symbol, account_id
+            )
+            .as_bytes(),
+        );
+    }
+}
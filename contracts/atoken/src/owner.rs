@@ -0,0 +1,27 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+  #[payable]
+  pub fn update_config(&mut self, owner_id: Option<AccountId>) {
+    self.assert_owner();
+    assert_one_yocto();
+
+    if let Some(owner_id) = owner_id {
+      self.config.owner_id = owner_id;
+    }
+  }
+
+  #[payable]
+  pub fn update_metadata(&mut self, metadata: FungibleTokenMetadata) {
+    assert_one_yocto();
+    self.assert_owner();
+    metadata.assert_valid();
+
+    self.metadata = metadata;
+  }
+
+  pub fn get_config(&self) -> Config {
+    self.config.clone()
+  }
+}
@@ -0,0 +1,16 @@
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Config {
+  pub owner_id: AccountId,
+  // the only account minting and burning the aToken
+  pub market_contract: AccountId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Collection {
+  pub balance_map: LookupMap<AccountId, Balance>,
+  // NEP-145 storage balance of the registered accounts
+  pub storage_balance_map: LookupMap<AccountId, Balance>,
+}
@@ -0,0 +1,60 @@
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use crate::metadata::FT_METADATA_SPEC;
+use crate::*;
+
+mod tests;
+mod token_ut;
+
+pub(crate) fn atoken_metadata() -> FungibleTokenMetadata {
+  FungibleTokenMetadata {
+    spec: FT_METADATA_SPEC.to_string(),
+    name: String::from("Synchro USDT Deposit"),
+    symbol: String::from("aUSDT"),
+    icon: None,
+    reference: None,
+    reference_hash: None,
+    decimals: 6,
+  }
+}
+
+/// The aToken of the USDT reserve, minted by `market`
+pub(crate) fn setup_contract() -> (VMContextBuilder, Contract) {
+  let mut context = VMContextBuilder::new();
+  testing_env!(context
+    .current_account_id(ValidAccountId::try_from("ausdt").unwrap())
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .attached_deposit(1)
+    .build());
+  let contract = Contract::new(
+    AccountId::from("owner"),
+    AccountId::from("market"),
+    atoken_metadata(),
+  );
+  (context, contract)
+}
+
+/// Registers the account with the minimum storage balance, as the caller
+pub(crate) fn register(context: &mut VMContextBuilder, contract: &mut Contract, account_id: &str) {
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from(account_id).unwrap())
+    .attached_deposit(min_balance)
+    .build());
+  contract.storage_deposit(None, None);
+}
+
+/// Mints to a registered account, as the market
+pub(crate) fn mint(
+  context: &mut VMContextBuilder,
+  contract: &mut Contract,
+  account_id: &str,
+  amount: Balance,
+) {
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("market").unwrap())
+    .attached_deposit(0)
+    .build());
+  contract.mint(AccountId::from(account_id), U128(amount));
+}
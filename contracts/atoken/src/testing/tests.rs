@@ -0,0 +1,105 @@
+use near_sdk::test_utils::accounts;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use crate::metadata::{FungibleTokenMetadataProvider, FT_METADATA_SPEC};
+use crate::testing::{atoken_metadata, mint, register, setup_contract};
+use crate::token::FungibleTokenCore;
+use crate::*;
+
+#[test]
+fn proper_initialization() {
+  let (_, contract) = setup_contract();
+
+  let metadata = contract.ft_metadata();
+  assert_eq!(FT_METADATA_SPEC, metadata.spec);
+  assert_eq!(String::from("aUSDT"), metadata.symbol);
+  assert_eq!(6, metadata.decimals);
+  assert_eq!(U128(0), contract.ft_total_supply());
+  assert_eq!(
+    AccountId::from("market"),
+    contract.get_config().market_contract
+  );
+  assert_eq!(1, contract.get_state_version());
+}
+
+#[test]
+fn proper_mint_and_burn() {
+  let (mut context, mut contract) = setup_contract();
+  register(&mut context, &mut contract, accounts(1).as_ref());
+  register(&mut context, &mut contract, accounts(2).as_ref());
+
+  mint(&mut context, &mut contract, accounts(1).as_ref(), 1000);
+  mint(&mut context, &mut contract, accounts(2).as_ref(), 500);
+  assert_eq!(U128(1000), contract.ft_balance_of(accounts(1)));
+  assert_eq!(U128(500), contract.ft_balance_of(accounts(2)));
+  assert_eq!(U128(1500), contract.ft_total_supply());
+
+  contract.burn(accounts(1).into(), U128(400));
+  assert_eq!(U128(600), contract.ft_balance_of(accounts(1)));
+  assert_eq!(U128(1100), contract.ft_total_supply());
+}
+
+#[test]
+#[should_panic(expected = "The account doesn't have enough balance")]
+fn burn_exceeds_balance() {
+  let (mut context, mut contract) = setup_contract();
+  register(&mut context, &mut contract, accounts(1).as_ref());
+
+  mint(&mut context, &mut contract, accounts(1).as_ref(), 100);
+  contract.burn(accounts(1).into(), U128(101));
+}
+
+#[test]
+#[should_panic(expected = "The account bob is not registered")]
+fn mint_to_unregistered_account() {
+  let (mut context, mut contract) = setup_contract();
+
+  mint(&mut context, &mut contract, accounts(1).as_ref(), 100);
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the market")]
+fn mint_only_market() {
+  let (mut context, mut contract) = setup_contract();
+  register(&mut context, &mut contract, accounts(1).as_ref());
+
+  contract.mint(accounts(1).into(), U128(100));
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the market")]
+fn burn_only_market() {
+  let (mut context, mut contract) = setup_contract();
+  register(&mut context, &mut contract, accounts(1).as_ref());
+  mint(&mut context, &mut contract, accounts(1).as_ref(), 100);
+
+  testing_env!(context.predecessor_account_id(accounts(1)).build());
+  contract.burn(accounts(1).into(), U128(100));
+}
+
+#[test]
+fn proper_update_metadata() {
+  let (mut context, mut contract) = setup_contract();
+
+  let mut metadata = atoken_metadata();
+  metadata.name = String::from("Synchro Tether Deposit");
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .attached_deposit(1)
+    .build());
+  contract.update_metadata(metadata.clone());
+
+  assert_eq!(metadata, contract.ft_metadata());
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the owner")]
+fn update_metadata_only_owner() {
+  let (mut context, mut contract) = setup_contract();
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("market").unwrap())
+    .attached_deposit(1)
+    .build());
+  contract.update_metadata(atoken_metadata());
+}
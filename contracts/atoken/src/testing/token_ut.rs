@@ -0,0 +1,87 @@
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain, RuntimeFeesConfig, VMConfig};
+
+use crate::testing::{mint, register, setup_contract};
+use crate::token::{FungibleTokenCore, FungibleTokenResolver};
+use crate::*;
+
+/// bob holds 1000 and charlie is registered
+fn setup_holders() -> (VMContextBuilder, Contract) {
+  let (mut context, mut contract) = setup_contract();
+  register(&mut context, &mut contract, accounts(1).as_ref());
+  register(&mut context, &mut contract, accounts(2).as_ref());
+  mint(&mut context, &mut contract, accounts(1).as_ref(), 1000);
+
+  (context, contract)
+}
+
+#[test]
+fn proper_transfer() {
+  let (mut context, mut contract) = setup_holders();
+
+  testing_env!(context
+    .predecessor_account_id(accounts(1))
+    .attached_deposit(1)
+    .build());
+  contract.ft_transfer(accounts(2), U128(300), None);
+
+  assert_eq!(U128(700), contract.ft_balance_of(accounts(1)));
+  assert_eq!(U128(300), contract.ft_balance_of(accounts(2)));
+  assert_eq!(U128(1000), contract.ft_total_supply());
+}
+
+#[test]
+#[should_panic(expected = "The account danny is not registered")]
+fn transfer_to_unregistered_account() {
+  let (mut context, mut contract) = setup_holders();
+
+  testing_env!(context
+    .predecessor_account_id(accounts(1))
+    .attached_deposit(1)
+    .build());
+  contract.ft_transfer(accounts(3), U128(300), None);
+}
+
+#[test]
+#[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+fn transfer_requires_one_yocto() {
+  let (mut context, mut contract) = setup_holders();
+
+  testing_env!(context
+    .predecessor_account_id(accounts(1))
+    .attached_deposit(0)
+    .build());
+  contract.ft_transfer(accounts(2), U128(300), None);
+}
+
+#[test]
+fn refund_unused_transfer_call() {
+  let (mut context, mut contract) = setup_holders();
+
+  testing_env!(context
+    .predecessor_account_id(accounts(1))
+    .attached_deposit(1)
+    .prepaid_gas(10u64.pow(14))
+    .build());
+  contract.ft_transfer_call(accounts(2), U128(300), None, String::from(""));
+
+  // the receiver only used 100 of the transfer
+  testing_env!(
+    context
+      .predecessor_account_id(ValidAccountId::try_from("ausdt").unwrap())
+      .attached_deposit(0)
+      .build(),
+    VMConfig::default(),
+    RuntimeFeesConfig::default(),
+    Default::default(),
+    vec![PromiseResult::Successful(
+      near_sdk::serde_json::to_vec(&U128(200)).unwrap()
+    )]
+  );
+  let used_amount = contract.ft_resolve_transfer(accounts(1), accounts(2), U128(300));
+
+  assert_eq!(U128(100), used_amount);
+  assert_eq!(U128(900), contract.ft_balance_of(accounts(1)));
+  assert_eq!(U128(100), contract.ft_balance_of(accounts(2)));
+  assert_eq!(U128(1000), contract.ft_total_supply());
+}
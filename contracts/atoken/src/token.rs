@@ -0,0 +1,155 @@
+use crate::*;
+
+const GAS_FOR_RESOLVE_TRANSFER: Gas = 5_000_000_000_000;
+const GAS_FOR_FT_TRANSFER_CALL: Gas = 25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER;
+
+#[ext_contract(ext_fungible_token_receiver)]
+pub trait FungibleTokenReceiver {
+  fn ft_on_transfer(
+    &mut self,
+    sender_id: AccountId,
+    amount: U128,
+    msg: String,
+  ) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_self)]
+pub trait FungibleTokenResolverContract {
+  fn ft_resolve_transfer(
+    &mut self,
+    sender_id: AccountId,
+    receiver_id: AccountId,
+    amount: U128,
+  ) -> U128;
+}
+
+pub trait FungibleTokenCore {
+  fn ft_transfer(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>);
+
+  fn ft_transfer_call(
+    &mut self,
+    receiver_id: ValidAccountId,
+    amount: U128,
+    memo: Option<String>,
+    msg: String,
+  ) -> PromiseOrValue<U128>;
+
+  fn ft_total_supply(&self) -> U128;
+
+  fn ft_balance_of(&self, account_id: ValidAccountId) -> U128;
+}
+
+pub trait FungibleTokenResolver {
+  fn ft_resolve_transfer(
+    &mut self,
+    sender_id: ValidAccountId,
+    receiver_id: ValidAccountId,
+    amount: U128,
+  ) -> U128;
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+  #[payable]
+  fn ft_transfer(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>) {
+    assert_one_yocto();
+    let sender_id = env::predecessor_account_id();
+
+    self.internal_transfer(&sender_id, receiver_id.as_ref(), amount.0, memo);
+  }
+
+  #[payable]
+  fn ft_transfer_call(
+    &mut self,
+    receiver_id: ValidAccountId,
+    amount: U128,
+    memo: Option<String>,
+    msg: String,
+  ) -> PromiseOrValue<U128> {
+    assert_one_yocto();
+    let sender_id = env::predecessor_account_id();
+
+    self.internal_transfer(&sender_id, receiver_id.as_ref(), amount.0, memo);
+
+    ext_fungible_token_receiver::ft_on_transfer(
+      sender_id.clone(),
+      amount,
+      msg,
+      receiver_id.as_ref(),
+      NO_DEPOSIT,
+      env::prepaid_gas() - GAS_FOR_FT_TRANSFER_CALL,
+    )
+    .then(ext_self::ft_resolve_transfer(
+      sender_id,
+      receiver_id.into(),
+      amount,
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      GAS_FOR_RESOLVE_TRANSFER,
+    ))
+    .into()
+  }
+
+  fn ft_total_supply(&self) -> U128 {
+    self.total_supply.into()
+  }
+
+  fn ft_balance_of(&self, account_id: ValidAccountId) -> U128 {
+    self.internal_balance(account_id.as_ref()).into()
+  }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+  #[private]
+  fn ft_resolve_transfer(
+    &mut self,
+    sender_id: ValidAccountId,
+    receiver_id: ValidAccountId,
+    amount: U128,
+  ) -> U128 {
+    let sender_id: AccountId = sender_id.into();
+    let receiver_id: AccountId = receiver_id.into();
+    let amount: Balance = amount.0;
+
+    // Get the unused amount from the `ft_on_transfer` call result.
+    let unused_amount = match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Successful(value) => {
+        if let Ok(unused_amount) = near_sdk::serde_json::from_slice::<U128>(&value) {
+          std::cmp::min(amount, unused_amount.0)
+        } else {
+          amount
+        }
+      }
+      PromiseResult::Failed => amount,
+    };
+
+    if unused_amount > 0 {
+      let receiver_balance = self.internal_balance(&receiver_id);
+      if receiver_balance > 0 {
+        let refund_amount = std::cmp::min(receiver_balance, unused_amount);
+        self
+          .collection
+          .balance_map
+          .insert(&receiver_id, &(receiver_balance - refund_amount));
+
+        let sender_balance = self.internal_balance(&sender_id);
+        self
+          .collection
+          .balance_map
+          .insert(&sender_id, &(sender_balance + refund_amount));
+
+        env::log(
+          format!(
+            "Refund {} {} from {} to {}",
+            refund_amount, self.metadata.symbol, receiver_id, sender_id
+          )
+          .as_bytes(),
+        );
+        return (amount - refund_amount).into();
+      }
+    }
+    amount.into()
+  }
+}
@@ -0,0 +1,69 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+/// Layouts the contract state has been persisted with, kept under
+/// `STATE_VERSION_KEY`. The aToken was versioned from its first deployment.
+pub enum VersionedContract {
+  V1(Contract),
+}
+
+impl Contract {
+  pub(crate) fn internal_read_state_version() -> u8 {
+    env::storage_read(STATE_VERSION_KEY)
+      .map(|value| value[0])
+      .unwrap_or(0)
+  }
+
+  pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+    match Self::internal_read_state_version() {
+      CURRENT_STATE_VERSION => {
+        VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+      }
+      _ => env::panic("Unknown state version".as_bytes()),
+    }
+  }
+
+  pub(crate) fn internal_write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+  }
+}
+
+#[near_bindgen]
+impl Contract {
+  /// Converts the stored state into the current layout. Called by `upgrade`
+  /// right after the new code is deployed, or by the owner directly.
+  #[init(ignore_state)]
+  pub fn migrate() -> Self {
+    let VersionedContract::V1(contract) = Self::internal_read_versioned_state();
+
+    if env::predecessor_account_id() != env::current_account_id() {
+      contract.assert_owner();
+    }
+
+    Self::internal_write_state_version();
+
+    contract
+  }
+
+  /// Deploys the wasm blob attached as the call input and migrates the state.
+  pub fn upgrade(&self) -> Promise {
+    self.assert_owner();
+
+    let code = env::input().expect("Error: No code attached");
+    let migrate_gas = env::prepaid_gas()
+      .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+      .expect("Not enough gas attached to migrate after the upgrade");
+
+    Promise::new(env::current_account_id())
+      .deploy_contract(code)
+      .function_call(b"migrate".to_vec(), b"{}".to_vec(), NO_DEPOSIT, migrate_gas)
+  }
+
+  pub fn get_state_version(&self) -> u8 {
+    Self::internal_read_state_version()
+  }
+}
@@ -0,0 +1,151 @@
+//! NEP-145 storage management of the market, the overseer, the custodies and
+//! the aTokens.
+//!
+//! Every contract including this module keeps the storage balances in
+//! `collection.storage_balance_map` and defines
+//! - `STORAGE_BYTES_PER_ACCOUNT`, the storage paid by the registration
+//! - `MAX_STORAGE_BYTES_PER_ACCOUNT`, the most storage an account can pay for
+//! - `Contract::storage_bytes_of`, the storage the entries of an account use
+use crate::*;
+
+/// NEP-145 storage balance of an account
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Registers `account_id`, the caller by default, or tops up its storage
+    /// balance. Anything attached above the maximum storage balance is refunded,
+    /// and above the minimum one with `registration_only`
+    #[payable]
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        assert!(
+            env::is_valid_account_id(account_id.as_bytes()),
+            "The account ID is invalid"
+        );
+        let registration_only = registration_only.unwrap_or(false);
+        let bounds = self.storage_balance_bounds();
+        let max_balance = bounds.max.map_or(Balance::MAX, |max| max.0);
+
+        let (total, deposit) = match self.collection.storage_balance_map.get(&account_id) {
+            Some(total) => {
+                let deposit = if registration_only {
+                    0
+                } else {
+                    amount.min(max_balance.saturating_sub(total))
+                };
+                if deposit == 0 {
+                    env::log(b"The account is already registered, refunding the deposit");
+                }
+                (total + deposit, deposit)
+            }
+            None => {
+                assert!(
+                    amount >= bounds.min.0,
+                    "The attached deposit is less than the minimum storage balance"
+                );
+                let deposit = if registration_only {
+                    bounds.min.0
+                } else {
+                    amount.min(max_balance)
+                };
+                (deposit, deposit)
+            }
+        };
+        self.collection
+            .storage_balance_map
+            .insert(&account_id, &total);
+        let refund = amount - deposit;
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    /// Withdraws `amount` of the storage balance the entries of the caller do
+    /// not use, all of it by default
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        self.assert_registered(&account_id);
+
+        let storage_balance = self.storage_balance_of(account_id.clone()).unwrap();
+        let amount = amount.map_or(storage_balance.available.0, |amount| amount.0);
+        if amount > storage_balance.available.0 {
+            env::panic(b"The amount is greater than the available storage balance");
+        }
+        if amount > 0 {
+            self.collection
+                .storage_balance_map
+                .insert(&account_id, &(storage_balance.total.0 - amount));
+            Promise::new(account_id.clone()).transfer(amount);
+        }
+
+        self.storage_balance_of(account_id).unwrap()
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128::from(STORAGE_BYTES_PER_ACCOUNT as Balance * env::storage_byte_cost()),
+            max: MAX_STORAGE_BYTES_PER_ACCOUNT
+                .map(|max| U128::from(max as Balance * env::storage_byte_cost())),
+        }
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        let used = self.storage_bytes_of(&account_id) as Balance * env::storage_byte_cost();
+        self.collection
+            .storage_balance_map
+            .get(&account_id)
+            .map(|total| StorageBalance {
+                total: U128::from(total),
+                available: U128::from(total.saturating_sub(used)),
+            })
+    }
+}
+
+impl Contract {
+    pub(crate) fn is_registered(&self, account_id: &AccountId) -> bool {
+        self.collection.storage_balance_map.contains_key(account_id)
+    }
+
+    pub(crate) fn assert_registered(&self, account_id: &AccountId) {
+        if !self.is_registered(account_id) {
+            env::panic(format!("The account {} is not registered", account_id).as_bytes());
+        }
+    }
+
+    /// Asserts the storage balance of a registered account covers its entries
+    pub(crate) fn assert_storage_covered(&self, account_id: &AccountId) {
+        self.assert_registered(account_id);
+        let total = self.collection.storage_balance_map.get(account_id).unwrap();
+        if total < self.storage_bytes_of(account_id) as Balance * env::storage_byte_cost() {
+            env::panic(
+                format!(
+                    "The storage balance of {} does not cover its entries",
+                    account_id
+                )
+                .as_bytes(),
+            );
+        }
+    }
+}
@@ -0,0 +1,2 @@
+cargo build --target wasm32-unknown-unknown --release
+copy target\wasm32-unknown-unknown\release\*.wasm res
\ No newline at end of file
@@ -2,19 +2,33 @@
 ./build.sh
 near deploy \
     --accountId custody_bnear.synchro.testnet \
-    --wasmFile res/custody_bnear.wasm \
+    --wasmFile res/custody.wasm \
     --initFunction new \
     --initArgs '{
         "owner_id": "blockwave.testnet",
         "overseer_contract": "overseer.synchro.testnet",
         "collateral_token": "bnear.synchro.testnet",
         "market_contract": "market.synchro.testnet",
-        "reward_contract": "reward.synchro.testnet",
         "liquidation_contract": "liquidation.synchro.testnet",
         "stable_coin_contract": "stable_coin.testnet",
         "basset_info": {
             "name": "bnear",
             "symbol": "bNear",
             "decimals": 8
+        },
+        "reward_source": {
+            "reward_contract": {
+                "reward_contract": "reward.synchro.testnet",
+                "reward_token": "wrap.testnet"
+            }
+        },
+        "dex": {
+            "dex_contract": "dex.synchro.testnet",
+            "max_slippage": {
+                "num": "1000000",
+                "decimal": 100000000
+            },
+            "reward_decimals": 24,
+            "stable_coin_decimals": 6
         }
     }'
@@ -0,0 +1,239 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+  // Executor: bAsset token contract
+  pub(crate) fn deposit_collateral(&mut self, borrower: AccountId, amount: Balance) {
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+
+    borrower_info.balance += amount;
+    borrower_info.spendable += amount;
+
+    self.add_borrower_info_map(&borrower, &borrower_info);
+    self.state.total_balance += amount;
+  }
+
+  // Executor: borrower
+  /// Wraps the attached NEAR into the collateral through `near_deposit` of the
+  /// wNEAR contract, it is credited once wrapped
+  #[payable]
+  pub fn deposit_native(&mut self) {
+    let wnear_contract = self.assert_native();
+    let borrower = env::predecessor_account_id();
+    let amount = env::attached_deposit();
+    assert!(amount > 0, "Deposit amount must be greater than 0");
+    // the entry is created once wrapped
+    self.assert_borrower_storage(&borrower);
+
+    ext_wnear::near_deposit(&wnear_contract, amount, SINGLE_CALL_GAS).then(
+      ext_self::callback_deposit_native(
+        borrower,
+        U128::from(amount),
+        &env::current_account_id(),
+        NO_DEPOSIT,
+        SINGLE_CALL_GAS,
+      ),
+    );
+  }
+
+  // Executor: borrwer
+  /// Withdraws the collateral token, or NEAR unwrapped through `near_withdraw`
+  /// of the wNEAR contract when `unwrap` is set
+  pub fn withdraw_collateral(&mut self, amount: Option<Balance>, unwrap: Option<bool>) {
+    let wnear_contract = if unwrap.unwrap_or(false) {
+      Some(self.assert_native())
+    } else {
+      None
+    };
+    let borrower = env::predecessor_account_id();
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+
+    let amount = amount.unwrap_or(borrower_info.spendable);
+    if borrower_info.spendable < amount {
+      env::panic(
+        ("Withdraw Amount Exceeds Spendable: ".to_string() + &borrower_info.spendable.to_string())
+          .as_bytes(),
+      );
+    }
+
+    borrower_info.balance = borrower_info.balance - amount;
+    borrower_info.spendable = borrower_info.spendable - amount;
+
+    self.add_borrower_info_map(&borrower, &borrower_info);
+    self.state.total_balance -= amount;
+
+    if let Some(wnear_contract) = wnear_contract {
+      ext_wnear::near_withdraw(
+        U128::from(amount),
+        &wnear_contract,
+        ONE_YOCTO,
+        SINGLE_CALL_GAS,
+      )
+      .then(ext_self::callback_withdraw_native(
+        borrower,
+        U128::from(amount),
+        &env::current_account_id(),
+        NO_DEPOSIT,
+        SINGLE_CALL_GAS,
+      ));
+      return;
+    }
+
+    fungible_token::ft_transfer(
+      borrower.clone(),
+      U128::from(amount),
+      None,
+      &self.config.collateral_token,
+      ONE_YOCTO,
+      SINGLE_CALL_GAS,
+    )
+    .then(ext_self::callback_withdraw_collateral(
+      borrower,
+      U128::from(amount),
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      SINGLE_CALL_GAS,
+    ));
+  }
+
+  // Executor: overseer
+  pub fn lock_collateral(&mut self, borrower: AccountId, amount: Balance) {
+    self.assert_overseer();
+
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+
+    if amount > borrower_info.spendable {
+      env::panic(
+        ("Lock Amount Exceeds Spendable: ".to_string() + &borrower_info.spendable.to_string())
+          .as_bytes(),
+      );
+    }
+
+    borrower_info.spendable = borrower_info.spendable - amount;
+
+    self.add_borrower_info_map(&borrower, &borrower_info);
+  }
+
+  pub fn unlock_collateral(&mut self, borrower: AccountId, amount: Balance) {
+    self.assert_overseer();
+
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+    let borrrowed_amount = borrower_info.balance - borrower_info.spendable;
+
+    if amount > borrrowed_amount {
+      env::panic(
+        ("Unlcok Amount Exceeds Locked: ".to_string() + &borrrowed_amount.to_string()).as_bytes(),
+      );
+    }
+
+    borrower_info.spendable += amount;
+    self.add_borrower_info_map(&borrower, &borrower_info);
+  }
+
+  // Executer: overseer
+  pub fn liquidate_collateral(
+    &mut self,
+    liquidator: AccountId,
+    borrower: AccountId,
+    amount: Balance,
+  ) {
+    self.assert_overseer();
+
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+    let borrrowed_amount = borrower_info.balance - borrower_info.spendable;
+
+    if amount > borrrowed_amount {
+      env::panic(
+        ("Liquidation Amount Exceeds Locked: ".to_string() + &borrrowed_amount.to_string())
+          .as_bytes(),
+      );
+    }
+
+    borrower_info.balance = borrower_info.balance - amount;
+    self.add_borrower_info_map(&borrower, &borrower_info);
+    self.state.total_balance -= amount;
+
+    // the proceeds repay the loan of the borrower in the market, what the loan
+    // does not use goes back to the borrower and the bid fee goes to the interest
+    // buffer of the overseer
+    let repay_msg = serde_json::json!({
+      "repay_stable": {
+        "borrower": borrower,
+      },
+    })
+    .to_string();
+    let msg = serde_json::json!({
+      "liquidator": liquidator,
+      "repay_address": self.config.market_contract,
+      "fee_address": self.config.overseer_contract,
+      "repay_msg": repay_msg,
+      "refund_address": borrower,
+    })
+    .to_string();
+    let transfer_gas = env::prepaid_gas()
+      .checked_sub(env::used_gas() + GAS_FOR_LIQUIDATION_RESERVE)
+      .expect("Not enough gas to liquidate the collateral");
+
+    fungible_token::ft_transfer_call(
+      self.config.liquidation_contract.clone(),
+      U128::from(amount),
+      None,
+      msg,
+      &self.config.collateral_token,
+      ONE_YOCTO,
+      transfer_gas,
+    )
+    .then(ext_self::callback_liquidate_collateral(
+      borrower,
+      U128::from(amount),
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      GAS_FOR_LIQUIDATION_CALLBACK,
+    ));
+  }
+}
+
+impl Contract {
+  /// Credits back collateral that left the custody's accounting but not the
+  /// custody, it is spendable since the overseer no longer counts it as locked
+  pub(crate) fn internal_restore_collateral(&mut self, borrower: AccountId, amount: Balance) {
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+
+    borrower_info.balance += amount;
+    borrower_info.spendable += amount;
+
+    self.add_borrower_info_map(&borrower, &borrower_info);
+    self.state.total_balance += amount;
+
+    self.internal_log_event(
+      "restore_collateral",
+      serde_json::json!({
+        "borrower": borrower,
+        "amount": U128::from(amount),
+      }),
+    );
+  }
+
+  /// Credits back collateral a liquidation did not sell, it stays locked since
+  /// the overseer counts it again
+  pub(crate) fn internal_restore_locked_collateral(
+    &mut self,
+    borrower: AccountId,
+    amount: Balance,
+  ) {
+    let mut borrower_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
+
+    borrower_info.balance += amount;
+
+    self.add_borrower_info_map(&borrower, &borrower_info);
+    self.state.total_balance += amount;
+
+    self.internal_log_event(
+      "restore_locked_collateral",
+      serde_json::json!({
+        "borrower": borrower,
+        "amount": U128::from(amount),
+      }),
+    );
+  }
+}
@@ -0,0 +1,112 @@
+use crate::*;
+
+/// `msg` of the transfer of rewards to the DEX. Swaps are made with
+/// `ft_transfer_call` of the token in to the DEX, which sends the token out to
+/// the sender and refunds the token in when it cannot pay `min_amount_out`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapMsg {
+  pub token_out: AccountId,
+  pub min_amount_out: U128,
+}
+
+// This value is used as the minimum reward claim amount
+// thus if a user's reward is less than 1 ust do not send the ClaimRewards msg
+const REWARDS_THRESHOLD: Balance = 1_000_000u128;
+
+#[near_bindgen]
+impl Contract {
+  // Executor: overseer
+  /// Every step of the distribution forwards the gas it does not use, the overseer
+  /// attaches at least `GAS_FOR_DISTRIBUTE_REWARDS`
+  pub fn distribute_rewards(&self) {
+    self.assert_overseer();
+    assert!(
+      env::prepaid_gas() >= GAS_FOR_DISTRIBUTE_REWARDS,
+      "Not enough gas to distribute the rewards"
+    );
+
+    match &self.config.reward_source {
+      RewardSource::None => {}
+      RewardSource::RewardContract {
+        reward_contract, ..
+      } => {
+        ext_reward::get_account_stake_rewards(
+          env::current_account_id(),
+          reward_contract,
+          NO_DEPOSIT,
+          GAS_FOR_VIEW,
+        )
+        .then(ext_self::callback_distribute_rewards(
+          REWARDS_THRESHOLD,
+          &env::current_account_id(),
+          NO_DEPOSIT,
+          Self::reward_callback_gas(GAS_FOR_VIEW),
+        ));
+      }
+      // the rewards are paid in the collateral itself, a claim is worth at least one token
+      RewardSource::Rebasing => {
+        fungible_token::ft_balance_of(
+          env::current_account_id(),
+          &self.config.collateral_token,
+          NO_DEPOSIT,
+          GAS_FOR_VIEW,
+        )
+        .then(ext_self::callback_distribute_rewards(
+          self.config.basset_info.unit(),
+          &env::current_account_id(),
+          NO_DEPOSIT,
+          Self::reward_callback_gas(GAS_FOR_VIEW),
+        ));
+      }
+    }
+  }
+
+  // Executor: itself
+  #[private]
+  pub fn distribute_hook(&self) {
+    fungible_token::ft_balance_of(
+      env::current_account_id(),
+      &self.config.stable_coin_contract,
+      NO_DEPOSIT,
+      GAS_FOR_VIEW,
+    )
+    .then(ext_self::callback_distribute_hook(
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      Self::reward_callback_gas(GAS_FOR_VIEW),
+    ));
+  }
+
+  // Executor: itself
+  /// Swaps rewards held by the custody to the stable coin, at most `max_slippage`
+  /// below their value at the oracle price, then sends the stable coin to the overseer
+  #[private]
+  pub fn swap_to_stable_denom(&self, token_in: AccountId, amount_in: U128) {
+    if token_in == self.config.stable_coin_contract {
+      self.distribute_hook();
+      return;
+    }
+
+    if self.config.dex.is_none() {
+      env::log(format!("No DEX to swap {} to the stable coin", token_in).as_bytes());
+      return;
+    }
+
+    // a quote of the DEX could be moved by the pool the rewards are swapped in,
+    // the least accepted is bound to the price the overseer reads from the oracle
+    ext_overseer::get_price_status(
+      token_in.clone(),
+      &self.config.overseer_contract,
+      NO_DEPOSIT,
+      GAS_FOR_VIEW,
+    )
+    .then(ext_self::callback_get_price_status(
+      token_in,
+      amount_in,
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      Self::reward_callback_gas(GAS_FOR_VIEW),
+    ));
+  }
+}
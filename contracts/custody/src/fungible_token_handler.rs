@@ -0,0 +1,80 @@
+use crate::*;
+
+/// What to do with a collateral deposit besides crediting it. An empty msg only credits it
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollateralReceiverMsg {
+    #[serde(default)]
+    pub lock: bool,
+    // borrowed once the deposit is locked
+    pub borrow: Option<U128>,
+    // market reserve to borrow from, the stable coin of the custody by default
+    pub stable_asset: Option<AccountId>,
+}
+
+pub trait FungibleTokenReceiver {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+}
+
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    fn ft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        amount: U128,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        if env::predecessor_account_id() == self.config.collateral_token {
+            let payload: CollateralReceiverMsg = if msg.is_empty() {
+                CollateralReceiverMsg::default()
+            } else {
+                near_sdk::serde_json::from_str(&msg)
+                    .expect("Failed to parse the payload, invalid `msg` format")
+            };
+            assert!(
+                payload.lock || payload.borrow.is_none(),
+                "Only a locked deposit can be borrowed against"
+            );
+
+            self.deposit_collateral(sender_id.clone(), amount.0);
+
+            // the deposit stays spendable if the overseer fails to lock it
+            if payload.lock {
+                let borrow = payload.borrow.map(|borrow_amount| {
+                    let stable_asset = payload
+                        .stable_asset
+                        .unwrap_or_else(|| self.config.stable_coin_contract.clone());
+                    (stable_asset, borrow_amount)
+                });
+                let lock_gas = if borrow.is_some() {
+                    GAS_FOR_OVERSEER_LOCK_AND_BORROW
+                } else {
+                    GAS_FOR_OVERSEER_LOCK
+                };
+                assert!(
+                    env::prepaid_gas() >= GAS_FOR_DEPOSIT_STEP + lock_gas,
+                    "Not enough gas to lock the deposit"
+                );
+                ext_overseer::lock_collateral_from_custody(
+                    sender_id,
+                    self.config.collateral_token.clone(),
+                    amount,
+                    borrow,
+                    &self.config.overseer_contract,
+                    NO_DEPOSIT,
+                    lock_gas,
+                );
+            }
+            return PromiseOrValue::Value(U128(0));
+        } else {
+            env::log(b"Only whitelisted tokens can transfer_call to this");
+
+            return PromiseOrValue::Value(amount);
+        }
+    }
+}
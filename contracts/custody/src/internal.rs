@@ -0,0 +1,98 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+  pub(crate) fn assert_owner(&self) {
+    assert_eq!(
+      env::predecessor_account_id(),
+      self.config.owner_id,
+      "Can only be called by the owner"
+    );
+  }
+
+  pub(crate) fn assert_overseer(&self) {
+    assert_eq!(
+      env::predecessor_account_id(),
+      self.config.overseer_contract,
+      "Can only be called by the overseer"
+    );
+  }
+
+  /// the registration pays for every entry of an account
+  pub(crate) fn storage_bytes_of(&self, _account_id: &AccountId) -> StorageUsage {
+    STORAGE_BYTES_PER_ACCOUNT
+  }
+
+  pub(crate) fn assert_valid_basset_info(basset_info: &BAssetInfo) {
+    assert!(basset_info.decimals <= 24, "Decimals must not exceed 24");
+  }
+
+  pub(crate) fn assert_valid_dex(dex: &DexConfig) {
+    assert!(
+      dex.max_slippage <= D128::one(),
+      "The max slippage must not exceed 1"
+    );
+    assert!(
+      dex.reward_decimals <= 24 && dex.stable_coin_decimals <= 24,
+      "Decimals must not exceed 24"
+    );
+  }
+
+  pub(crate) fn assert_valid_wnear_contract(
+    wnear_contract: &AccountId,
+    collateral_token: &AccountId,
+  ) {
+    assert_eq!(
+      wnear_contract, collateral_token,
+      "NEAR can only be wrapped into the collateral token"
+    );
+  }
+
+  /// The wNEAR contract, for a custody that wraps NEAR
+  pub(crate) fn assert_native(&self) -> AccountId {
+    self
+      .config
+      .wnear_contract
+      .clone()
+      .unwrap_or_else(|| env::panic(b"The collateral cannot be deposited or withdrawn as NEAR"))
+  }
+
+  /// Rewards are swapped at most `max_slippage` below their price at the overseer,
+  /// the reward source is dropped if the overseer has no price of its token
+  pub(crate) fn internal_check_reward_token(&self) {
+    if let RewardSource::RewardContract { reward_token, .. } = &self.config.reward_source {
+      if reward_token != &self.config.stable_coin_contract {
+        ext_overseer::get_price_status(
+          reward_token.clone(),
+          &self.config.overseer_contract,
+          NO_DEPOSIT,
+          GAS_FOR_VIEW,
+        )
+        .then(ext_self::callback_check_reward_token(
+          reward_token.clone(),
+          &env::current_account_id(),
+          NO_DEPOSIT,
+          GAS_FOR_REWARD_STEP,
+        ));
+      }
+    }
+  }
+
+  /// gas forwarded to the callback of a step of the reward distribution, once the
+  /// step attached `attached_gas` to its call and kept its own receipts
+  pub(crate) fn reward_callback_gas(attached_gas: Gas) -> Gas {
+    env::prepaid_gas()
+      .checked_sub(env::used_gas() + attached_gas + GAS_FOR_REWARD_STEP)
+      .expect("Not enough gas to distribute the rewards")
+  }
+
+  pub(crate) fn internal_log_event(&self, event: &str, data: serde_json::Value) {
+    let event = serde_json::json!({
+      "standard": "custody",
+      "version": "1.0.0",
+      "event": event,
+      "data": [data],
+    });
+    env::log(format!("EVENT_JSON:{}", event).as_bytes());
+  }
+}
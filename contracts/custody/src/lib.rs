@@ -0,0 +1,166 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, near_bindgen, serde_json, AccountId, Balance,
+    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, StorageUsage,
+};
+
+use uint::construct_uint;
+
+use crate::math::{value_of, D128};
+use crate::state::{
+    BAssetInfo, BorrowerInfo, Collection, Config, DexConfig, PriceStatus, RewardSource, State,
+};
+use crate::utils::{ext_overseer, ext_reward, ext_self, ext_wnear, fungible_token};
+
+mod collateral;
+mod distribution;
+mod fungible_token_handler;
+mod internal;
+mod math;
+mod owner;
+mod state;
+#[path = "../../common/storage.rs"]
+mod storage;
+#[cfg(test)]
+mod testing;
+mod upgrade;
+mod utils;
+mod view;
+
+const NO_DEPOSIT: Balance = 0;
+
+const ONE_YOCTO: Balance = 1;
+
+const SINGLE_CALL_GAS: Gas = 100_000_000_000_000;
+
+// the collateral a liquidation did not sell locked again in the overseer
+const GAS_FOR_OVERSEER_RESTORE: Gas = 10_000_000_000_000;
+
+// the callback of a liquidation, it gives back what the liquidation contract refused
+const GAS_FOR_LIQUIDATION_CALLBACK: Gas = 10_000_000_000_000 + GAS_FOR_OVERSEER_RESTORE;
+
+// kept by a liquidation for its receipts and callback, the sale gets the rest of the gas
+const GAS_FOR_LIQUIDATION_RESERVE: Gas = GAS_FOR_LIQUIDATION_CALLBACK + 20_000_000_000_000;
+
+// a balance, reward or price read from another contract
+const GAS_FOR_VIEW: Gas = 10_000_000_000_000;
+
+// the reward contract sends the claimed rewards
+const GAS_FOR_CLAIM_REWARDS: Gas = 30_000_000_000_000;
+
+// the DEX swaps the rewards and sends the stable coin out
+const GAS_FOR_SWAP: Gas = 50_000_000_000_000;
+
+// the stable coin sent to the overseer
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+
+// kept by each step of a reward distribution for its receipts, the rest is forwarded down the chain
+const GAS_FOR_REWARD_STEP: Gas = 20_000_000_000_000;
+
+// least gas of a reward distribution: six steps with three views, the claim, the swap
+// and the transfer to the overseer
+const GAS_FOR_DISTRIBUTE_REWARDS: Gas = 6 * GAS_FOR_REWARD_STEP
+    + 3 * GAS_FOR_VIEW
+    + GAS_FOR_CLAIM_REWARDS
+    + GAS_FOR_SWAP
+    + GAS_FOR_FT_TRANSFER;
+
+// kept by a deposit locked through the overseer for its receipts
+const GAS_FOR_DEPOSIT_STEP: Gas = 20_000_000_000_000;
+
+// the overseer locks a deposit: its step, the lock by the custody and the callback step
+const GAS_FOR_OVERSEER_LOCK: Gas = 60_000_000_000_000;
+
+// least gas of the market to borrow against a deposit just locked
+const GAS_FOR_MARKET_BORROW: Gas = 120_000_000_000_000;
+
+// the overseer locks a deposit, then the market borrows against it
+const GAS_FOR_OVERSEER_LOCK_AND_BORROW: Gas = GAS_FOR_OVERSEER_LOCK + GAS_FOR_MARKET_BORROW;
+
+// a registration and the collateral balances of a borrower
+const STORAGE_BYTES_PER_ACCOUNT: StorageUsage = 300;
+// entries of an account do not grow with its activity
+const MAX_STORAGE_BYTES_PER_ACCOUNT: Option<StorageUsage> = Some(STORAGE_BYTES_PER_ACCOUNT);
+
+construct_uint! {
+    /// 256-bit unsigned integer.
+    pub struct U256(4);
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+pub(crate) enum StorageKey {
+    BorrowerInfo,
+    StorageBalance,
+}
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    config: Config,
+    state: State,
+    collection: Collection,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(
+        owner_id: AccountId,
+        overseer_contract: AccountId,
+        collateral_token: AccountId,
+        market_contract: AccountId,
+        liquidation_contract: AccountId,
+        stable_coin_contract: AccountId,
+        basset_info: BAssetInfo,
+        reward_source: RewardSource,
+        dex: Option<DexConfig>,
+        wnear_contract: Option<AccountId>,
+    ) -> Self {
+        assert!(!env::state_exists(), "The contract is already initialized");
+        assert!(
+            env::is_valid_account_id(owner_id.as_bytes()),
+            "The owner account ID is invalid"
+        );
+        Self::assert_valid_basset_info(&basset_info);
+        if let Some(dex) = dex.as_ref() {
+            Self::assert_valid_dex(dex);
+        }
+        if let Some(wnear_contract) = wnear_contract.as_ref() {
+            Self::assert_valid_wnear_contract(wnear_contract, &collateral_token);
+        }
+
+        let config = Config {
+            owner_id,
+            overseer_contract,
+            collateral_token,
+            market_contract,
+            liquidation_contract,
+            stable_coin_contract,
+            basset_info,
+            reward_source,
+            dex,
+            wnear_contract,
+        };
+
+        let state = State { total_balance: 0 };
+
+        let collection = Collection {
+            borrower_info_map: LookupMap::new(StorageKey::BorrowerInfo),
+            storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
+        };
+
+        Self::internal_write_state_version();
+
+        let contract = Self {
+            config,
+            state,
+            collection,
+        };
+        contract.internal_check_reward_token();
+
+        contract
+    }
+}
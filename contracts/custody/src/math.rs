@@ -73,6 +73,16 @@ impl D128 {
     }
 }
 
+/// Value of `amount` of a token with `decimals` decimals at `price` per whole
+/// token, in the smallest unit of a token with `value_decimals` decimals
+pub fn value_of(amount: u128, decimals: u8, price: D128, value_decimals: u8) -> D128 {
+    D128::new(
+        (U256::from(amount) * U256::from(price.num.0) * U256::exp10(value_decimals as usize)
+            / U256::exp10(decimals as usize))
+        .as_u128(),
+    )
+}
+
 impl Add<D128> for D128 {
     type Output = Self;
     #[inline]
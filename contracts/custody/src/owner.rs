@@ -8,10 +8,12 @@ impl Contract {
     overseer_contract: Option<AccountId>,
     collateral_token: Option<AccountId>,
     market_contract: Option<AccountId>,
-    reward_contract: Option<AccountId>,
     liquidation_contract: Option<AccountId>,
     stable_coin_contract: Option<AccountId>,
     basset_info: Option<BAssetInfo>,
+    reward_source: Option<RewardSource>,
+    dex: Option<DexConfig>,
+    wnear_contract: Option<AccountId>,
   ) {
     self.assert_owner();
     assert_one_yocto();
@@ -24,9 +26,6 @@ impl Contract {
     if let Some(market_contract) = market_contract {
       self.config.market_contract = market_contract;
     }
-    if let Some(reward_contract) = reward_contract {
-      self.config.reward_contract = reward_contract;
-    }
     if let Some(liquidation_contract) = liquidation_contract {
       self.config.liquidation_contract = liquidation_contract;
     }
@@ -34,7 +33,28 @@ impl Contract {
       self.config.stable_coin_contract = stable_coin_contract;
     }
     if let Some(basset_info) = basset_info {
+      Self::assert_valid_basset_info(&basset_info);
       self.config.basset_info = basset_info;
     }
+    if let Some(reward_source) = reward_source {
+      // the balance held for the borrowers is only tracked from the deployment on
+      assert!(
+        reward_source != RewardSource::Rebasing
+          || self.config.reward_source == RewardSource::Rebasing,
+        "A custody can only be rebasing from its deployment"
+      );
+      self.config.reward_source = reward_source;
+      self.internal_check_reward_token();
+    }
+    if let Some(dex) = dex {
+      Self::assert_valid_dex(&dex);
+      self.config.dex = Some(dex);
+    }
+    if let Some(wnear_contract) = wnear_contract {
+      self.config.wnear_contract = Some(wnear_contract);
+    }
+    if let Some(wnear_contract) = self.config.wnear_contract.as_ref() {
+      Self::assert_valid_wnear_contract(wnear_contract, &self.config.collateral_token);
+    }
   }
 }
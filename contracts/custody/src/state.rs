@@ -0,0 +1,127 @@
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Config {
+  pub owner_id: AccountId,
+  pub overseer_contract: AccountId,
+  pub collateral_token: AccountId,
+  pub market_contract: AccountId,
+  pub liquidation_contract: AccountId,
+  pub stable_coin_contract: AccountId,
+  pub basset_info: BAssetInfo,
+  pub reward_source: RewardSource,
+  // rewards other than the stable coin are kept until it is set
+  pub dex: Option<DexConfig>,
+  // wNEAR contract the NEAR of native deposits is wrapped through, the collateral must be its token
+  pub wnear_contract: Option<AccountId>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub struct State {
+  // collateral held for the borrowers, the balance of a rebasing collateral above it is reward
+  pub total_balance: Balance,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Collection {
+  pub borrower_info_map: LookupMap<AccountId, BorrowerInfo>,
+  // NEP-145 storage balance of the registered accounts
+  pub storage_balance_map: LookupMap<AccountId, Balance>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BAssetInfo {
+  pub name: String,
+  pub symbol: String,
+  pub decimals: u8,
+}
+
+impl BAssetInfo {
+  /// one whole token, in the smallest unit of the collateral
+  pub fn unit(&self) -> Balance {
+    10u128.pow(self.decimals as u32)
+  }
+}
+
+/// Where the staking rewards of the collateral come from
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum RewardSource {
+  // no rewards, or rewards accrued in the price of the collateral
+  None,
+  // rewards paid in `reward_token` by a bAsset reward contract to the holders of the collateral
+  RewardContract {
+    reward_contract: AccountId,
+    reward_token: AccountId,
+  },
+  // rewards minted to the holders as a growing balance
+  Rebasing,
+}
+
+/// DEX the rewards are swapped to the stable coin through
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DexConfig {
+  pub dex_contract: AccountId,
+  // largest shortfall of a swap from the value of the rewards at the oracle price
+  pub max_slippage: D128,
+  // the oracle prices whole tokens, the rewards of a reward contract and the stable
+  // coin are scaled by their decimals
+  pub reward_decimals: u8,
+  pub stable_coin_decimals: u8,
+}
+
+/// Price of a token in the stable coin, as reported by `get_price_status` of the overseer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceStatus {
+  pub price: D128,
+  pub last_updated_at: u64,
+  // unit: seconds
+  pub age: u64,
+  pub is_fresh: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BorrowerInfo {
+  pub balance: Balance,
+  pub spendable: Balance,
+}
+
+#[near_bindgen]
+impl Contract {
+  pub fn get_borrower_info_map(&self, key: &String) -> BorrowerInfo {
+    match self.collection.borrower_info_map.get(&key) {
+      Some(value) => {
+        let log_message = format!("Value from LookupMap is {:?}", value.clone());
+        env::log(log_message.as_bytes());
+        value
+      }
+      // created on the first deposit
+      None => BorrowerInfo {
+        balance: 0,
+        spendable: 0,
+      },
+    }
+  }
+}
+
+impl Contract {
+  pub(crate) fn add_borrower_info_map(&mut self, key: &String, value: &BorrowerInfo) {
+    self.assert_borrower_storage(key);
+    self.collection.borrower_info_map.insert(&key, value);
+  }
+
+  /// The storage of a new entry is paid by the registration, entries created
+  /// before the registrations are kept without one
+  pub(crate) fn assert_borrower_storage(&self, borrower: &AccountId) {
+    if !self.collection.borrower_info_map.contains_key(borrower) {
+      self.assert_storage_covered(borrower);
+    }
+  }
+}
@@ -0,0 +1,1236 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain, RuntimeFeesConfig, VMConfig};
+
+use crate::fungible_token_handler::FungibleTokenReceiver;
+use crate::upgrade::{CollectionV0, ConfigV0, ContractV0, StateV0};
+use crate::*;
+
+fn setup_contract() -> (VMContextBuilder, Contract) {
+  let mut context = VMContextBuilder::new();
+  testing_env!(context
+    .predecessor_account_id(accounts(0))
+    .attached_deposit(1)
+    .build());
+  let contract = Contract::new(
+    AccountId::from("owner"),
+    AccountId::from("overseer"),
+    AccountId::from("collateral"),
+    AccountId::from("market"),
+    AccountId::from("liquidation"),
+    AccountId::from("stable_coin"),
+    BAssetInfo {
+      name: String::from("name"),
+      symbol: String::from("symbol"),
+      decimals: 8,
+    },
+    RewardSource::RewardContract {
+      reward_contract: AccountId::from("reward"),
+      reward_token: AccountId::from("reward_token"),
+    },
+    Some(DexConfig {
+      dex_contract: AccountId::from("dex"),
+      max_slippage: D128::new_exp(1, -2),
+      reward_decimals: 6,
+      stable_coin_decimals: 6,
+    }),
+    None,
+  );
+  (context, contract)
+}
+
+fn callback_context(context: &mut VMContextBuilder, results: Vec<PromiseResult>) {
+  testing_env!(
+    context
+      .current_account_id(ValidAccountId::try_from("custody").unwrap())
+      .predecessor_account_id(ValidAccountId::try_from("custody").unwrap())
+      .build(),
+    VMConfig::default(),
+    RuntimeFeesConfig::default(),
+    Default::default(),
+    results
+  );
+}
+
+fn amount_result(amount: u128) -> PromiseResult {
+  PromiseResult::Successful(serde_json::to_vec(&U128::from(amount)).unwrap())
+}
+
+/// events logged by the contract
+fn events() -> Vec<serde_json::Value> {
+  get_logs()
+    .iter()
+    .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+    .map(|event| serde_json::from_str(event).unwrap())
+    .collect()
+}
+
+fn price_result(price: D128, is_fresh: bool) -> PromiseResult {
+  PromiseResult::Successful(
+    serde_json::to_vec(&PriceStatus {
+      price,
+      last_updated_at: 0,
+      age: 0,
+      is_fresh,
+    })
+    .unwrap(),
+  )
+}
+
+fn setup_rebasing_contract() -> (VMContextBuilder, Contract) {
+  let (context, mut contract) = setup_contract();
+  contract.config.reward_source = RewardSource::Rebasing;
+  (context, contract)
+}
+
+#[test]
+fn proper_migrate_from_v0() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 100,
+      spendable: 40,
+    },
+  );
+
+  env::state_write(&ContractV0 {
+    config: ConfigV0 {
+      owner_id: contract.config.owner_id,
+      overseer_contract: contract.config.overseer_contract,
+      collateral_token: contract.config.collateral_token,
+      market_contract: contract.config.market_contract,
+      reward_contract: AccountId::from("reward"),
+      liquidation_contract: contract.config.liquidation_contract,
+      stable_coin_contract: contract.config.stable_coin_contract,
+      basset_info: contract.config.basset_info,
+    },
+    state: StateV0 {},
+    collection: CollectionV0 {
+      borrower_info_map: contract.collection.borrower_info_map,
+    },
+  });
+  env::storage_remove(b"STATE_VERSION");
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .build());
+  let contract = Contract::migrate();
+
+  assert_eq!(1, contract.get_state_version());
+  assert_eq!(AccountId::from("owner"), contract.config.owner_id);
+  assert_eq!(
+    RewardSource::RewardContract {
+      reward_contract: AccountId::from("reward"),
+      reward_token: AccountId::from("stable_coin"),
+    },
+    contract.config.reward_source
+  );
+  assert_eq!(None, contract.config.dex);
+  assert_eq!(None, contract.config.wnear_contract);
+  assert_eq!(0, contract.get_state().total_balance);
+  assert_eq!(
+    BorrowerInfo {
+      balance: 100,
+      spendable: 40,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  // the borrowers of the old layout were never registered
+  assert_eq!(
+    None,
+    contract.storage_balance_of(AccountId::from("borrower"))
+  );
+}
+
+#[test]
+fn migrate_current_state() {
+  let (mut context, mut contract) = setup_rebasing_contract();
+  contract.state.total_balance = 70;
+  env::state_write(&contract);
+  env::storage_write(b"STATE_VERSION", &[1]);
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .build());
+  let contract = Contract::migrate();
+
+  assert_eq!(1, contract.get_state_version());
+  assert_eq!(RewardSource::Rebasing, contract.config.reward_source);
+  assert_eq!(70, contract.get_state().total_balance);
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the owner")]
+fn migrate_only_owner() {
+  let (mut context, contract) = setup_contract();
+  env::state_write(&contract);
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("someone").unwrap())
+    .build());
+  Contract::migrate();
+}
+
+#[test]
+fn liquidate_collateral_repays_market() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 100,
+      spendable: 0,
+    },
+  );
+  contract.state.total_balance = 100;
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .prepaid_gas(100_000_000_000_000)
+    .build());
+  contract.liquidate_collateral(
+    AccountId::from("liquidator"),
+    AccountId::from("borrower"),
+    40,
+  );
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 60,
+      spendable: 0,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("collateral", receipts[0]["receiver_id"]);
+  assert_eq!("ft_transfer_call", function_call["method_name"]);
+  assert_eq!(1, function_call["deposit"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  let msg: serde_json::Value = serde_json::from_str(args["msg"].as_str().unwrap()).unwrap();
+  assert_eq!("liquidation", args["receiver_id"]);
+  assert_eq!("market", msg["repay_address"]);
+  assert_eq!("overseer", msg["fee_address"]);
+  assert_eq!(
+    "{\"repay_stable\":{\"borrower\":\"borrower\"}}",
+    msg["repay_msg"]
+  );
+  assert_eq!("borrower", msg["refund_address"]);
+  assert_eq!(
+    "callback_liquidate_collateral",
+    receipts[1]["actions"][0]["FunctionCall"]["method_name"]
+  );
+
+  // the sale gets the prepaid gas left once the callback and the receipts are covered
+  let transfer_gas = function_call["gas"].as_u64().unwrap();
+  assert!(transfer_gas > 50_000_000_000_000 && transfer_gas <= 60_000_000_000_000);
+  assert_eq!(
+    20_000_000_000_000u64,
+    receipts[1]["actions"][0]["FunctionCall"]["gas"]
+  );
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to liquidate the collateral")]
+fn liquidate_collateral_without_gas() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 100,
+      spendable: 0,
+    },
+  );
+  contract.state.total_balance = 100;
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .prepaid_gas(25_000_000_000_000)
+    .build());
+  contract.liquidate_collateral(
+    AccountId::from("liquidator"),
+    AccountId::from("borrower"),
+    40,
+  );
+}
+
+#[test]
+#[should_panic(expected = "Liquidation Amount Exceeds Locked: 40")]
+fn liquidate_more_than_locked() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 100,
+      spendable: 60,
+    },
+  );
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .build());
+  contract.liquidate_collateral(
+    AccountId::from("liquidator"),
+    AccountId::from("borrower"),
+    50,
+  );
+}
+
+#[test]
+fn deposit_without_msg() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 0,
+      spendable: 0,
+    },
+  );
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .build());
+
+  contract.ft_on_transfer(AccountId::from("borrower"), U128::from(100), String::new());
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 100,
+      spendable: 100,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  assert!(get_created_receipts().is_empty());
+}
+
+#[test]
+fn deposit_lock_and_borrow() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 0,
+      spendable: 0,
+    },
+  );
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .build());
+
+  contract.ft_on_transfer(
+    AccountId::from("borrower"),
+    U128::from(100),
+    String::from("{\"lock\": true, \"borrow\": \"30\"}"),
+  );
+
+  // the overseer locks the deposit through the custody
+  assert_eq!(
+    100,
+    contract.get_borrower(AccountId::from("borrower")).spendable
+  );
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("overseer", receipts[0]["receiver_id"]);
+  assert_eq!("lock_collateral_from_custody", function_call["method_name"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  assert_eq!("borrower", args["borrower"]);
+  assert_eq!("collateral", args["collateral_token"]);
+  assert_eq!("100", args["amount"]);
+  assert_eq!(serde_json::json!(["stable_coin", "30"]), args["borrow"]);
+  // the overseer gets the gas of the lock and of the borrow by the market
+  assert_eq!(GAS_FOR_OVERSEER_LOCK_AND_BORROW, function_call["gas"]);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to lock the deposit")]
+fn deposit_lock_and_borrow_without_gas() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 0,
+      spendable: 0,
+    },
+  );
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .prepaid_gas(GAS_FOR_OVERSEER_LOCK_AND_BORROW)
+    .build());
+
+  contract.ft_on_transfer(
+    AccountId::from("borrower"),
+    U128::from(100),
+    String::from("{\"lock\": true, \"borrow\": \"30\"}"),
+  );
+}
+
+#[test]
+#[should_panic(expected = "Only a locked deposit can be borrowed against")]
+fn deposit_and_borrow_without_lock() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .build());
+
+  contract.ft_on_transfer(
+    AccountId::from("borrower"),
+    U128::from(100),
+    String::from("{\"borrow\": \"30\"}"),
+  );
+}
+
+#[test]
+fn deposit_and_withdraw_track_total_balance() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 0,
+      spendable: 0,
+    },
+  );
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .build());
+  contract.ft_on_transfer(AccountId::from("borrower"), U128::from(100), String::new());
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .build());
+  contract.withdraw_collateral(Some(30), None);
+
+  assert_eq!(70, contract.get_state().total_balance);
+}
+
+#[test]
+fn distribute_rewards_from_reward_contract() {
+  let (mut context, contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .build());
+
+  contract.distribute_rewards();
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("reward", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "get_account_stake_rewards",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+}
+
+#[test]
+fn distribute_rebasing_rewards() {
+  let (mut context, contract) = setup_rebasing_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .build());
+
+  contract.distribute_rewards();
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("collateral", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "ft_balance_of",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+
+  // claims start at one whole token of the collateral
+  let callback = &receipts[1]["actions"][0]["FunctionCall"];
+  let args: serde_json::Value = serde_json::from_str(callback["args"].as_str().unwrap()).unwrap();
+  assert_eq!("callback_distribute_rewards", callback["method_name"]);
+  assert_eq!(100_000_000u64, args["rewards_threshold"]);
+}
+
+#[test]
+fn distribute_without_reward_source() {
+  let (mut context, mut contract) = setup_contract();
+  contract.config.reward_source = RewardSource::None;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .build());
+
+  contract.distribute_rewards();
+
+  assert!(get_created_receipts().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "A custody can only be rebasing from its deployment")]
+fn update_config_to_rebasing() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .build());
+
+  contract.update_config(
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(RewardSource::Rebasing),
+    None,
+    None,
+  );
+}
+
+#[test]
+fn claim_accrued_rewards() {
+  let (mut context, mut contract) = setup_contract();
+  callback_context(&mut context, vec![amount_result(2_000_000)]);
+
+  contract.callback_distribute_rewards(1_000_000);
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("reward", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "claim_rewards",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+  assert_eq!(
+    "callback_claim_rewards",
+    receipts[1]["actions"][0]["FunctionCall"]["method_name"]
+  );
+}
+
+#[test]
+fn swap_claimed_rewards() {
+  let (mut context, contract) = setup_contract();
+  callback_context(&mut context, vec![amount_result(2_000_000)]);
+
+  contract.callback_claim_rewards(AccountId::from("reward_token"));
+
+  assert_eq!("claim_rewards", events()[0]["event"]);
+  assert_eq!("2000000", events()[0]["data"][0]["amount"]);
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("overseer", receipts[0]["receiver_id"]);
+  assert_eq!("get_price_status", function_call["method_name"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  assert_eq!("reward_token", args["asset"]);
+}
+
+#[test]
+fn swap_within_max_slippage() {
+  let (mut context, contract) = setup_contract();
+  callback_context(&mut context, vec![price_result(D128::new_exp(5, -1), true)]);
+
+  contract.callback_get_price_status(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("reward_token", receipts[0]["receiver_id"]);
+  assert_eq!("ft_transfer_call", function_call["method_name"]);
+  assert_eq!(1, function_call["deposit"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  let msg: serde_json::Value = serde_json::from_str(args["msg"].as_str().unwrap()).unwrap();
+  assert_eq!("dex", args["receiver_id"]);
+  assert_eq!("2000000", args["amount"]);
+  assert_eq!("stable_coin", msg["token_out"]);
+  // 2 reward tokens at 0.5, less 1%
+  assert_eq!("990000", msg["min_amount_out"]);
+}
+
+#[test]
+fn postpone_swap_on_stale_price() {
+  let (mut context, contract) = setup_contract();
+  callback_context(
+    &mut context,
+    vec![price_result(D128::new_exp(5, -1), false)],
+  );
+
+  contract.callback_get_price_status(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  assert!(get_created_receipts().is_empty());
+  assert_eq!(
+    vec!["The price of reward_token is stale, the swap is postponed"],
+    get_logs()
+  );
+}
+
+#[test]
+fn keep_rewards_without_price() {
+  let (mut context, contract) = setup_contract();
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+
+  contract.callback_get_price_status(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  assert!(get_created_receipts().is_empty());
+  assert_eq!(
+    vec!["No price to swap reward_token to the stable coin"],
+    get_logs()
+  );
+}
+
+#[test]
+fn distribute_swapped_rewards() {
+  let (mut context, contract) = setup_contract();
+  callback_context(&mut context, vec![amount_result(2_000_000)]);
+
+  contract.callback_swap_to_stable_denom(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  assert_eq!("swap_rewards", events()[0]["event"]);
+  assert_eq!("2000000", events()[0]["data"][0]["amount_swapped"]);
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("stable_coin", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "ft_balance_of",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+}
+
+#[test]
+fn keep_refunded_rewards() {
+  let (mut context, contract) = setup_contract();
+  callback_context(&mut context, vec![amount_result(0)]);
+
+  contract.callback_swap_to_stable_denom(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  assert_eq!("0", events()[0]["data"][0]["amount_swapped"]);
+  assert!(get_created_receipts().is_empty());
+}
+
+#[test]
+fn swap_rebasing_rewards() {
+  let (mut context, mut contract) = setup_rebasing_contract();
+  contract.state.total_balance = 500_000_000;
+  callback_context(&mut context, vec![amount_result(700_000_000)]);
+
+  contract.callback_distribute_rewards(100_000_000);
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("overseer", receipts[0]["receiver_id"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  assert_eq!("collateral", args["asset"]);
+
+  let function_call = &receipts[1]["actions"][0]["FunctionCall"];
+  assert_eq!("callback_get_price_status", function_call["method_name"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  assert_eq!("200000000", args["amount_in"]);
+}
+
+#[test]
+fn swap_rebasing_rewards_by_collateral_decimals() {
+  let (mut context, contract) = setup_rebasing_contract();
+  callback_context(&mut context, vec![price_result(D128::new_exp(2, 0), true)]);
+
+  contract.callback_get_price_status(AccountId::from("collateral"), U128::from(200_000_000));
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  let msg: serde_json::Value = serde_json::from_str(args["msg"].as_str().unwrap()).unwrap();
+  // 2 collateral tokens of 8 decimals at 2, less 1%
+  assert_eq!("3960000", msg["min_amount_out"]);
+}
+
+/// gas of the callback a step of the reward distribution scheduled
+fn callback_gas() -> Gas {
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  receipts[1]["actions"][0]["FunctionCall"]["gas"]
+    .as_u64()
+    .unwrap()
+}
+
+#[test]
+fn distribute_rewards_within_forwarded_gas() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .prepaid_gas(GAS_FOR_DISTRIBUTE_REWARDS)
+    .build());
+  contract.distribute_rewards();
+
+  // every callback runs on the gas the step before forwarded
+  context.prepaid_gas(callback_gas());
+  callback_context(&mut context, vec![amount_result(2_000_000)]);
+  contract.callback_distribute_rewards(1_000_000);
+
+  context.prepaid_gas(callback_gas());
+  callback_context(&mut context, vec![amount_result(2_000_000)]);
+  contract.callback_claim_rewards(AccountId::from("reward_token"));
+
+  context.prepaid_gas(callback_gas());
+  callback_context(&mut context, vec![price_result(D128::new_exp(5, -1), true)]);
+  contract.callback_get_price_status(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  context.prepaid_gas(callback_gas());
+  callback_context(&mut context, vec![amount_result(2_000_000)]);
+  contract.callback_swap_to_stable_denom(AccountId::from("reward_token"), U128::from(2_000_000));
+
+  context.prepaid_gas(callback_gas());
+  callback_context(&mut context, vec![amount_result(990_000)]);
+  contract.callback_distribute_hook();
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("stable_coin", receipts[0]["receiver_id"]);
+  assert_eq!("ft_transfer", function_call["method_name"]);
+  assert_eq!(GAS_FOR_FT_TRANSFER, function_call["gas"].as_u64().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to distribute the rewards")]
+fn distribute_rewards_without_gas() {
+  let (mut context, contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .prepaid_gas(GAS_FOR_DISTRIBUTE_REWARDS - 1)
+    .build());
+
+  contract.distribute_rewards();
+}
+
+#[test]
+fn check_reward_token_price_on_deployment() {
+  setup_contract();
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("overseer", receipts[0]["receiver_id"]);
+  assert_eq!("get_price_status", function_call["method_name"]);
+  assert_eq!(
+    "callback_check_reward_token",
+    receipts[1]["actions"][0]["FunctionCall"]["method_name"]
+  );
+}
+
+#[test]
+fn drop_reward_token_without_price() {
+  let (mut context, mut contract) = setup_contract();
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+
+  contract.callback_check_reward_token(AccountId::from("reward_token"));
+
+  assert_eq!(RewardSource::None, contract.config.reward_source);
+  assert_eq!(
+    vec!["The overseer has no price of reward_token, its rewards are not distributed"],
+    get_logs()
+  );
+}
+
+#[test]
+fn keep_reward_token_with_price() {
+  let (mut context, mut contract) = setup_contract();
+  callback_context(
+    &mut context,
+    vec![price_result(D128::new_exp(5, -1), false)],
+  );
+
+  contract.callback_check_reward_token(AccountId::from("reward_token"));
+
+  assert_eq!(
+    RewardSource::RewardContract {
+      reward_contract: AccountId::from("reward"),
+      reward_token: AccountId::from("reward_token"),
+    },
+    contract.config.reward_source
+  );
+}
+
+#[test]
+#[should_panic(expected = "The max slippage must not exceed 1")]
+fn update_config_with_invalid_slippage() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .build());
+
+  contract.update_config(
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(DexConfig {
+      dex_contract: AccountId::from("dex"),
+      max_slippage: D128::new_exp(2, 0),
+      reward_decimals: 6,
+      stable_coin_decimals: 6,
+    }),
+    None,
+  );
+}
+
+#[test]
+fn storage_deposit_refunds_excess() {
+  let (mut context, mut contract) = setup_contract();
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .attached_deposit(min_balance + 10)
+    .build());
+
+  let storage_balance = contract.storage_deposit(None, None);
+
+  assert_eq!(min_balance, storage_balance.total.0);
+  assert_eq!(0, storage_balance.available.0);
+  // a refund in yoctoNEAR does not fit a json number
+  let receipts = serde_json::to_string(&get_created_receipts()).unwrap();
+  assert!(receipts.contains("\"receiver_id\":\"borrower\""));
+  assert!(receipts.contains("{\"Transfer\":{\"deposit\":10}}"));
+}
+
+#[test]
+fn storage_deposit_for_registered_account() {
+  let (mut context, mut contract) = setup_contract();
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("someone").unwrap())
+    .attached_deposit(min_balance)
+    .build());
+  contract.storage_deposit(Some(AccountId::from("borrower")), None);
+
+  let storage_balance = contract.storage_deposit(Some(AccountId::from("borrower")), Some(true));
+
+  assert_eq!(min_balance, storage_balance.total.0);
+  assert_eq!(
+    "The account is already registered, refunding the deposit",
+    get_logs()[0]
+  );
+  let receipts = serde_json::to_string(&get_created_receipts()).unwrap();
+  assert!(receipts.contains(&format!(
+    "\"receiver_id\":\"someone\",\"actions\":[{{\"Transfer\":{{\"deposit\":{}}}}}]",
+    min_balance
+  )));
+}
+
+#[test]
+#[should_panic(expected = "The attached deposit is less than the minimum storage balance")]
+fn storage_deposit_below_minimum() {
+  let (mut context, mut contract) = setup_contract();
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .attached_deposit(min_balance - 1)
+    .build());
+
+  contract.storage_deposit(None, None);
+}
+
+#[test]
+#[should_panic(expected = "The amount is greater than the available storage balance")]
+fn storage_withdraw_more_than_available() {
+  let (mut context, mut contract) = setup_contract();
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .attached_deposit(min_balance)
+    .build());
+  contract.storage_deposit(None, None);
+
+  testing_env!(context.attached_deposit(1).build());
+  contract.storage_withdraw(Some(U128::from(1)));
+}
+
+#[test]
+fn first_deposit_of_registered_account() {
+  let (mut context, mut contract) = setup_contract();
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .attached_deposit(min_balance)
+    .build());
+  contract.storage_deposit(None, None);
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .attached_deposit(0)
+    .build());
+  contract.ft_on_transfer(AccountId::from("borrower"), U128::from(100), String::new());
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 100,
+      spendable: 100,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+}
+
+#[test]
+#[should_panic(expected = "The account borrower is not registered")]
+fn deposit_of_unregistered_account() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("collateral").unwrap())
+    .build());
+
+  contract.ft_on_transfer(AccountId::from("borrower"), U128::from(100), String::new());
+}
+
+#[test]
+fn withdraw_collateral_resolves_transfer() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 100,
+      spendable: 100,
+    },
+  );
+  contract.state.total_balance = 100;
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .build());
+  contract.withdraw_collateral(Some(30), None);
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!(
+    "ft_transfer",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+  assert_eq!(1, receipts[0]["actions"][0]["FunctionCall"]["deposit"]);
+  assert_eq!(
+    "callback_withdraw_collateral",
+    receipts[1]["actions"][0]["FunctionCall"]["method_name"]
+  );
+
+  callback_context(&mut context, vec![PromiseResult::Successful(vec![])]);
+  contract.callback_withdraw_collateral(AccountId::from("borrower"), U128::from(30));
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 70,
+      spendable: 70,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  assert_eq!(70, contract.get_state().total_balance);
+}
+
+#[test]
+fn restore_refused_withdrawal() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 70,
+      spendable: 50,
+    },
+  );
+  contract.state.total_balance = 70;
+
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+  contract.callback_withdraw_collateral(AccountId::from("borrower"), U128::from(30));
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 100,
+      spendable: 80,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  assert_eq!(100, contract.get_state().total_balance);
+  assert_eq!("restore_collateral", events()[0]["event"]);
+  assert_eq!("30", events()[0]["data"][0]["amount"]);
+}
+
+#[test]
+fn restore_refused_liquidation() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 60,
+      spendable: 0,
+    },
+  );
+  contract.state.total_balance = 60;
+
+  // the queue only had bids for 25 of the 40 liquidated
+  callback_context(&mut context, vec![amount_result(25)]);
+  contract.callback_liquidate_collateral(AccountId::from("borrower"), U128::from(40));
+
+  // the overseer removed the 40 from the collaterals, the 15 left stay locked
+  // until it counts them again
+  assert_eq!(
+    BorrowerInfo {
+      balance: 75,
+      spendable: 0,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  assert_eq!(75, contract.get_state().total_balance);
+  assert_eq!("restore_locked_collateral", events()[0]["event"]);
+  assert_eq!("15", events()[0]["data"][0]["amount"]);
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("overseer", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "restore_collateral_from_custody",
+    function_call["method_name"]
+  );
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  assert_eq!("borrower", args["borrower"]);
+  assert_eq!("collateral", args["collateral_token"]);
+  assert_eq!("15", args["amount"]);
+}
+
+#[test]
+fn restore_refused_liquidation_within_callback_gas() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 60,
+      spendable: 0,
+    },
+  );
+
+  // the callback runs with the gas the liquidation attached to it
+  testing_env!(
+    context
+      .current_account_id(ValidAccountId::try_from("custody").unwrap())
+      .predecessor_account_id(ValidAccountId::try_from("custody").unwrap())
+      .prepaid_gas(GAS_FOR_LIQUIDATION_CALLBACK)
+      .build(),
+    VMConfig::default(),
+    RuntimeFeesConfig::default(),
+    Default::default(),
+    vec![PromiseResult::Failed]
+  );
+  contract.callback_liquidate_collateral(AccountId::from("borrower"), U128::from(40));
+
+  assert_eq!(
+    100,
+    contract.get_borrower(AccountId::from("borrower")).balance
+  );
+}
+
+#[test]
+fn keep_fully_used_liquidation() {
+  let (mut context, mut contract) = setup_contract();
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 60,
+      spendable: 0,
+    },
+  );
+
+  callback_context(&mut context, vec![amount_result(40)]);
+  contract.callback_liquidate_collateral(AccountId::from("borrower"), U128::from(40));
+
+  assert_eq!(
+    60,
+    contract.get_borrower(AccountId::from("borrower")).balance
+  );
+  assert!(events().is_empty());
+}
+
+fn setup_native_contract() -> (VMContextBuilder, Contract) {
+  let (context, mut contract) = setup_contract();
+  contract.config.collateral_token = AccountId::from("wnear");
+  contract.config.wnear_contract = Some(AccountId::from("wnear"));
+  contract.collection.borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      balance: 100,
+      spendable: 100,
+    },
+  );
+  contract.state.total_balance = 100;
+  (context, contract)
+}
+
+#[test]
+fn deposit_native_wraps_near() {
+  let (mut context, mut contract) = setup_native_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .attached_deposit(50)
+    .build());
+
+  contract.deposit_native();
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("wnear", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "near_deposit",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+  assert_eq!(50, receipts[0]["actions"][0]["FunctionCall"]["deposit"]);
+  assert_eq!(
+    "callback_deposit_native",
+    receipts[1]["actions"][0]["FunctionCall"]["method_name"]
+  );
+
+  // credited once wrapped
+  assert_eq!(
+    100,
+    contract.get_borrower(AccountId::from("borrower")).balance
+  );
+  callback_context(&mut context, vec![PromiseResult::Successful(vec![])]);
+  contract.callback_deposit_native(AccountId::from("borrower"), U128::from(50));
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 150,
+      spendable: 150,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  assert_eq!(150, contract.get_state().total_balance);
+  assert_eq!("deposit_native", events()[0]["event"]);
+}
+
+#[test]
+fn refund_refused_native_deposit() {
+  let (mut context, mut contract) = setup_native_contract();
+
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+  contract.callback_deposit_native(AccountId::from("borrower"), U128::from(50));
+
+  assert_eq!(
+    100,
+    contract.get_borrower(AccountId::from("borrower")).balance
+  );
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("borrower", receipts[0]["receiver_id"]);
+  assert_eq!(50, receipts[0]["actions"][0]["Transfer"]["deposit"]);
+}
+
+#[test]
+#[should_panic(expected = "The collateral cannot be deposited or withdrawn as NEAR")]
+fn deposit_native_without_wnear_contract() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .attached_deposit(50)
+    .build());
+
+  contract.deposit_native();
+}
+
+#[test]
+#[should_panic(expected = "The account borrower2 is not registered")]
+fn deposit_native_of_unregistered_account() {
+  let (mut context, mut contract) = setup_native_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower2").unwrap())
+    .attached_deposit(50)
+    .build());
+
+  contract.deposit_native();
+}
+
+#[test]
+fn withdraw_collateral_as_near() {
+  let (mut context, mut contract) = setup_native_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .build());
+
+  contract.withdraw_collateral(Some(30), Some(true));
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("wnear", receipts[0]["receiver_id"]);
+  assert_eq!(
+    "near_withdraw",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+  assert_eq!(1, receipts[0]["actions"][0]["FunctionCall"]["deposit"]);
+  assert_eq!(
+    "callback_withdraw_native",
+    receipts[1]["actions"][0]["FunctionCall"]["method_name"]
+  );
+  assert_eq!(70, contract.get_state().total_balance);
+
+  callback_context(&mut context, vec![PromiseResult::Successful(vec![])]);
+  contract.callback_withdraw_native(AccountId::from("borrower"), U128::from(30));
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!("borrower", receipts[0]["receiver_id"]);
+  assert_eq!(30, receipts[0]["actions"][0]["Transfer"]["deposit"]);
+  assert_eq!(
+    70,
+    contract.get_borrower(AccountId::from("borrower")).balance
+  );
+}
+
+#[test]
+fn restore_refused_unwrap() {
+  let (mut context, mut contract) = setup_native_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .build());
+  contract.withdraw_collateral(Some(30), Some(true));
+
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+  contract.callback_withdraw_native(AccountId::from("borrower"), U128::from(30));
+
+  assert_eq!(
+    BorrowerInfo {
+      balance: 100,
+      spendable: 100,
+    },
+    contract.get_borrower(AccountId::from("borrower"))
+  );
+  assert_eq!(100, contract.get_state().total_balance);
+  assert!(get_created_receipts().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "NEAR can only be wrapped into the collateral token")]
+fn update_config_with_foreign_wnear_contract() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .build());
+
+  contract.update_config(
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    None,
+    Some(AccountId::from("wnear")),
+  );
+}
+
+#[test]
+fn distribute_hook_sends_stable_coin_to_overseer() {
+  let (mut context, contract) = setup_contract();
+  callback_context(&mut context, vec![amount_result(3_000_000)]);
+
+  contract.callback_distribute_hook();
+
+  let receipts = serde_json::to_value(get_created_receipts()).unwrap();
+  let function_call = &receipts[0]["actions"][0]["FunctionCall"];
+  assert_eq!("stable_coin", receipts[0]["receiver_id"]);
+  assert_eq!("ft_transfer", function_call["method_name"]);
+  assert_eq!(1, function_call["deposit"]);
+
+  let args: serde_json::Value =
+    serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+  assert_eq!("overseer", args["receiver_id"]);
+  assert_eq!("3000000", args["amount"]);
+}
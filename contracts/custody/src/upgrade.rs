@@ -0,0 +1,133 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+/// Layouts the contract state has been persisted with. The version is kept
+/// under `STATE_VERSION_KEY`; the deployment made before it existed is `V0`,
+/// converted into the current layout by `migrate`.
+pub enum VersionedContract {
+  V0(ContractV0),
+  V1(Contract),
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ConfigV0 {
+  pub owner_id: AccountId,
+  pub overseer_contract: AccountId,
+  pub collateral_token: AccountId,
+  pub market_contract: AccountId,
+  pub reward_contract: AccountId,
+  pub liquidation_contract: AccountId,
+  pub stable_coin_contract: AccountId,
+  pub basset_info: BAssetInfo,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StateV0 {}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CollectionV0 {
+  pub borrower_info_map: LookupMap<AccountId, BorrowerInfo>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+  pub config: ConfigV0,
+  pub state: StateV0,
+  pub collection: CollectionV0,
+}
+
+impl From<ContractV0> for Contract {
+  fn from(old: ContractV0) -> Self {
+    Self {
+      config: Config {
+        owner_id: old.config.owner_id,
+        overseer_contract: old.config.overseer_contract,
+        collateral_token: old.config.collateral_token,
+        market_contract: old.config.market_contract,
+        liquidation_contract: old.config.liquidation_contract,
+        basset_info: old.config.basset_info,
+        // the custodies deployed so far held bNEAR, whose rewards are distributed as
+        // they are paid until the owner sets the reward token
+        reward_source: RewardSource::RewardContract {
+          reward_contract: old.config.reward_contract,
+          reward_token: old.config.stable_coin_contract.clone(),
+        },
+        stable_coin_contract: old.config.stable_coin_contract,
+        dex: None,
+        wnear_contract: None,
+      },
+      // the balances cannot be summed over a lookup map, only a rebasing custody reads the total
+      state: State { total_balance: 0 },
+      // the borrowers of the old layout keep their entries without a registration
+      collection: Collection {
+        borrower_info_map: old.collection.borrower_info_map,
+        storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
+      },
+    }
+  }
+}
+
+impl Contract {
+  pub(crate) fn internal_read_state_version() -> u8 {
+    env::storage_read(STATE_VERSION_KEY)
+      .map(|value| value[0])
+      .unwrap_or(0)
+  }
+
+  pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+    match Self::internal_read_state_version() {
+      0 => VersionedContract::V0(env::state_read().expect("Failed to read the V0 state")),
+      CURRENT_STATE_VERSION => {
+        VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+      }
+      _ => env::panic("Unknown state version".as_bytes()),
+    }
+  }
+
+  pub(crate) fn internal_write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+  }
+}
+
+#[near_bindgen]
+impl Contract {
+  /// Converts the stored state into the current layout. Called by `upgrade`
+  /// right after the new code is deployed, or by the owner directly.
+  #[init(ignore_state)]
+  pub fn migrate() -> Self {
+    let contract: Contract = match Self::internal_read_versioned_state() {
+      VersionedContract::V0(old) => old.into(),
+      VersionedContract::V1(contract) => contract,
+    };
+
+    if env::predecessor_account_id() != env::current_account_id() {
+      contract.assert_owner();
+    }
+
+    Self::internal_write_state_version();
+
+    contract
+  }
+
+  /// Deploys the wasm blob attached as the call input and migrates the state.
+  pub fn upgrade(&self) -> Promise {
+    self.assert_owner();
+
+    let code = env::input().expect("Error: No code attached");
+    let migrate_gas = env::prepaid_gas()
+      .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+      .expect("Not enough gas attached to migrate after the upgrade");
+
+    Promise::new(env::current_account_id())
+      .deploy_contract(code)
+      .function_call(b"migrate".to_vec(), b"{}".to_vec(), NO_DEPOSIT, migrate_gas)
+  }
+
+  pub fn get_state_version(&self) -> u8 {
+    Self::internal_read_state_version()
+  }
+}
@@ -0,0 +1,378 @@
+use crate::distribution::SwapMsg;
+use crate::*;
+
+#[ext_contract(fungible_token)]
+pub trait FungibleToken {
+  fn ft_total_supply(&self) -> U128;
+
+  fn ft_balance_of(&self, account_id: AccountId) -> U128;
+
+  fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+  fn ft_transfer_call(
+    &mut self,
+    receiver_id: AccountId,
+    amount: U128,
+    memo: Option<String>,
+    msg: String,
+  ) -> PromiseOrValue<U128>;
+}
+
+#[ext_contract(ext_overseer)]
+pub trait OverseerContract {
+  fn lock_collateral_from_custody(
+    &mut self,
+    borrower: AccountId,
+    collateral_token: AccountId,
+    amount: U128,
+    borrow: Option<(AccountId, U128)>,
+  ) -> Promise;
+
+  fn restore_collateral_from_custody(
+    &mut self,
+    borrower: AccountId,
+    collateral_token: AccountId,
+    amount: U128,
+  );
+
+  fn get_price_status(&self, asset: AccountId) -> PriceStatus;
+}
+
+#[ext_contract(ext_reward)]
+pub trait RewardContract {
+  fn get_account_stake_rewards(&self, account_id: AccountId) -> U128;
+
+  // transfers the rewards of the caller, returns their amount
+  fn claim_rewards(&mut self) -> U128;
+}
+
+#[ext_contract(ext_wnear)]
+pub trait WrappedNear {
+  // mints wNEAR for the attached NEAR to the caller
+  fn near_deposit(&mut self);
+
+  // burns wNEAR of the caller and sends the NEAR back, requires one yoctoNEAR
+  fn near_withdraw(&mut self, amount: U128) -> Promise;
+}
+
+#[ext_contract(ext_self)]
+pub trait Contract {
+  fn callback_distribute_rewards(&mut self, rewards_threshold: Balance);
+  fn callback_claim_rewards(&self, reward_token: AccountId);
+  fn callback_get_price_status(&self, token_in: AccountId, amount_in: U128);
+  fn callback_swap_to_stable_denom(&self, token_in: AccountId, amount_in: U128);
+  fn callback_distribute_hook(&self);
+  fn callback_check_reward_token(&mut self, reward_token: AccountId);
+  fn callback_withdraw_collateral(&mut self, borrower: AccountId, amount: U128);
+  fn callback_liquidate_collateral(&mut self, borrower: AccountId, amount: U128);
+  fn callback_deposit_native(&mut self, borrower: AccountId, amount: U128);
+  fn callback_withdraw_native(&mut self, borrower: AccountId, amount: U128);
+}
+
+#[near_bindgen]
+impl Contract {
+  #[private]
+  pub fn callback_distribute_rewards(&mut self, rewards_threshold: Balance) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        env::panic("fail".as_bytes());
+      }
+      PromiseResult::Successful(result) => {
+        let result: Balance = near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0;
+        // a rebasing collateral reports the balance of the custody, rewards included
+        let accrued_rewards = match self.config.reward_source {
+          RewardSource::Rebasing => result.saturating_sub(self.state.total_balance),
+          _ => result,
+        };
+
+        if accrued_rewards < rewards_threshold {
+          return;
+        }
+
+        match self.config.reward_source.clone() {
+          RewardSource::RewardContract {
+            reward_contract,
+            reward_token,
+          } => {
+            ext_reward::claim_rewards(&reward_contract, NO_DEPOSIT, GAS_FOR_CLAIM_REWARDS).then(
+              ext_self::callback_claim_rewards(
+                reward_token,
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                Self::reward_callback_gas(GAS_FOR_CLAIM_REWARDS),
+              ),
+            );
+          }
+          RewardSource::Rebasing => {
+            self.swap_to_stable_denom(
+              self.config.collateral_token.clone(),
+              U128::from(accrued_rewards),
+            );
+          }
+          RewardSource::None => {}
+        }
+      }
+    }
+  }
+
+  #[private]
+  pub fn callback_claim_rewards(&self, reward_token: AccountId) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        env::panic("fail".as_bytes());
+      }
+      PromiseResult::Successful(result) => {
+        let claimed_amount: U128 = near_sdk::serde_json::from_slice::<U128>(&result).unwrap();
+
+        self.internal_log_event(
+          "claim_rewards",
+          serde_json::json!({
+            "reward_token": reward_token,
+            "amount": claimed_amount,
+          }),
+        );
+
+        if claimed_amount.0 != 0 {
+          self.swap_to_stable_denom(reward_token, claimed_amount);
+        }
+      }
+    }
+  }
+
+  /// Sends the rewards to the DEX with the least it may pay for them. Without a
+  /// fresh price of the rewards they are kept for the next distribution
+  #[private]
+  pub fn callback_get_price_status(&self, token_in: AccountId, amount_in: U128) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        env::log(format!("No price to swap {} to the stable coin", token_in).as_bytes());
+      }
+      PromiseResult::Successful(result) => {
+        let price_status: PriceStatus = near_sdk::serde_json::from_slice(&result).unwrap();
+        if !price_status.is_fresh {
+          env::log(format!("The price of {} is stale, the swap is postponed", token_in).as_bytes());
+          return;
+        }
+
+        let dex = self.config.dex.clone().unwrap();
+        // the oracle prices whole tokens, a rebasing collateral pays its rewards in itself
+        let decimals_in = if token_in == self.config.collateral_token {
+          self.config.basset_info.decimals
+        } else {
+          dex.reward_decimals
+        };
+        let value = value_of(
+          amount_in.0,
+          decimals_in,
+          price_status.price,
+          dex.stable_coin_decimals,
+        );
+        let min_amount_out = ((D128::one() - dex.max_slippage) * value).as_u128();
+
+        let msg = serde_json::to_string(&SwapMsg {
+          token_out: self.config.stable_coin_contract.clone(),
+          min_amount_out: U128::from(min_amount_out),
+        })
+        .unwrap();
+
+        fungible_token::ft_transfer_call(
+          dex.dex_contract,
+          amount_in,
+          None,
+          msg,
+          &token_in,
+          ONE_YOCTO,
+          GAS_FOR_SWAP,
+        )
+        .then(ext_self::callback_swap_to_stable_denom(
+          token_in,
+          amount_in,
+          &env::current_account_id(),
+          NO_DEPOSIT,
+          Self::reward_callback_gas(GAS_FOR_SWAP),
+        ));
+      }
+    }
+  }
+
+  /// Reports the swap, then distributes whatever the DEX paid. The refunded
+  /// rewards stay in the custody for the next distribution
+  #[private]
+  pub fn callback_swap_to_stable_denom(&self, token_in: AccountId, amount_in: U128) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    // `ft_transfer_call` resolves to the amount the DEX used
+    let amount_swapped: U128 = match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => U128::from(0),
+      PromiseResult::Successful(result) => {
+        near_sdk::serde_json::from_slice::<U128>(&result).unwrap()
+      }
+    };
+
+    self.internal_log_event(
+      "swap_rewards",
+      serde_json::json!({
+        "token_in": token_in,
+        "amount_in": amount_in,
+        "amount_swapped": amount_swapped,
+      }),
+    );
+
+    if amount_swapped.0 != 0 {
+      self.distribute_hook();
+    }
+  }
+
+  #[private]
+  pub fn callback_distribute_hook(&self) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        env::panic("fail".as_bytes());
+      }
+      PromiseResult::Successful(result) => {
+        let reward_amount: Balance = near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0;
+
+        if reward_amount != 0 {
+          self.internal_log_event(
+            "distribute_rewards",
+            serde_json::json!({
+              "amount": U128::from(reward_amount),
+            }),
+          );
+          fungible_token::ft_transfer(
+            self.config.overseer_contract.clone(),
+            U128::from(reward_amount),
+            None,
+            &self.config.stable_coin_contract,
+            ONE_YOCTO,
+            GAS_FOR_FT_TRANSFER,
+          );
+        }
+      }
+    }
+  }
+
+  /// Drops the reward source if the overseer has no price to swap its token at
+  #[private]
+  pub fn callback_check_reward_token(&mut self, reward_token: AccountId) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    if let PromiseResult::Successful(_) = env::promise_result(0) {
+      return;
+    }
+    // the reward source may have been updated again since
+    if let RewardSource::RewardContract {
+      reward_token: current_token,
+      ..
+    } = &self.config.reward_source
+    {
+      if current_token == &reward_token {
+        env::log(
+          format!(
+            "The overseer has no price of {}, its rewards are not distributed",
+            reward_token
+          )
+          .as_bytes(),
+        );
+        self.config.reward_source = RewardSource::None;
+      }
+    }
+  }
+
+  /// Restores the withdrawal refused by the collateral token, e.g. when the
+  /// borrower is not registered with it
+  #[private]
+  pub fn callback_withdraw_collateral(&mut self, borrower: AccountId, amount: U128) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        self.internal_restore_collateral(borrower, amount.0);
+      }
+      PromiseResult::Successful(_) => {}
+    }
+  }
+
+  /// Restores the part of a liquidation the liquidation contract refunded. The
+  /// overseer no longer counts it among the collaterals of the borrower, so it
+  /// stays locked and the overseer is told to count it again
+  #[private]
+  pub fn callback_liquidate_collateral(&mut self, borrower: AccountId, amount: U128) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    // `ft_transfer_call` resolves to the amount the liquidation contract used
+    let used_amount: Balance = match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => 0,
+      PromiseResult::Successful(result) => {
+        near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0
+      }
+    };
+
+    let unused_amount = amount.0.saturating_sub(used_amount);
+    if unused_amount != 0 {
+      self.internal_restore_locked_collateral(borrower.clone(), unused_amount);
+      ext_overseer::restore_collateral_from_custody(
+        borrower,
+        self.config.collateral_token.clone(),
+        U128::from(unused_amount),
+        &self.config.overseer_contract,
+        NO_DEPOSIT,
+        GAS_FOR_OVERSEER_RESTORE,
+      );
+    }
+  }
+
+  /// Credits the wrapped deposit, or gives the NEAR back if the wNEAR contract refused it
+  #[private]
+  pub fn callback_deposit_native(&mut self, borrower: AccountId, amount: U128) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        Promise::new(borrower).transfer(amount.0);
+      }
+      PromiseResult::Successful(_) => {
+        self.deposit_collateral(borrower.clone(), amount.0);
+        self.internal_log_event(
+          "deposit_native",
+          serde_json::json!({
+            "borrower": borrower,
+            "amount": amount,
+          }),
+        );
+      }
+    }
+  }
+
+  /// Sends the unwrapped NEAR to the borrower, or restores the withdrawal the
+  /// wNEAR contract refused
+  #[private]
+  pub fn callback_withdraw_native(&mut self, borrower: AccountId, amount: U128) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+    match env::promise_result(0) {
+      PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Failed => {
+        self.internal_restore_collateral(borrower, amount.0);
+      }
+      PromiseResult::Successful(_) => {
+        Promise::new(borrower).transfer(amount.0);
+      }
+    }
+  }
+}
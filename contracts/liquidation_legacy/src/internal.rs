@@ -11,6 +11,10 @@ impl Contract {
         );
     }
 
+    pub(crate) fn assert_valid_decimals(decimals: u8) {
+        assert!(decimals <= 24, "Decimals must not exceed 24");
+    }
+
     pub(crate) fn internal_get_bid(&self, bidder: &AccountId) -> Option<Bid> {
         self.bids.get(bidder)
     }
@@ -23,21 +27,26 @@ impl Contract {
         self.bids.insert(bidder, &bid);
     }
 
-    /// updates price response at every function call
-    pub(crate) fn internal_update_price_response(
-        &mut self,
-    ) -> Promise {
+    /// reads the NEAR price request, kept by the requester contract under
+    /// `liquidation/near`, and stores its price once finalized. The callback gets
+    /// the gas left to pay the next request
+    pub(crate) fn internal_update_price_response(&mut self) -> Promise {
+        let callback_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_PRICE_QUERY + GAS_FOR_PRICE_UPDATE)
+            .filter(|gas| *gas >= DR_NEW_GAS + GAS_FOR_PRICE_UPDATE)
+            .expect("Not enough gas to request the next price");
+
         requester::get_data_request(
-            env::current_account_id().try_into().unwrap(),
+            format!("{}/near", env::current_account_id()),
             // Near params
             &self.requester_contract,
             0,
-            3_000_000_000_000,
+            GAS_FOR_PRICE_QUERY,
         ).then(ext_self::callback_get_price_response(
             // Near params
             &env::current_account_id(),
             0,
-            30_000_000_000_000,
+            callback_gas,
         ))
     }
 
@@ -53,7 +62,6 @@ impl Contract {
 
     /// callback on transfer stable coin
     pub(crate) fn internal_submit_bid(&mut self, bidder: AccountId, premium_rate: D128, amount: U128) {
-        self.internal_update_price_response();
         assert!(self.internal_get_bid(&bidder).is_none(), "User already has bid");
         assert!(premium_rate < self.max_premium_rate, "Premium rate cannot exceed the max premium rate");
 
@@ -72,14 +80,22 @@ impl Contract {
         liquidator: AccountId,
         repay_address: AccountId,
         fee_address: AccountId,
-        amount: U128,   // amount of bNEAR (decimal: 24)
+        amount: U128,   // amount of bNEAR
     ) {
-        self.internal_update_price_response();
+        if self.last_price_response.last_updated_at + self.price_timeframe * SECOND_TO_NANO < env::block_timestamp() {
+            panic!("Price is too old to execute bids");
+        }
+
         let bid: Bid = self.internal_get_bid(&liquidator).expect("No bids with the specified information exist");
 
-        // corresponding collateral bNEAR value in USD (decimal: 6, which is decimal of USDT)
-        let collateral_value: Balance = self.last_price_response.price.mul_int(amount.0) / 1_000_000_000_000_000_000;
-        // required amount of USDT (decimal: 6)
+        // corresponding collateral bNEAR value in the stable coin
+        let collateral_value: Balance = value_of(
+            amount.0,
+            self.bnear_decimals,
+            self.last_price_response.price,
+            self.stable_coin_decimals,
+        ).as_u128();
+        // required amount of the stable coin
         let required_stable: Balance = (D128::one() - std::cmp::min(bid.premium_rate, self.max_premium_rate))
             .mul_int(collateral_value);
         
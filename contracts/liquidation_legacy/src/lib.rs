@@ -1,23 +1,35 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Serialize, Deserialize};
-use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::json_types::U128;
 use near_sdk::collections::{LookupMap};
-use near_sdk::{env, near_bindgen, serde_json, assert_one_yocto, AccountId, Balance, PanicOnDefault, PromiseOrValue, Promise};
-use math::{D128, DECIMAL};
+use near_sdk::{env, near_bindgen, serde_json, assert_one_yocto, AccountId, Balance, Gas, PanicOnDefault, PromiseOrValue, Promise};
+use math::{value_of, D128, DECIMAL};
 use utils::{fungible_token_transfer, fungible_token_transfer_call, requester, ext_self};
-use std::convert::TryInto;
+use flux_sdk::consts::DR_NEW_GAS;
 
 mod internal;
 mod math;
 mod owner;
+#[cfg(test)]
+mod testing;
 mod token_receiver;
+mod upgrade;
 mod utils;
 mod views;
 
+const SECOND_TO_NANO: u64 = 1_000_000_000;
+
+// the pending NEAR price request read from the requester contract
+const GAS_FOR_PRICE_QUERY: Gas = 10_000_000_000_000;
+
+// kept by the price update and its callback for their receipts, the callback also
+// pays the next request
+const GAS_FOR_PRICE_UPDATE: Gas = 25_000_000_000_000;
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Bid {
-    // amount of USDT (decimal: 6)
+    // amount of the stable coin
     pub amount: U128,
     pub premium_rate: D128,
 }
@@ -29,6 +41,16 @@ pub struct PriceResponse {
     pub last_updated_at: u64,
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceStatus {
+    pub price: D128,
+    pub last_updated_at: u64,
+    // unit: seconds
+    pub age: u64,
+    pub is_fresh: bool,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct BnearReceiverPayload {
@@ -49,13 +71,17 @@ pub struct StableReceiverPayload {
 pub struct Contract {
     owner: AccountId,
     bnear_contract: AccountId,
+    bnear_decimals: u8,
     stable_coin_contract: AccountId,
+    stable_coin_decimals: u8,
     requester_contract: AccountId,
     oracle_payment_token: AccountId,
     safe_ratio: D128,
     bid_fee: D128,
     max_premium_rate: D128,
     liquidation_threshold: Balance,
+    // unit: seconds
+    price_timeframe: u64,
     bids: LookupMap<AccountId, Bid>,
     last_price_response: PriceResponse,
 }
@@ -66,39 +92,52 @@ impl Contract {
     pub fn new(
         owner: AccountId,
         bnear_contract: AccountId,
+        bnear_decimals: u8,
         stable_coin_contract: AccountId,
+        stable_coin_decimals: u8,
         requester_contract: AccountId,
         oracle_payment_token: AccountId,
         safe_ratio: D128,
         bid_fee: D128,
         max_premium_rate: D128,
         liquidation_threshold: Balance,
+        price_timeframe: u64,
     ) -> Self {
-        let mut instance = Self{
+        Self::assert_valid_decimals(bnear_decimals);
+        Self::assert_valid_decimals(stable_coin_decimals);
+        Self::internal_write_state_version();
+
+        Self{
             owner,
             bnear_contract,
+            bnear_decimals,
             stable_coin_contract,
+            stable_coin_decimals,
             requester_contract,
             oracle_payment_token,
             safe_ratio,
             bid_fee,
             max_premium_rate,
             liquidation_threshold,
+            price_timeframe,
             bids: LookupMap::new(b"b".to_vec()),
             last_price_response: PriceResponse{price: D128::one(), last_updated_at: env::block_timestamp()},
-        };
-        // Updates initial price
-        instance.internal_update_price_response();
+        }
+    }
 
-        instance
+    /// Stores the NEAR price once its request is finalized and requests the next one.
+    /// Anyone can refresh the price
+    #[payable]
+    pub fn update_price(&mut self) -> Promise {
+        assert_one_yocto();
+
+        self.internal_update_price_response()
     }
 
     #[payable]
     pub fn retract_bid(&mut self, amount: Option<U128>) {
         assert_one_yocto();
         
-        self.internal_update_price_response();
-        
         let bidder: AccountId = env::predecessor_account_id();
 
         let bid: Bid = self.internal_get_bid(&bidder).expect("No bids with the specified information exist");
@@ -6,13 +6,16 @@ impl Contract {
         &mut self,
         owner: Option<AccountId>,
         bnear_contract: Option<AccountId>,
+        bnear_decimals: Option<u8>,
         stable_coin_contract: Option<AccountId>,
+        stable_coin_decimals: Option<u8>,
         requester_contract: Option<AccountId>,
         oracle_payment_token: Option<AccountId>,
         safe_ratio: Option<D128>,
         bid_fee: Option<D128>,
         max_premium_rate: Option<D128>,
         liquidation_threshold: Option<Balance>,
+        price_timeframe: Option<u64>,
     ) {
         self.assert_owner();
 
@@ -24,10 +27,20 @@ impl Contract {
             self.bnear_contract = bnear_contract;
         }
 
+        if let Some(bnear_decimals) = bnear_decimals {
+            Self::assert_valid_decimals(bnear_decimals);
+            self.bnear_decimals = bnear_decimals;
+        }
+
         if let Some(stable_coin_contract) = stable_coin_contract {
             self.stable_coin_contract = stable_coin_contract;
         }
 
+        if let Some(stable_coin_decimals) = stable_coin_decimals {
+            Self::assert_valid_decimals(stable_coin_decimals);
+            self.stable_coin_decimals = stable_coin_decimals;
+        }
+
         if let Some(requester_contract) = requester_contract {
             self.requester_contract = requester_contract;
         }
@@ -51,5 +64,9 @@ impl Contract {
         if let Some(liquidation_threshold) = liquidation_threshold {
             self.liquidation_threshold = liquidation_threshold;
         }
+
+        if let Some(price_timeframe) = price_timeframe {
+            self.price_timeframe = price_timeframe;
+        }
     }
 }
\ No newline at end of file
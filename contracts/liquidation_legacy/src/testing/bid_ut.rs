@@ -0,0 +1,176 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use super::{
+    created_transfers, execute_bid, setup_contract, setup_contract_with_decimals, submit_bid,
+};
+use crate::*;
+
+#[test]
+fn execute_bid_by_token_decimals() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(
+        &mut context,
+        &mut contract,
+        "bidder",
+        D128::new_exp(5, -2),
+        1_000_000_000,
+    );
+
+    // 100 bNEAR at 5 are worth 500 of the stable coin, the bid pays 475 of them
+    execute_bid(
+        &mut context,
+        &mut contract,
+        "custody",
+        "bidder",
+        100_000_000_000_000_000_000_000_000,
+    );
+
+    assert_eq!(
+        U128(525_000_000),
+        contract
+            .internal_get_bid(&AccountId::from("bidder"))
+            .unwrap()
+            .amount
+    );
+    assert_eq!(
+        vec![
+            (
+                String::from("bnear"),
+                String::from("bidder"),
+                100_000_000_000_000_000_000_000_000
+            ),
+            (
+                String::from("stable_coin"),
+                String::from("custody"),
+                470_250_000
+            ),
+            (
+                String::from("stable_coin"),
+                String::from("custody"),
+                4_750_000
+            ),
+        ],
+        created_transfers()
+    );
+}
+
+#[test]
+fn execute_bid_with_other_decimals() {
+    let (mut context, mut contract) = setup_contract_with_decimals(8, 18);
+    submit_bid(
+        &mut context,
+        &mut contract,
+        "bidder",
+        D128::zero(),
+        10_000_000_000_000_000_000,
+    );
+
+    // 2 tokens of 8 decimals at 5 take the whole bid of 10 of 18 decimals
+    execute_bid(
+        &mut context,
+        &mut contract,
+        "custody",
+        "bidder",
+        200_000_000,
+    );
+
+    assert!(contract
+        .internal_get_bid(&AccountId::from("bidder"))
+        .is_none());
+    assert_eq!(
+        (
+            String::from("stable_coin"),
+            String::from("custody"),
+            9_900_000_000_000_000_000
+        ),
+        created_transfers()[1]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Insufficient bid balance; Required balance: 500000000")]
+fn execute_bid_over_bid_balance() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(
+        &mut context,
+        &mut contract,
+        "bidder",
+        D128::zero(),
+        400_000_000,
+    );
+
+    execute_bid(
+        &mut context,
+        &mut contract,
+        "custody",
+        "bidder",
+        100_000_000_000_000_000_000_000_000,
+    );
+}
+
+#[test]
+fn execute_bid_at_price_timeframe() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(
+        &mut context,
+        &mut contract,
+        "bidder",
+        D128::zero(),
+        1_000_000_000,
+    );
+
+    testing_env!(context.block_timestamp(60 * SECOND_TO_NANO).build());
+    execute_bid(
+        &mut context,
+        &mut contract,
+        "custody",
+        "bidder",
+        100_000_000_000_000_000_000_000_000,
+    );
+
+    assert_eq!(
+        U128(500_000_000),
+        contract
+            .internal_get_bid(&AccountId::from("bidder"))
+            .unwrap()
+            .amount
+    );
+}
+
+#[test]
+#[should_panic(expected = "Price is too old to execute bids")]
+fn execute_bid_with_stale_price() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(
+        &mut context,
+        &mut contract,
+        "bidder",
+        D128::zero(),
+        1_000_000_000,
+    );
+
+    testing_env!(context.block_timestamp(60 * SECOND_TO_NANO + 1).build());
+    execute_bid(
+        &mut context,
+        &mut contract,
+        "custody",
+        "bidder",
+        100_000_000_000_000_000_000_000_000,
+    );
+}
+
+#[test]
+fn price_status_of_stale_price() {
+    let (mut context, contract) = setup_contract();
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("alice").unwrap())
+        .block_timestamp(61 * SECOND_TO_NANO)
+        .build());
+    let price_status = contract.get_price_status();
+
+    assert_eq!(61, price_status.age);
+    assert!(!price_status.is_fresh);
+}
@@ -0,0 +1,113 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::Value;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::token_receiver::FungibleTokenReceiver;
+use crate::*;
+
+pub mod bid_ut;
+pub mod tests;
+pub mod upgrade_ut;
+
+/// a contract selling bNEAR of 24 decimals for a stable coin of 6 decimals, priced
+/// at 5 by a price a minute old at most
+pub(crate) fn setup_contract() -> (VMContextBuilder, Contract) {
+    setup_contract_with_decimals(24, 6)
+}
+
+pub(crate) fn setup_contract_with_decimals(
+    bnear_decimals: u8,
+    stable_coin_decimals: u8,
+) -> (VMContextBuilder, Contract) {
+    let mut context = VMContextBuilder::new();
+    testing_env!(context
+        .current_account_id(ValidAccountId::try_from("liquidation").unwrap())
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    let mut contract = Contract::new(
+        AccountId::from("owner"),
+        AccountId::from("bnear"),
+        bnear_decimals,
+        AccountId::from("stable_coin"),
+        stable_coin_decimals,
+        AccountId::from("requester"),
+        AccountId::from("oracle_payment_token"),
+        D128::new_exp(8, -1),
+        D128::new_exp(1, -2),
+        D128::new_exp(3, -1),
+        200,
+        60,
+    );
+    contract.last_price_response = PriceResponse {
+        price: D128::new_exp(5, 0),
+        last_updated_at: 0,
+    };
+
+    (context, contract)
+}
+
+/// bids `amount` of the stable coin with a premium rate of `premium_rate`
+pub(crate) fn submit_bid(
+    context: &mut VMContextBuilder,
+    contract: &mut Contract,
+    bidder: &str,
+    premium_rate: D128,
+    amount: u128,
+) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("stable_coin").unwrap())
+        .build());
+    contract.ft_on_transfer(
+        AccountId::from(bidder),
+        U128(amount),
+        format!(
+            "{{\"premium_rate\": {}}}",
+            serde_json::to_string(&premium_rate).unwrap()
+        ),
+    );
+}
+
+/// `sender` sells `amount` of bNEAR to the bid of `liquidator`
+pub(crate) fn execute_bid(
+    context: &mut VMContextBuilder,
+    contract: &mut Contract,
+    sender: &str,
+    liquidator: &str,
+    amount: u128,
+) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bnear").unwrap())
+        .build());
+    contract.ft_on_transfer(
+        AccountId::from(sender),
+        U128(amount),
+        format!("{{\"liquidator\": \"{}\"}}", liquidator),
+    );
+}
+
+/// token, receiver and amount of every transfer the contract made
+pub(crate) fn created_transfers() -> Vec<(String, String, u128)> {
+    let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+
+    let mut transfers = vec![];
+    for receipt in receipts.as_array().unwrap() {
+        for action in receipt["actions"].as_array().unwrap() {
+            let function_call = &action["FunctionCall"];
+            if function_call["method_name"] != "ft_transfer" {
+                continue;
+            }
+            let args: Value =
+                serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap();
+            transfers.push((
+                receipt["receiver_id"].as_str().unwrap().to_string(),
+                args["receiver_id"].as_str().unwrap().to_string(),
+                args["amount"].as_str().unwrap().parse().unwrap(),
+            ));
+        }
+    }
+
+    transfers
+}
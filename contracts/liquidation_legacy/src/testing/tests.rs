@@ -0,0 +1,43 @@
+use crate::math::{value_of, D128};
+
+#[test]
+fn value_of_bnear_in_stable_coin() {
+    // 1.5 bNEAR of 24 decimals at 5 are worth 7.5 of a stable coin of 6 decimals
+    assert_eq!(
+        7_500_000,
+        value_of(
+            1_500_000_000_000_000_000_000_000,
+            24,
+            D128::new_exp(5, 0),
+            6
+        )
+        .as_u128()
+    );
+}
+
+#[test]
+fn value_of_token_with_fewer_decimals_than_stable_coin() {
+    // 2 tokens of 8 decimals at 5 are worth 10 of a stable coin of 18 decimals
+    assert_eq!(
+        10_000_000_000_000_000_000,
+        value_of(200_000_000, 8, D128::new_exp(5, 0), 18).as_u128()
+    );
+}
+
+#[test]
+fn value_of_keeps_fraction_of_smallest_unit() {
+    // 1e-12 bNEAR at 5 is worth 5e-6 of the smallest unit of the stable coin
+    let value = value_of(1_000_000_000_000, 24, D128::new_exp(5, 0), 6);
+
+    assert_eq!(0, value.as_u128());
+    assert_eq!(D128::new_exp(5, -6), value);
+}
+
+#[test]
+fn value_of_same_decimals() {
+    // 0.5 of a token of 6 decimals at 0.5 are worth 0.25 of a stable coin of 6 decimals
+    assert_eq!(
+        250_000,
+        value_of(500_000, 6, D128::new_exp(5, -1), 6).as_u128()
+    );
+}
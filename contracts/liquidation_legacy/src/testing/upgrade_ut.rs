@@ -0,0 +1,121 @@
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use super::{execute_bid, submit_bid};
+use crate::upgrade::ContractV0;
+use crate::*;
+
+/// the deployed contract with a bid of 1000 at a premium rate of 5%
+fn setup_contract_v0() -> VMContextBuilder {
+    let mut context = VMContextBuilder::new();
+    testing_env!(context
+        .current_account_id(ValidAccountId::try_from("liquidation").unwrap())
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+
+    let mut bids: LookupMap<AccountId, Bid> = LookupMap::new(b"b".to_vec());
+    bids.insert(
+        &AccountId::from("bidder"),
+        &Bid {
+            amount: U128(1_000_000_000),
+            premium_rate: D128::new_exp(5, -2),
+        },
+    );
+
+    env::state_write(&ContractV0 {
+        owner: AccountId::from("owner"),
+        bnear_contract: AccountId::from("bnear"),
+        stable_coin_contract: AccountId::from("stable_coin"),
+        requester_contract: AccountId::from("requester"),
+        oracle_payment_token: AccountId::from("oracle_payment_token"),
+        safe_ratio: D128::new_exp(8, -1),
+        bid_fee: D128::new_exp(1, -2),
+        max_premium_rate: D128::new_exp(3, -1),
+        liquidation_threshold: 200,
+        bids,
+        last_price_response: PriceResponse {
+            price: D128::new_exp(5, 0),
+            last_updated_at: 0,
+        },
+    });
+
+    context
+}
+
+#[test]
+fn proper_migrate_from_v0() {
+    let mut context = setup_contract_v0();
+    let mut contract = Contract::migrate();
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(24, contract.bnear_decimals);
+    assert_eq!(6, contract.stable_coin_decimals);
+    assert_eq!(3600, contract.price_timeframe);
+    assert_eq!(D128::new_exp(5, 0), contract.get_price_status().price);
+
+    // the bid is kept and valued by the decimals of the deployed tokens
+    execute_bid(
+        &mut context,
+        &mut contract,
+        "custody",
+        "bidder",
+        100_000_000_000_000_000_000_000_000,
+    );
+    assert_eq!(
+        U128(525_000_000),
+        contract
+            .internal_get_bid(&AccountId::from("bidder"))
+            .unwrap()
+            .amount
+    );
+}
+
+#[test]
+#[should_panic(expected = "This method can only be called by owner")]
+fn migrate_only_owner() {
+    let mut context = setup_contract_v0();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("alice").unwrap())
+        .build());
+
+    Contract::migrate();
+}
+
+#[test]
+fn migrate_current_state() {
+    let mut context = setup_contract_v0();
+    let mut contract = Contract::migrate();
+    submit_bid(
+        &mut context,
+        &mut contract,
+        "alice",
+        D128::zero(),
+        500_000_000,
+    );
+    env::state_write(&contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    let contract = Contract::migrate();
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(
+        U128(500_000_000),
+        contract
+            .internal_get_bid(&AccountId::from("alice"))
+            .unwrap()
+            .amount
+    );
+    assert_eq!(
+        U128(1_000_000_000),
+        contract
+            .internal_get_bid(&AccountId::from("bidder"))
+            .unwrap()
+            .amount
+    );
+}
@@ -0,0 +1,116 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+// unit: seconds
+const DEFAULT_PRICE_TIMEFRAME: u64 = 3600;
+
+/// Layouts the contract state has been persisted with. The version is kept
+/// under `STATE_VERSION_KEY`; the deployment made before it existed is `V0`,
+/// converted into the current layout by `migrate`.
+pub enum VersionedContract {
+    V0(ContractV0),
+    V1(Contract),
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+    pub owner: AccountId,
+    pub bnear_contract: AccountId,
+    pub stable_coin_contract: AccountId,
+    pub requester_contract: AccountId,
+    pub oracle_payment_token: AccountId,
+    pub safe_ratio: D128,
+    pub bid_fee: D128,
+    pub max_premium_rate: D128,
+    pub liquidation_threshold: Balance,
+    pub bids: LookupMap<AccountId, Bid>,
+    pub last_price_response: PriceResponse,
+}
+
+impl From<ContractV0> for Contract {
+    fn from(old: ContractV0) -> Self {
+        Self {
+            owner: old.owner,
+            bnear_contract: old.bnear_contract,
+            bnear_decimals: 24,
+            stable_coin_contract: old.stable_coin_contract,
+            // the stable coin of the V0 contract was USDT
+            stable_coin_decimals: 6,
+            requester_contract: old.requester_contract,
+            oracle_payment_token: old.oracle_payment_token,
+            safe_ratio: old.safe_ratio,
+            bid_fee: old.bid_fee,
+            max_premium_rate: old.max_premium_rate,
+            liquidation_threshold: old.liquidation_threshold,
+            // the V0 contract never checked the age of its price
+            price_timeframe: DEFAULT_PRICE_TIMEFRAME,
+            bids: old.bids,
+            last_price_response: old.last_price_response,
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_read_state_version() -> u8 {
+        env::storage_read(STATE_VERSION_KEY)
+            .map(|value| value[0])
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+        match Self::internal_read_state_version() {
+            0 => VersionedContract::V0(env::state_read().expect("Failed to read the V0 state")),
+            CURRENT_STATE_VERSION => {
+                VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+            }
+            _ => env::panic(b"Unknown state version"),
+        }
+    }
+
+    pub(crate) fn internal_write_state_version() {
+        env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the stored state into the current layout. Called by `upgrade`
+    /// right after the new code is deployed, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let contract: Contract = match Self::internal_read_versioned_state() {
+            VersionedContract::V0(old) => old.into(),
+            VersionedContract::V1(contract) => contract,
+        };
+
+        if env::predecessor_account_id() != env::current_account_id() {
+            contract.assert_owner();
+        }
+
+        Self::internal_write_state_version();
+
+        contract
+    }
+
+    /// Deploys the wasm blob attached as the call input and migrates the state.
+    pub fn upgrade(&self) -> Promise {
+        self.assert_owner();
+
+        let code = env::input().expect("Error: No code attached");
+        let migrate_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+            .expect("Not enough gas attached to migrate after the upgrade");
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), b"{}".to_vec(), 0, migrate_gas)
+    }
+
+    pub fn get_state_version(&self) -> u8 {
+        Self::internal_read_state_version()
+    }
+}
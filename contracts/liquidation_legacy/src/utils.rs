@@ -24,7 +24,7 @@ pub trait FungibleToken {
 
 #[ext_contract(requester)]
 pub trait RequesterContract {
-    fn get_data_request(&self, request_id: ValidAccountId) -> Option<DataRequestDetails>;
+    fn get_data_request(&self, request_id: String) -> Option<DataRequestDetails>;
 }
 
 #[ext_contract(ext_self)]
@@ -70,8 +70,15 @@ pub fn fungible_token_transfer_call(
 impl Contract {
     #[private]
     pub fn callback_get_price_response(&mut self, #[callback] result: Option<DataRequestDetails>) {
-        let result: DataRequestDetails = result.expect("ERR: There is no response.");
-        
+        // the first request, or one the requester stored before requests were kept per asset
+        let result: DataRequestDetails = match result {
+            Some(result) => result,
+            None => {
+                self.internal_create_new_price_request();
+                return;
+            }
+        };
+
         let status: RequestStatus = result.status;
 
         if let RequestStatus::Finalized(outcome) = status {
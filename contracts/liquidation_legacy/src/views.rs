@@ -2,4 +2,14 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
+    pub fn get_price_status(&self) -> PriceStatus {
+        let age: u64 = env::block_timestamp().saturating_sub(self.last_price_response.last_updated_at);
+
+        PriceStatus {
+            price: self.last_price_response.price,
+            last_updated_at: self.last_price_response.last_updated_at,
+            age: age / SECOND_TO_NANO,
+            is_fresh: age <= self.price_timeframe * SECOND_TO_NANO,
+        }
+    }
 }
\ No newline at end of file
@@ -2,31 +2,34 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-    /// Overseer executes the liquidation providing a whitelisted collateral(bNEAR).
+    /// Overseer executes the liquidation providing a registered collateral.
     /// This operation returns a repay_amount based on the available bids on each
-    /// premium slot, consuming bids from lowest to higher premium slots
+    /// premium slot of the collateral, consuming bids from lowest to higher premium slots
     pub(crate) fn on_receive_execute_liquidation(
         &mut self,
+        collateral_token: AccountId,
         sender: AccountId,
         liquidator: AccountId,
         repay_address: AccountId,
         fee_address: AccountId,
+        repay_msg: Option<String>,
+        refund_address: Option<AccountId>,
         amount: U128,
     ) {
-        self.internal_update_price_response();
-
         let config: Config = self.config.clone();
-        let collateral_info: CollateralInfo = config.collateral_info;
-        let available_bids: u128 = self.total_bids.0;
+        let collateral_info: CollateralInfo = self.internal_read_collateral_info(&collateral_token);
+        let available_bids: u128 = self.internal_read_total_bids(&collateral_token).0;
+        let price_response: PriceResponse = self.internal_read_price_response(&collateral_token);
 
         // only collateral token custody can execute liquidations
-        assert_eq!(config.custody_contract, sender, "Unauthorized: only custody contract can execute liquidations");
+        assert_eq!(collateral_info.custody_contract, sender, "Unauthorized: only custody contract can execute liquidations");
+        assert_price_fresh(&price_response, config.price_timeframe);
 
         let mut remaining_collateral_to_liquidate: u128 = amount.0;
         let mut repay_amount: u128 = 0;
         let mut filled: bool = false;
-        for slot in 0..collateral_info.max_slot + 1 {
-            let mut bid_pool: BidPool = match self.interanl_read_bid_pool(slot) {
+        for slot in 0..=collateral_info.max_slot {
+            let mut bid_pool: BidPool = match self.interanl_read_bid_pool(&collateral_token, slot) {
                 Some(bid_pool) => bid_pool,
                 None => continue,
             };
@@ -35,14 +38,16 @@ impl Contract {
             };
 
             let (pool_repay_amount, pool_liquidated_collateral) = self.internal_execute_pool_liquidation(
+                &collateral_token,
+                &collateral_info,
                 &mut bid_pool,
                 slot,
                 remaining_collateral_to_liquidate,
-                self.last_price_response.price,
+                price_response.price,
                 &mut filled,
             );
 
-            self.internal_store_bid_pool(slot, &bid_pool);
+            self.internal_store_bid_pool(&collateral_token, slot, &bid_pool);
 
             repay_amount += pool_repay_amount;
 
@@ -56,17 +61,40 @@ impl Contract {
 
         assert_eq!(remaining_collateral_to_liquidate, 0, "Not enough bids to execute this liquidation");
 
-        self.total_bids = (available_bids - repay_amount).into();
+        self.internal_store_total_bids(&collateral_token, (available_bids - repay_amount).into());
 
         let bid_fee: D128 = repay_amount * config.bid_fee;
         let liquidator_fee: D128 = repay_amount * config.liquidator_fee;
         let repay_amount: D128 = repay_amount - bid_fee - liquidator_fee;
         
-        fungible_token_transfer(
-            config.stable_coin_contract.clone(), 
-            repay_address, 
-            repay_amount.as_u128()
-        );
+        if let Some(repay_msg) = repay_msg {
+            let repay_gas = env::prepaid_gas()
+                .checked_sub(env::used_gas() + GAS_FOR_LIQUIDATION_RESERVE)
+                .expect("Not enough gas to repay the liquidation");
+            let repay = fungible_token_transfer_call(
+                config.stable_coin_contract.clone(),
+                repay_address,
+                repay_amount.as_u128(),
+                repay_msg,
+                repay_gas,
+            );
+            // proceeds exceeding the loan would otherwise be refunded to this contract
+            if let Some(refund_address) = refund_address {
+                repay.then(ext_self::callback_refund_liquidation(
+                    refund_address,
+                    U128(repay_amount.as_u128()),
+                    &env::current_account_id(),
+                    0,
+                    GAS_FOR_REFUND,
+                ));
+            }
+        } else {
+            fungible_token_transfer(
+                config.stable_coin_contract.clone(), 
+                repay_address, 
+                repay_amount.as_u128()
+            );
+        }
 
         if bid_fee != D128::zero() {
             fungible_token_transfer(
@@ -88,20 +116,25 @@ impl Contract {
     /// callback on transfer stable coin.
     /// Stable asset is submitted to create a bid record. If available bids for the collateral is under
     /// the threshold, the bid is activated. Bids are not used for liquidations until activated
-    pub(crate) fn on_receive_submit_bid(&mut self, bidder: AccountId, premium_slot: u8, amount: U128) {
-        self.internal_update_price_response();
-        
+    pub(crate) fn on_receive_submit_bid(
+        &mut self,
+        bidder: AccountId,
+        collateral_token: AccountId,
+        premium_slot: u8,
+        amount: U128,
+    ) {
         let config = self.config.clone();
-        let collateral_info = config.collateral_info;
+        let collateral_info: CollateralInfo = self.internal_read_collateral_info(&collateral_token);
 
         // read or create bid_pool, make sure slot is valid
         let mut bid_pool: BidPool =
-            self.internal_read_or_create_bid_pool(&collateral_info, premium_slot);
+            self.internal_read_or_create_bid_pool(&collateral_token, &collateral_info, premium_slot);
 
         // create bid object
         let bid_idx: u128 = self.internal_pop_bid_idx();
         let mut bid = Bid {
             idx: bid_idx.into(),
+            collateral_token: collateral_token.clone(),
             premium_slot,
             bidder,
             amount,
@@ -114,16 +147,16 @@ impl Contract {
         };
 
         // if available bids is lower than bid_threshold, directly activate bid
-        let available_bids: U128 = self.total_bids;
+        let available_bids: U128 = self.internal_read_total_bids(&collateral_token);
         if available_bids.0 < collateral_info.bid_threshold.0 {
             // update bid and bid pool, add new share and pool indexes to bid
             process_bid_activation(&mut bid, &mut bid_pool, amount);
 
             // store bid_pool
-            self.internal_store_bid_pool(premium_slot, &bid_pool);
+            self.internal_store_bid_pool(&collateral_token, premium_slot, &bid_pool);
 
             // increase total bid amount
-            self.total_bids = (available_bids.0 + amount.0).into();
+            self.internal_store_total_bids(&collateral_token, (available_bids.0 + amount.0).into());
         } else {
             // calculate wait_end from current time
             bid.wait_end = Some(((env::block_timestamp() + (config.waiting_period * SECOND_TO_NANO)) / SECOND_TO_NANO).into());
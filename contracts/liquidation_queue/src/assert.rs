@@ -12,10 +12,20 @@ impl Contract{
     }
 }
 
+pub fn assert_price_fresh(price_response: &PriceResponse, price_timeframe: u64) {
+    if price_response.last_updated_at + price_timeframe * SECOND_TO_NANO < env::block_timestamp() {
+        panic!("Price is too old to execute liquidations");
+    }
+}
+
 pub fn assert_fees(fees: D128) {
     assert!(fees > D128::one(), "The sum of bid_fee and liquidator_fee can not be greater than one");
 }
 
+pub fn assert_decimals(decimals: u8) {
+    assert!(decimals <= 24, "Decimals must not exceed 24");
+}
+
 pub fn assert_activate_status(bid: &Bid, available_bids: U128, bid_threshold: U128) -> Result<(), String> {
     match bid.wait_end {
         Some(wait_end) => {
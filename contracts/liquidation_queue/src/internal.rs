@@ -2,38 +2,100 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-    /// updates price response at every function call
-    pub(crate) fn internal_update_price_response(
-        &mut self,
-    ) -> Promise {
+    /// reads the request of a collateral, kept by the requester contract under
+    /// `queue/collateral`, and stores its price once finalized. The callback gets
+    /// the gas left to pay the next request of the collateral
+    pub(crate) fn internal_update_price_response(&mut self, collateral_token: AccountId) -> Promise {
+        let callback_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_PRICE_QUERY + GAS_FOR_PRICE_UPDATE)
+            .filter(|gas| *gas >= DR_NEW_GAS + GAS_FOR_PRICE_UPDATE)
+            .expect("Not enough gas to request the next price");
+
         requester::get_data_request(
-            env::current_account_id().try_into().unwrap(),
+            format!("{}/{}", env::current_account_id(), collateral_token),
             // Near params
             &self.config.requester_contract,
             0,
-            3_000_000_000_000,
+            GAS_FOR_PRICE_QUERY,
         ).then(ext_self::callback_get_price_response(
+            collateral_token,
             // Near params
             &env::current_account_id(),
             0,
-            30_000_000_000_000,
+            callback_gas,
         ))
     }
 
-    pub(crate) fn internal_create_new_price_request(&self) {
+    /// requests the next price of a collateral from its feed
+    pub(crate) fn internal_create_new_price_request(&self, collateral_token: &AccountId) {
+        let collateral_info: CollateralInfo = self.internal_read_collateral_info(collateral_token);
+
         fungible_token_transfer_call(
             self.config.oracle_payment_token.clone(), 
             self.config.requester_contract.clone(), 
             1_000_000_000_000_000_000_000_000, 
-            // query NEAR price
-            format!("{{\"sources\": [{{ \"end_point\": \"https://api.coingecko.com/api/v3/simple/price?ids=tether%2Cnear&vs_currencies=usd\", \"source_path\":\"near.usd\"}}], \"tags\":[\"pricing\",\"near\"],  \"challenge_period\":\"120000000000\", \"settlement_time\":\"1\", \"data_type\":{{\"Number\":\"{}\"}}, \"creator\":\"{}\"}}", DECIMAL, env::current_account_id())
+            format!("{{\"sources\": [{{ \"end_point\": \"{}\", \"source_path\":\"{}\"}}], \"tags\":[\"pricing\",\"{}\"],  \"challenge_period\":\"120000000000\", \"settlement_time\":\"1\", \"data_type\":{{\"Number\":\"{}\"}}, \"creator\":\"{}\"}}", collateral_info.price_feed.end_point, collateral_info.price_feed.source_path, collateral_token, DECIMAL, env::current_account_id()),
+            DR_NEW_GAS,
         );
     }
 
+    /// Premium the active bids would take to buy the whole collateral amount, consuming
+    /// the bid pools from the lowest premium slot. The part no bid covers is priced
+    /// at the premium of the highest slot
+    pub(crate) fn internal_expected_premium_rate(
+        &self,
+        collateral_token: &AccountId,
+        collateral_amount: u128,
+        price: D128,
+    ) -> D128 {
+        let collateral_info: CollateralInfo = self.internal_read_collateral_info(collateral_token);
+
+        if collateral_amount == 0 {
+            return D128::zero();
+        }
+
+        let mut remaining_collateral: u128 = collateral_amount;
+        // sum of the collateral bought by each pool times its premium rate
+        let mut weighted_premium: u128 = 0;
+        for slot in 0..=collateral_info.max_slot {
+            let bid_pool: BidPool = match self.interanl_read_bid_pool(collateral_token, slot) {
+                Some(bid_pool) => bid_pool,
+                None => continue,
+            };
+            if bid_pool.total_bid_amount.0 == 0 {
+                continue;
+            }
+
+            let premium_price: D128 = price * (D128::one() - bid_pool.premium_rate);
+            let pool_collateral: u128 = amount_of(
+                D128::new(bid_pool.total_bid_amount.0 * DECIMAL),
+                self.config.stable_coin_decimals,
+                premium_price,
+                collateral_info.decimals,
+            ).as_u128().min(remaining_collateral);
+
+            weighted_premium += bid_pool.premium_rate.mul_int(pool_collateral);
+            remaining_collateral -= pool_collateral;
+
+            if remaining_collateral == 0 {
+                break;
+            }
+        }
+
+        let max_premium_rate: D128 = collateral_info.premium_rate(collateral_info.max_slot);
+        weighted_premium += max_premium_rate.mul_int(remaining_collateral);
+
+        D128::new(
+            (U256::from(weighted_premium) * U256::from(DECIMAL) / U256::from(collateral_amount)).as_u128()
+        )
+    }
+
     /// On each collateral execution the product_snapshot and sum_snapshot are updated
     /// to track the expense and reward distribution for biders in the pool
     pub(crate) fn internal_execute_pool_liquidation(
         &mut self, 
+        collateral_token: &AccountId,
+        collateral_info: &CollateralInfo,
         bid_pool: &mut BidPool,
         premium_slot: u8,
         collateral_to_liquidate: u128,
@@ -42,11 +104,21 @@ impl Contract {
     ) -> (u128, u128) {
         let premium_price: D128 = price * (D128::one() - bid_pool.premium_rate);
         let mut pool_collateral_to_liquidate: u128 = collateral_to_liquidate;
-        let mut pool_required_stable: D128 = pool_collateral_to_liquidate * premium_price;
+        let mut pool_required_stable: D128 = value_of(
+            pool_collateral_to_liquidate,
+            collateral_info.decimals,
+            premium_price,
+            self.config.stable_coin_decimals,
+        );
 
         if pool_required_stable > D128::new(bid_pool.total_bid_amount.0 * DECIMAL) {
             pool_required_stable = D128::new(bid_pool.total_bid_amount.0 * DECIMAL);
-            pool_collateral_to_liquidate = (pool_required_stable / premium_price).as_u128();
+            pool_collateral_to_liquidate = amount_of(
+                pool_required_stable,
+                self.config.stable_coin_decimals,
+                premium_price,
+                collateral_info.decimals,
+            ).as_u128();
         } else {
             *filled = true;
         }
@@ -69,6 +141,7 @@ impl Contract {
 
         // save reward sum for current epoch and scale
         self.internal_store_epoch_scale_sum(
+            collateral_token,
             premium_slot,
             bid_pool.current_epoch,
             bid_pool.current_scale,
@@ -133,15 +206,19 @@ impl Contract {
     }
 
     pub(crate) fn internal_calculate_liquidated_collateral(&self, bid: &Bid) -> (U128, D128) {
+        // no sum is stored for a scale until a liquidation happens at it, the sum is
+        // then still the one the bid took its snapshot of
         let reference_sum_snapshot: D128 = self.internal_read_epoch_scale_sum(
+            &bid.collateral_token,
             bid.premium_slot,
             bid.epoch_snapshot,
             bid.scale_snapshot,
-        ).unwrap_or(D128::zero());
+        ).unwrap_or(bid.sum_snapshot);
 
         // reward = reward from first scale + reward from second scale (if any)
         let first_portion = reference_sum_snapshot - bid.sum_snapshot;
         let second_portion: D128 = if let Some(second_scale_sum_snapshot) = self.internal_read_epoch_scale_sum(
+            &bid.collateral_token,
             bid.premium_slot,
             bid.epoch_snapshot,
             (bid.scale_snapshot.0 + 1).into()
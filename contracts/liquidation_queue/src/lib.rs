@@ -2,11 +2,12 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Serialize, Deserialize};
 use near_sdk::json_types::{ValidAccountId, U64, U128};
 use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::{env, near_bindgen, serde_json, assert_one_yocto, BorshStorageKey, AccountId, Balance, PanicOnDefault, PromiseOrValue, Promise};
-use math::{D128, DECIMAL};
+use near_sdk::{env, near_bindgen, serde_json, assert_one_yocto, BorshStorageKey, AccountId, Balance, Gas, PanicOnDefault, PromiseOrValue, Promise};
+use math::{amount_of, value_of, D128, DECIMAL, U256};
+use upgrade::BidV0;
+use flux_sdk::consts::{DR_NEW_GAS, GAS_BASE_TRANSFER};
 use utils::{fungible_token_transfer, fungible_token_transfer_call, requester, ext_self};
 use assert::*;
-use std::convert::TryInto;
 
 mod api;
 mod assert;
@@ -14,12 +15,32 @@ mod internal;
 mod math;
 mod owner;
 mod state;
+#[cfg(test)]
+mod testing;
 mod token_receiver;
+mod upgrade;
 mod utils;
 mod views;
 
 const SECOND_TO_NANO: u64 = 1_000_000_000;
 
+// decimals of the loan and collateral values the overseer asks liquidation amounts for
+const VALUE_DECIMALS: u8 = 6;
+
+// the refund of the liquidation proceeds the repay address did not use
+const GAS_FOR_REFUND: Gas = 10_000_000_000_000;
+
+// kept by a liquidation for its receipts, the fee transfers and the refund, the
+// repayment gets the rest of the gas
+const GAS_FOR_LIQUIDATION_RESERVE: Gas = 30_000_000_000_000 + 3 * GAS_BASE_TRANSFER + GAS_FOR_REFUND;
+
+// the pending request of a collateral read from the requester contract
+const GAS_FOR_PRICE_QUERY: Gas = 10_000_000_000_000;
+
+// kept by the price update and its callback for their receipts, the callback also
+// pays the next request
+const GAS_FOR_PRICE_UPDATE: Gas = 25_000_000_000_000;
+
 #[derive(BorshStorageKey, BorshSerialize)]
 pub enum StorageKeys {
     Indexer,
@@ -27,6 +48,11 @@ pub enum StorageKeys {
     EpochScaleSum,
     Account { account_hash: Vec<u8> },
     BidPools,
+    CollateralInfos,
+    CollateralBids,
+    CollateralEpochScaleSum,
+    TotalBids,
+    PriceResponses,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -36,6 +62,16 @@ pub struct PriceResponse {
     pub last_updated_at: u64,
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceStatus {
+    pub price: D128,
+    pub last_updated_at: u64,
+    // unit: seconds
+    pub age: u64,
+    pub is_fresh: bool,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct BidPool {
@@ -53,6 +89,7 @@ pub struct BidPool {
 #[serde(crate = "near_sdk::serde")]
 pub struct Bid {
     pub idx: U128,
+    pub collateral_token: AccountId,
     pub premium_slot: u8,
     pub bidder: AccountId,
     // amount of USDT (decimal: 6)
@@ -66,13 +103,42 @@ pub struct Bid {
     pub scale_snapshot: U128,
 }
 
+/// What a bid would give back if it were retracted and claimed now
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimableResponse {
+    pub bid_idx: U128,
+    pub collateral_token: AccountId,
+    // amount of the stable coin left in the bid
+    pub remaining_bid: U128,
+    pub liquidated_collateral: U128,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct CollateralInfo {
-    pub bnear_contract: AccountId,
+    // the only account allowed to liquidate the collateral
+    pub custody_contract: AccountId,
+    pub decimals: u8,
     pub bid_threshold: U128,
     pub max_slot: u8,
     pub premium_rate_per_slot: D128,
+    pub price_feed: PriceFeed,
+}
+
+impl CollateralInfo {
+    /// premium of the bids of a slot, `premium_rate_per_slot` per slot above the first
+    pub fn premium_rate(&self, premium_slot: u8) -> D128 {
+        self.premium_rate_per_slot * D128::new(premium_slot as u128 * DECIMAL)
+    }
+}
+
+/// Oracle source the price of a collateral is requested from
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceFeed {
+    pub end_point: String,
+    pub source_path: String,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -80,31 +146,39 @@ pub struct CollateralInfo {
 pub struct Config {
     pub owner: AccountId,
     pub stable_coin_contract: AccountId,
+    pub stable_coin_decimals: u8,
     pub requester_contract: AccountId,
     pub oracle_payment_token: AccountId,
     pub overseer_contract: AccountId,
-    pub custody_contract: AccountId,
     pub safe_ratio: D128,
     pub bid_fee: D128,
     pub liquidator_fee: D128,
     pub liquidation_threshold: Balance,
     // unit: seconds
     pub waiting_period: u64,
-    pub collateral_info: CollateralInfo,
+    // unit: seconds
+    pub price_timeframe: u64,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     config: Config,
+    collateral_infos: UnorderedMap<AccountId, CollateralInfo>,
     bids_indexer_by_user: LookupMap<AccountId, UnorderedSet<U128>>,
     bids: LookupMap<U128, Bid>,
-    bid_pools: UnorderedMap<u8, BidPool>,
-    // premium_slot, epoch, scale => sum
-    epoch_scale_sum: LookupMap<(u8, U128, U128), D128>,
+    // collateral_token, premium_slot => pool
+    bid_pools: UnorderedMap<(AccountId, u8), BidPool>,
+    // collateral_token, premium_slot, epoch, scale => sum
+    epoch_scale_sum: LookupMap<(AccountId, u8, U128, U128), D128>,
     bid_idx: U128,
-    total_bids: U128,
-    last_price_response: PriceResponse,
+    // collateral_token => amount of the active bids
+    total_bids: LookupMap<AccountId, U128>,
+    price_responses: LookupMap<AccountId, PriceResponse>,
+    // the bids and sums of the single collateral queue, read until they are stored again
+    legacy_collateral: Option<AccountId>,
+    legacy_bids: LookupMap<U128, BidV0>,
+    legacy_epoch_scale_sum: LookupMap<(u8, U128, U128), D128>,
 }
 
 #[near_bindgen]
@@ -113,55 +187,58 @@ impl Contract {
     pub fn new(
         owner: ValidAccountId,
         stable_coin_contract: ValidAccountId,
+        stable_coin_decimals: u8,
         requester_contract: ValidAccountId,
         oracle_payment_token: ValidAccountId,
         overseer_contract: ValidAccountId,
-        custody_contract: ValidAccountId,
         safe_ratio: D128,
         bid_fee: D128,
         liquidator_fee: D128,
         liquidation_threshold: Balance,
         waiting_period: U64,
-        collateral_info: CollateralInfo,
+        price_timeframe: U64,
     ) -> Self {
-        let mut instance = Self{
+        assert_decimals(stable_coin_decimals);
+        Self::internal_write_state_version();
+
+        Self{
             config: Config {
                 owner: owner.into(),
                 stable_coin_contract: stable_coin_contract.into(),
+                stable_coin_decimals,
                 requester_contract: requester_contract.into(),
                 oracle_payment_token: oracle_payment_token.into(),
                 overseer_contract: overseer_contract.into(),
-                custody_contract: custody_contract.into(),
                 safe_ratio,
                 bid_fee,
                 liquidator_fee,
                 liquidation_threshold,
                 waiting_period: waiting_period.into(),
-                collateral_info
+                price_timeframe: price_timeframe.into(),
             },
+            collateral_infos: UnorderedMap::new(StorageKeys::CollateralInfos),
             bids_indexer_by_user: LookupMap::new(StorageKeys::Indexer),
-            bids: LookupMap::new(StorageKeys::Bids),
+            bids: LookupMap::new(StorageKeys::CollateralBids),
             bid_pools: UnorderedMap::new(StorageKeys::BidPools),
-            epoch_scale_sum: LookupMap::new(StorageKeys::EpochScaleSum),
+            epoch_scale_sum: LookupMap::new(StorageKeys::CollateralEpochScaleSum),
             bid_idx: U128(1),
-            total_bids: U128(0),
-            last_price_response: PriceResponse{price: D128::one(), last_updated_at: env::block_timestamp()},
-        };
-        // Updates initial price
-        instance.internal_update_price_response();
-
-        instance
+            total_bids: LookupMap::new(StorageKeys::TotalBids),
+            price_responses: LookupMap::new(StorageKeys::PriceResponses),
+            legacy_collateral: None,
+            legacy_bids: LookupMap::new(StorageKeys::Bids),
+            legacy_epoch_scale_sum: LookupMap::new(StorageKeys::EpochScaleSum),
+        }
     }
 
     /// After bids are submitted, need to execute the activation after wait_period expires
     /// Bids are not used for liquidations until activated
     #[payable]
-    pub fn activate_bids(&mut self, bids_idx: Option<Vec<U128>>) {
+    pub fn activate_bids(&mut self, collateral_token: AccountId, bids_idx: Option<Vec<U128>>) {
         assert_one_yocto();
-        self.internal_update_price_response();
 
         let bidder: AccountId = env::predecessor_account_id();
-        let mut available_bids: U128 = self.total_bids;
+        let collateral_info: CollateralInfo = self.internal_read_collateral_info(&collateral_token);
+        let mut available_bids: U128 = self.internal_read_total_bids(&collateral_token);
 
         let bids: Vec<Bid> = if let Some(bids_idx) = &bids_idx {
             bids_idx
@@ -169,7 +246,7 @@ impl Contract {
                 .map(|idx| self.internal_read_bid(*idx))
                 .collect::<Vec<Bid>>()
         } else {
-            self.internal_read_bids_by_user(&bidder, None, None)
+            self.internal_read_bids_by_user(&bidder, Some(&collateral_token), None, None)
                 .into_iter()
                 .filter(|bid| bid.wait_end.is_some())
                 .collect::<Vec<Bid>>()
@@ -180,14 +257,15 @@ impl Contract {
             if bid.bidder != bidder {
                 panic!("unauthorized");
             }
-            let mut bid_pool: BidPool = self.interanl_read_bid_pool(bid.premium_slot)
+            assert_eq!(bid.collateral_token, collateral_token, "The bid is for another collateral");
+            let mut bid_pool: BidPool = self.interanl_read_bid_pool(&collateral_token, bid.premium_slot)
                 .expect("No bids with the specified information exist");
             
             let amount_to_activate: U128 = bid.amount;
 
             // assert that the bid is inactive and wait period has expired
             if let Err(err_msg) = 
-                assert_activate_status(&bid, available_bids, self.config.collateral_info.bid_threshold)
+                assert_activate_status(&bid, available_bids, collateral_info.bid_threshold)
             {
                 if bids_idx.is_some() {
                     // if the user provided the idx to activate, we should return error to notify the user
@@ -203,20 +281,19 @@ impl Contract {
 
             // save to storage
             self.internal_store_bid(bid.idx, &bid);
-            self.internal_store_bid_pool(bid.premium_slot, &bid_pool);
+            self.internal_store_bid_pool(&collateral_token, bid.premium_slot, &bid_pool);
 
             total_activated_amount = (total_activated_amount.0 + amount_to_activate.0).into();
             available_bids = (available_bids.0 + amount_to_activate.0).into();
         }
 
-        self.total_bids = available_bids;
+        self.internal_store_total_bids(&collateral_token, available_bids);
     }
 
     /// Bid owners can withdraw the ramaning bid amount at any time
     #[payable]
     pub fn retract_bid(&mut self, bid_idx: U128, amount: Option<U128>) {
         assert_one_yocto();
-        self.internal_update_price_response();
         
         let bidder: AccountId = env::predecessor_account_id();
         let mut bid: Bid = self.internal_read_bid(bid_idx);
@@ -236,9 +313,9 @@ impl Contract {
 
             waiting_withdraw_amount
         } else {
-            let available_bids: U128 = self.total_bids;
+            let available_bids: U128 = self.internal_read_total_bids(&bid.collateral_token);
             let mut bid_pool: BidPool =
-                self.interanl_read_bid_pool(bid.premium_slot)
+                self.interanl_read_bid_pool(&bid.collateral_token, bid.premium_slot)
                     .expect("No bid pool with the specified information exist");
             
             // calculate spent and reward until this moment
@@ -257,6 +334,7 @@ impl Contract {
             // check requested amount
             let withdraw_amount: U128 = assert_withdraw_amount(amount, withdrawable_amount);
 
+            let collateral_token: AccountId = bid.collateral_token.clone();
             // remove or update bid
             if withdraw_amount.0 == withdrawable_amount.0 && bid.pending_liquidated_collateral.0 == 0 {
                 self.internal_remove_bid(bid.idx);
@@ -280,10 +358,11 @@ impl Contract {
             let refund_amount: u128 = withdraw_amount.0 + self.internal_claim_bid_residue(&mut bid_pool);
 
             self.internal_store_bid_pool(
+                &collateral_token,
                 bid.premium_slot,
                 &bid_pool,
             );
-            self.total_bids = (available_bids.0 - withdraw_amount.0).into();
+            self.internal_store_total_bids(&collateral_token, (available_bids.0 - withdraw_amount.0).into());
 
             refund_amount.into()
         };
@@ -291,12 +370,21 @@ impl Contract {
         fungible_token_transfer(self.config.stable_coin_contract.clone(), bidder, withdraw_amount.0);
     }
 
+    /// Stores the price of a collateral once its request is finalized and requests
+    /// the next one. Anyone can refresh a price, each collateral keeps one request
+    #[payable]
+    pub fn update_price(&mut self, collateral_token: AccountId) -> Promise {
+        assert_one_yocto();
+        self.internal_read_collateral_info(&collateral_token);
+
+        self.internal_update_price_response(collateral_token)
+    }
+
     /// Bid owner can claim their share of the liquidated collateral until the
     /// bid is consumed  
     #[payable]   
-    pub fn claim_liquidations(&mut self, bids_idx: Option<Vec<U128>>) {
+    pub fn claim_liquidations(&mut self, collateral_token: AccountId, bids_idx: Option<Vec<U128>>) {
         assert_one_yocto();
-        self.internal_update_price_response();
         
         let bidder: AccountId = env::predecessor_account_id();
 
@@ -306,12 +394,13 @@ impl Contract {
                 .map(|idx| self.internal_read_bid(*idx))
                 .collect::<Vec<Bid>>()
         } else {
-            self.internal_read_bids_by_user(&bidder, None, None)
+            self.internal_read_bids_by_user(&bidder, Some(&collateral_token), None, None)
         };
 
         let mut claim_amount: u128 = 0;
         for bid in bids.into_iter() {
             assert_eq!(bid.bidder, bidder, "unauthorized");
+            assert_eq!(bid.collateral_token, collateral_token, "The bid is for another collateral");
 
             if bid.wait_end.is_some() {
                 // bid not activated
@@ -319,7 +408,7 @@ impl Contract {
             }
 
             let mut bid_pool: BidPool =
-                self.interanl_read_bid_pool(bid.premium_slot)
+                self.interanl_read_bid_pool(&collateral_token, bid.premium_slot)
                     .expect("No bid pool with the specified information exist");
             
             // calculate remaining bid amount
@@ -340,7 +429,7 @@ impl Contract {
                 + self.internal_claim_col_residue(&mut bid_pool);
             
             // store bid_pool to update residue
-            self.internal_store_bid_pool(bid.premium_slot, &bid_pool);
+            self.internal_store_bid_pool(&collateral_token, bid.premium_slot, &bid_pool);
 
             // check if bid has been consumed, include 1 for rounding
             if remaining_bid.0 <= 1 {
@@ -361,7 +450,7 @@ impl Contract {
         }
         if claim_amount != 0 {
             fungible_token_transfer(
-                self.config.collateral_info.bnear_contract.clone(), 
+                collateral_token, 
                 bidder, 
                 claim_amount
             );
@@ -7,20 +7,19 @@ impl Contract {
         &mut self,
         owner: Option<ValidAccountId>,
         stable_coin_contract: Option<ValidAccountId>,
+        stable_coin_decimals: Option<u8>,
         requester_contract: Option<ValidAccountId>,
         oracle_payment_token: Option<ValidAccountId>,
         overseer_contract: Option<ValidAccountId>,
-        custody_contract: Option<ValidAccountId>,
         safe_ratio: Option<D128>,
         bid_fee: Option<D128>,
         liquidator_fee: Option<D128>,
         liquidation_threshold: Option<Balance>,
         waiting_period: Option<U64>,
-        collateral_info: Option<CollateralInfo>,
+        price_timeframe: Option<U64>,
     ) {
         self.assert_owner();
         assert_one_yocto();
-        self.internal_update_price_response();
 
         if let Some(owner) = owner {
             self.config.owner = owner.into();
@@ -30,6 +29,11 @@ impl Contract {
             self.config.stable_coin_contract = stable_coin_contract.into();
         }
 
+        if let Some(stable_coin_decimals) = stable_coin_decimals {
+            assert_decimals(stable_coin_decimals);
+            self.config.stable_coin_decimals = stable_coin_decimals;
+        }
+
         if let Some(requester_contract) = requester_contract {
             self.config.requester_contract = requester_contract.into();
         }
@@ -42,10 +46,6 @@ impl Contract {
             self.config.overseer_contract = overseer_contract.into();
         }
 
-        if let Some(custody_contract) = custody_contract {
-            self.config.custody_contract = custody_contract.into();
-        }
-
         if let Some(safe_ratio) = safe_ratio {
             self.config.safe_ratio = safe_ratio;
         }
@@ -68,8 +68,83 @@ impl Contract {
             self.config.waiting_period = waiting_period.into();
         }
 
-        if let Some(collateral_info) = collateral_info {
-            self.config.collateral_info = collateral_info;
+        if let Some(price_timeframe) = price_timeframe {
+            self.config.price_timeframe = price_timeframe.into();
         }
     }
+
+    /// Opens bid pools for a collateral, liquidated by its custody
+    #[payable]
+    pub fn register_collateral(
+        &mut self,
+        collateral_token: ValidAccountId,
+        custody_contract: ValidAccountId,
+        decimals: u8,
+        bid_threshold: U128,
+        max_slot: u8,
+        premium_rate_per_slot: D128,
+        price_feed: PriceFeed,
+    ) {
+        self.assert_owner();
+        assert_one_yocto();
+        assert_decimals(decimals);
+
+        let collateral_token: AccountId = collateral_token.into();
+        assert!(
+            self.collateral_infos.get(&collateral_token).is_none(),
+            "Collateral already registered"
+        );
+
+        self.collateral_infos.insert(
+            &collateral_token,
+            &CollateralInfo {
+                custody_contract: custody_contract.into(),
+                decimals,
+                bid_threshold,
+                max_slot,
+                premium_rate_per_slot,
+                price_feed,
+            },
+        );
+
+        // the collateral is only liquidated once its feed reports a price
+        self.internal_create_new_price_request(&collateral_token);
+    }
+
+    /// The premium rate of a slot is fixed when its pool is created, and the
+    /// decimals of a token never change, so neither can be updated
+    #[payable]
+    pub fn update_collateral(
+        &mut self,
+        collateral_token: ValidAccountId,
+        custody_contract: Option<ValidAccountId>,
+        bid_threshold: Option<U128>,
+        max_slot: Option<u8>,
+        price_feed: Option<PriceFeed>,
+    ) {
+        self.assert_owner();
+        assert_one_yocto();
+
+        let collateral_token: AccountId = collateral_token.into();
+        let mut collateral_info: CollateralInfo = self.internal_read_collateral_info(&collateral_token);
+
+        if let Some(custody_contract) = custody_contract {
+            collateral_info.custody_contract = custody_contract.into();
+        }
+
+        if let Some(bid_threshold) = bid_threshold {
+            collateral_info.bid_threshold = bid_threshold;
+        }
+
+        if let Some(max_slot) = max_slot {
+            collateral_info.max_slot = max_slot;
+        }
+
+        // the next price request of the collateral goes to the new feed
+        if let Some(price_feed) = price_feed {
+            collateral_info.price_feed = price_feed;
+        }
+
+        self.collateral_infos.insert(&collateral_token, &collateral_info);
+    }
 }
\ No newline at end of file
@@ -5,12 +5,40 @@ const MAX_LIMIT: u8 = 31;
 
 #[near_bindgen]
 impl Contract{
+    pub(crate) fn internal_read_collateral_info(&self, collateral_token: &AccountId) -> CollateralInfo {
+        self.collateral_infos
+            .get(collateral_token)
+            .unwrap_or_else(|| panic!("Collateral {} is not registered", collateral_token))
+    }
+
+    pub(crate) fn internal_read_total_bids(&self, collateral_token: &AccountId) -> U128 {
+        self.total_bids.get(collateral_token).unwrap_or(U128(0))
+    }
+
+    pub(crate) fn internal_store_total_bids(&mut self, collateral_token: &AccountId, total_bids: U128) {
+        self.total_bids.insert(collateral_token, &total_bids);
+    }
+
+    pub(crate) fn internal_read_price_response(&self, collateral_token: &AccountId) -> PriceResponse {
+        self.price_responses
+            .get(collateral_token)
+            .unwrap_or_else(|| panic!("No price for {}", collateral_token))
+    }
+
     pub(crate) fn internal_read_bid(&self, bid_idx: U128) -> Bid {
-        self.bids.get(&bid_idx).expect("No bids with the specified information exist")
+        self.bids
+            .get(&bid_idx)
+            .or_else(|| {
+                self.legacy_bids
+                    .get(&bid_idx)
+                    .map(|bid| bid.into_bid(self.legacy_collateral.clone().unwrap()))
+            })
+            .expect("No bids with the specified information exist")
     }
 
     pub(crate) fn internal_store_bid(&mut self, bid_idx: U128, bid: &Bid) {
         self.bids.insert(&bid_idx, bid);
+        self.legacy_bids.remove(&bid_idx);
 
         let mut idx_set: UnorderedSet<U128> = self.bids_indexer_by_user.get(&bid.bidder)
             .unwrap_or_else(|| {
@@ -26,14 +54,24 @@ impl Contract{
     pub(crate) fn internal_remove_bid(&mut self, bid_idx: U128) {
         let bid: Bid = self.internal_read_bid(bid_idx);
         self.bids.remove(&bid_idx);
+        self.legacy_bids.remove(&bid_idx);
 
         let mut idx_set: UnorderedSet<U128> = self.bids_indexer_by_user.get(&bid.bidder).unwrap();
         idx_set.remove(&bid_idx);
         self.bids_indexer_by_user.insert(&bid.bidder, &idx_set);
     }
 
-    pub(crate) fn internal_read_bids_by_user(&self, bidder: &AccountId, start_after: Option<U128>, limit: Option<u8>) -> Vec<Bid> {
-        let mut bids_user_index: Vec<U128> = self.bids_indexer_by_user.get(bidder).unwrap().to_vec();
+    pub(crate) fn internal_read_bids_by_user(
+        &self,
+        bidder: &AccountId,
+        collateral_token: Option<&AccountId>,
+        start_after: Option<U128>,
+        limit: Option<u8>,
+    ) -> Vec<Bid> {
+        let mut bids_user_index: Vec<U128> = self.bids_indexer_by_user
+            .get(bidder)
+            .map(|idx_set| idx_set.to_vec())
+            .unwrap_or_default();
 
         let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
         let start: u128 = calc_range_start_idx(start_after);
@@ -45,81 +83,101 @@ impl Contract{
             .into_iter()
             // get index larger than 'start_after'
             .filter(|idx| idx.0 >= start)
-            .take(limit)
             .map(|idx: U128| {
                 self.internal_read_bid(idx)
             })
+            // only the bids of the collateral, if any
+            .filter(|bid| collateral_token.map_or(true, |token| &bid.collateral_token == token))
+            .take(limit)
             .collect()
     }
 
     pub(crate) fn internal_store_epoch_scale_sum(
         &mut self,
+        collateral_token: &AccountId,
         premium_slot: u8, 
         epoch: U128, 
         scale: U128,
         sum: D128,
     ) {
-        self.epoch_scale_sum.insert(&(premium_slot, epoch, scale), &sum);
+        self.epoch_scale_sum.insert(&(collateral_token.clone(), premium_slot, epoch, scale), &sum);
     }
 
     pub(crate) fn internal_read_epoch_scale_sum(
         &self,
+        collateral_token: &AccountId,
         premium_slot: u8,
         epoch: U128,
         scale: U128,
     ) -> Option<D128> {
-        self.epoch_scale_sum.get(&(premium_slot, epoch, scale))
+        self.epoch_scale_sum
+            .get(&(collateral_token.clone(), premium_slot, epoch, scale))
+            .or_else(|| {
+                if self.legacy_collateral.as_ref() == Some(collateral_token) {
+                    self.legacy_epoch_scale_sum.get(&(premium_slot, epoch, scale))
+                } else {
+                    None
+                }
+            })
     }
 
-    pub(crate) fn interanl_read_bid_pool(&self, premium_slot: u8) -> Option<BidPool> {
-        self.bid_pools.get(&premium_slot)
+    pub(crate) fn interanl_read_bid_pool(&self, collateral_token: &AccountId, premium_slot: u8) -> Option<BidPool> {
+        self.bid_pools.get(&(collateral_token.clone(), premium_slot))
     }
 
-    pub(crate) fn internal_store_bid_pool(&mut self, premium_slot: u8, bid_pool: &BidPool) {
-        self.bid_pools.insert(&premium_slot, bid_pool);
+    pub(crate) fn internal_store_bid_pool(&mut self, collateral_token: &AccountId, premium_slot: u8, bid_pool: &BidPool) {
+        self.bid_pools.insert(&(collateral_token.clone(), premium_slot), bid_pool);
     }
 
-    pub(crate) fn internal_read_or_create_bid_pool(&mut self, collateral_info: &CollateralInfo, premium_slot: u8) -> BidPool {
-        match self.interanl_read_bid_pool(premium_slot) {
+    pub(crate) fn internal_read_or_create_bid_pool(
+        &mut self,
+        collateral_token: &AccountId,
+        collateral_info: &CollateralInfo,
+        premium_slot: u8,
+    ) -> BidPool {
+        match self.interanl_read_bid_pool(collateral_token, premium_slot) {
             Some(bid_pool) => bid_pool,
             None => {
-                assert!((0..collateral_info.max_slot + 1).contains(&premium_slot), "Invalid premium slot");
+                assert!(premium_slot <= collateral_info.max_slot, "Invalid premium slot");
 
                 let bid_pool = BidPool {
                     product_snapshot: D128::one(),
                     sum_snapshot: D128::zero(),
                     total_bid_amount: U128(0),
-                    premium_rate: collateral_info.premium_rate_per_slot * (premium_slot as u128),
+                    premium_rate: collateral_info.premium_rate(premium_slot),
                     current_epoch: U128(0),
                     current_scale: U128(0),
                     residue_collateral: D128::zero(),
                     residue_bid: D128::zero(),
                 };
 
-                self.internal_store_bid_pool(premium_slot, &bid_pool);
+                self.internal_store_bid_pool(collateral_token, premium_slot, &bid_pool);
                 bid_pool
             }
         }
     }
 
-    pub(crate) fn internal_read_bid_pools(&self, start_after: Option<u8>, limit: Option<u8>) -> Vec<BidPool> {
-        let mut bid_pools: Vec<(u8, BidPool)> = self.bid_pools.to_vec();
-
+    pub(crate) fn internal_read_bid_pools(
+        &self,
+        collateral_token: &AccountId,
+        start_after: Option<u8>,
+        limit: Option<u8>,
+    ) -> Vec<(u8, BidPool)> {
         let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-        let start: u8 = calc_range_start(start_after);
-
-        // ascending sort
-        bid_pools.sort_by(|a, b| (&a.0).cmp(&b.0));
-        // deque
-        bid_pools
-            .into_iter()
-            // get premium slots larger than 'start_after'
-            .filter(|elem| elem.0 >= start)
-            .take(limit)
-            .map(|elem| {
-                let (_, pool) = elem;
-                pool
+        // no slot comes after the last one
+        let start: u8 = match calc_range_start(start_after) {
+            Some(start) => start,
+            None => return vec![],
+        };
+
+        // the pools of the collateral are looked up slot by slot, up to the last
+        // one since a pool keeps its bids when max_slot is lowered
+        (start..=u8::MAX)
+            .filter_map(|premium_slot| {
+                self.interanl_read_bid_pool(collateral_token, premium_slot)
+                    .map(|bid_pool| (premium_slot, bid_pool))
             })
+            .take(limit)
             .collect()
     }
 
@@ -136,7 +194,7 @@ fn calc_range_start_idx(start_after: Option<U128>) -> u128 {
     start_after.unwrap_or(U128(0)).0 + 1
 }
 
-// this will set the first key after the provided key, by appending 1
-fn calc_range_start(start_after: Option<u8>) -> u8 {
-    start_after.unwrap_or(0) + 1
+// this will set the first key after the provided key, by appending 1, slots start at 0
+fn calc_range_start(start_after: Option<u8>) -> Option<u8> {
+    start_after.map_or(Some(0), |slot| slot.checked_add(1))
 }
\ No newline at end of file
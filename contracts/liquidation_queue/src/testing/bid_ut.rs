@@ -0,0 +1,203 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::testing::{execute_liquidation, register_collateral, setup_contract, submit_bid};
+use crate::*;
+
+const WNEAR: u128 = 1_000_000_000_000_000_000_000_000;
+
+/// bNEAR, and wNEAR of 24 decimals at 2
+fn setup_two_collaterals() -> (VMContextBuilder, Contract) {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    register_collateral(&mut contract, "wnear", "custody_wnear", 24, D128::new_exp(2, 0));
+
+    (context, contract)
+}
+
+/// bids wait for `waiting_period` once the bids of the collateral reach the threshold
+fn wait_for_activation(context: &mut VMContextBuilder, contract: &mut Contract, collateral_token: &str) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.update_collateral(
+        ValidAccountId::try_from(collateral_token).unwrap(),
+        None,
+        Some(U128(0)),
+        None,
+        None,
+    );
+}
+
+#[test]
+fn bids_of_each_collateral_are_kept_apart() {
+    let (mut context, mut contract) = setup_two_collaterals();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", 0, 1_000_000_000);
+
+    assert_eq!(AccountId::from("bnear"), contract.get_bid(U128(1)).collateral_token);
+    assert_eq!(AccountId::from("wnear"), contract.get_bid(U128(2)).collateral_token);
+    assert_eq!(U128(1_000_000_000), contract.get_total_bids(AccountId::from("bnear")));
+    assert_eq!(U128(1_000_000_000), contract.get_total_bids(AccountId::from("wnear")));
+
+    // 100 wNEAR at 2 take 200 of the wNEAR bids only
+    execute_liquidation(&mut context, &mut contract, "wnear", "custody_wnear", 100 * WNEAR);
+
+    assert_eq!(U128(800_000_000), contract.get_total_bids(AccountId::from("wnear")));
+    assert_eq!(
+        U128(800_000_000),
+        contract.get_bid_pool(AccountId::from("wnear"), 0).total_bid_amount
+    );
+    assert_eq!(U128(1_000_000_000), contract.get_total_bids(AccountId::from("bnear")));
+    assert_eq!(
+        U128(1_000_000_000),
+        contract.get_bid_pool(AccountId::from("bnear"), 0).total_bid_amount
+    );
+}
+
+#[test]
+fn claim_liquidations_of_one_collateral() {
+    let (mut context, mut contract) = setup_two_collaterals();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", 0, 1_000_000_000);
+    execute_liquidation(&mut context, &mut contract, "wnear", "custody_wnear", 100 * WNEAR);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.claim_liquidations(AccountId::from("wnear"), None);
+
+    let receipts = serde_json::to_string(&get_created_receipts()).unwrap();
+    assert!(receipts.contains("\"receiver_id\":\"wnear\""));
+    assert!(receipts.contains("\\\"amount\\\":\\\"100000000000000000000000000\\\""));
+    assert!(!receipts.contains("\"receiver_id\":\"bnear\""));
+
+    assert_eq!(U128(800_000_000), contract.get_bid(U128(2)).amount);
+    assert_eq!(U128(1_000_000_000), contract.get_bid(U128(1)).amount);
+}
+
+#[test]
+#[should_panic(expected = "The bid is for another collateral")]
+fn claim_liquidations_of_bid_for_another_collateral() {
+    let (mut context, mut contract) = setup_two_collaterals();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.claim_liquidations(AccountId::from("wnear"), Some(vec![U128(1)]));
+}
+
+#[test]
+fn activate_bids_of_one_collateral() {
+    let (mut context, mut contract) = setup_two_collaterals();
+    wait_for_activation(&mut context, &mut contract, "bnear");
+    wait_for_activation(&mut context, &mut contract, "wnear");
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", 0, 1_000_000_000);
+    assert_eq!(Some(U64(60)), contract.get_bid(U128(2)).wait_end);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .block_timestamp(61 * SECOND_TO_NANO)
+        .build());
+    contract.activate_bids(AccountId::from("wnear"), None);
+
+    assert_eq!(None, contract.get_bid(U128(2)).wait_end);
+    assert_eq!(U128(1_000_000_000), contract.get_total_bids(AccountId::from("wnear")));
+    assert_eq!(Some(U64(60)), contract.get_bid(U128(1)).wait_end);
+    assert_eq!(U128(0), contract.get_total_bids(AccountId::from("bnear")));
+}
+
+#[test]
+#[should_panic(expected = "The bid is for another collateral")]
+fn activate_bid_for_another_collateral() {
+    let (mut context, mut contract) = setup_two_collaterals();
+    wait_for_activation(&mut context, &mut contract, "bnear");
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .block_timestamp(61 * SECOND_TO_NANO)
+        .build());
+    contract.activate_bids(AccountId::from("wnear"), Some(vec![U128(1)]));
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized: only custody contract can execute liquidations")]
+fn liquidation_from_custody_of_another_collateral() {
+    let (mut context, mut contract) = setup_two_collaterals();
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", 0, 1_000_000_000);
+
+    execute_liquidation(&mut context, &mut contract, "wnear", "custody_bnear", 100 * WNEAR);
+}
+
+#[test]
+#[should_panic(expected = "Collateral unknown is not registered")]
+fn submit_bid_for_unregistered_collateral() {
+    let (mut context, mut contract) = setup_contract();
+
+    submit_bid(&mut context, &mut contract, "bidder", "unknown", 0, 1_000_000_000);
+}
+
+#[test]
+fn bid_on_last_premium_slot() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.register_collateral(
+        ValidAccountId::try_from("wnear").unwrap(),
+        ValidAccountId::try_from("custody_wnear").unwrap(),
+        24,
+        U128(1_000_000_000_000),
+        u8::MAX,
+        D128::new_exp(1, -3),
+        PriceFeed {
+            end_point: String::from("https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd"),
+            source_path: String::from("near.usd"),
+        },
+    );
+    contract.price_responses.insert(
+        &AccountId::from("wnear"),
+        &PriceResponse {
+            price: D128::new_exp(2, 0),
+            last_updated_at: 0,
+        },
+    );
+
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", u8::MAX, 1_000_000_000);
+
+    assert_eq!(
+        D128::new_exp(255, -3),
+        contract.get_bid_pool(AccountId::from("wnear"), u8::MAX).premium_rate
+    );
+    let bid_pools = contract.get_bid_pools(AccountId::from("wnear"), Some(254), None);
+    assert_eq!(vec![u8::MAX], bid_pools.iter().map(|(slot, _)| *slot).collect::<Vec<u8>>());
+    // no slot comes after the last one
+    assert!(contract.get_bid_pools(AccountId::from("wnear"), Some(u8::MAX), None).is_empty());
+
+    // the liquidation reaches the last slot, 1 wNEAR is bought at 2 * (1 - 25.5%)
+    execute_liquidation(&mut context, &mut contract, "wnear", "custody_wnear", WNEAR);
+    assert_eq!(U128(998_510_000), contract.get_total_bids(AccountId::from("wnear")));
+}
+
+#[test]
+#[should_panic(expected = "Invalid premium slot")]
+fn bid_over_max_slot() {
+    let (mut context, mut contract) = setup_contract();
+
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 11, 1_000_000_000);
+}
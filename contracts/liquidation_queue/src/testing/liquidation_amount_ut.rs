@@ -0,0 +1,227 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::testing::{register_collateral, setup_contract, submit_bid};
+use crate::*;
+
+// 1000 bNEAR of 6 decimals at 5 are worth 5000, and without bids they are sold at
+// the premium of the highest slot, 10%. After the 1% bid and liquidator fees the
+// bids repay 5000 * 0.9 * 0.99 * 0.99 = 4410, and the safe borrow limit left is
+// 5000 * 0.6 * 0.8 = 2400
+fn liquidation_amount(contract: &Contract, borrow_amount: u128, max_ltv: D128) -> u128 {
+    let amounts = contract.get_liquidation_amount(
+        U128(borrow_amount),
+        U128(borrow_amount - 1),
+        vec![(AccountId::from("stable_coin"), U128(borrow_amount))],
+        vec![(AccountId::from("bnear"), 1000)],
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+        vec![(AccountId::from("bnear"), max_ltv)],
+    );
+
+    assert_eq!(AccountId::from("bnear"), amounts[0].0);
+    amounts[0].1
+}
+
+#[test]
+fn safe_loan_is_not_liquidated() {
+    let (_, contract) = setup_contract();
+
+    let amounts = contract.get_liquidation_amount(
+        U128(3000),
+        U128(3000),
+        vec![(AccountId::from("stable_coin"), U128(3000))],
+        vec![(AccountId::from("bnear"), 1000)],
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+        vec![(AccountId::from("bnear"), D128::new_exp(6, -1))],
+    );
+
+    assert_eq!(vec![(AccountId::from("bnear"), 0)], amounts);
+}
+
+#[test]
+fn partial_liquidation_restores_safe_ratio() {
+    let (_, contract) = setup_contract();
+
+    // (3000 - 2400) / (4410 - 2400) of the collateral
+    assert_eq!(298, liquidation_amount(&contract, 3000, D128::new_exp(6, -1)));
+}
+
+#[test]
+fn loan_under_safe_borrow_limit_is_not_liquidated() {
+    let (_, contract) = setup_contract();
+
+    assert_eq!(0, liquidation_amount(&contract, 2400, D128::new_exp(6, -1)));
+    // (2410 - 2400) / (4410 - 2400) of the collateral
+    assert_eq!(4, liquidation_amount(&contract, 2410, D128::new_exp(6, -1)));
+}
+
+#[test]
+fn loan_under_liquidation_threshold_is_liquidated_whole() {
+    let (_, contract) = setup_contract();
+
+    assert_eq!(1000, liquidation_amount(&contract, 199, D128::new_exp(6, -1)));
+    // at the threshold the loan is under the safe borrow limit
+    assert_eq!(0, liquidation_amount(&contract, 200, D128::new_exp(6, -1)));
+}
+
+#[test]
+fn collateral_repaying_under_safe_borrow_limit_is_liquidated_whole() {
+    let (_, contract) = setup_contract();
+
+    // a max LTV of 110.25% leaves a safe borrow limit of 4410, the repaid value
+    assert_eq!(1000, liquidation_amount(&contract, 4405, D128::new_exp(11025, -4)));
+    // at 110% it is 4400, half of the collateral covers the 5 over it
+    assert_eq!(500, liquidation_amount(&contract, 4405, D128::new_exp(11, -1)));
+}
+
+#[test]
+fn loan_over_repay_value_is_liquidated_whole() {
+    let (_, contract) = setup_contract();
+
+    assert_eq!(1000, liquidation_amount(&contract, 4410, D128::new_exp(6, -1)));
+    assert_eq!(1000, liquidation_amount(&contract, 5000, D128::new_exp(6, -1)));
+    // (4409 - 2400) / (4410 - 2400) of the collateral
+    assert_eq!(999, liquidation_amount(&contract, 4409, D128::new_exp(6, -1)));
+}
+
+#[test]
+fn loan_in_other_stable_asset_is_not_liquidated() {
+    let (_, contract) = setup_contract();
+
+    let amounts = contract.get_liquidation_amount(
+        U128(3000),
+        U128(2999),
+        vec![(AccountId::from("other_stable_coin"), U128(3000))],
+        vec![(AccountId::from("bnear"), 1000)],
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+        vec![(AccountId::from("bnear"), D128::new_exp(6, -1))],
+    );
+
+    assert_eq!(vec![(AccountId::from("bnear"), 0)], amounts);
+}
+
+#[test]
+fn liquidation_stops_at_loan_in_stable_coin() {
+    let (_, contract) = setup_contract();
+
+    // 441 of the 3000 are in the stable coin, a tenth of the collateral repays them
+    let amounts = contract.get_liquidation_amount(
+        U128(3000),
+        U128(2999),
+        vec![
+            (AccountId::from("stable_coin"), U128(441)),
+            (AccountId::from("other_stable_coin"), U128(2559)),
+        ],
+        vec![(AccountId::from("bnear"), 1000)],
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+        vec![(AccountId::from("bnear"), D128::new_exp(6, -1))],
+    );
+
+    assert_eq!(vec![(AccountId::from("bnear"), 100)], amounts);
+}
+
+#[test]
+fn bids_lower_the_premium() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 10_000);
+
+    // the bids of the first slot buy it all without premium, they repay
+    // 5000 * 0.99 * 0.99 = 4900 and (3000 - 2400) / (4900 - 2400) is sold
+    assert_eq!(240, liquidation_amount(&contract, 3000, D128::new_exp(6, -1)));
+}
+
+#[test]
+fn collaterals_are_sold_in_the_same_proportion() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    register_collateral(&mut contract, "wnear", "custody_wnear", 24, D128::new_exp(2, 0));
+
+    // another 5000 of wNEAR doubles the safe borrow limit and the repaid value
+    let amounts = contract.get_liquidation_amount(
+        U128(6000),
+        U128(5999),
+        vec![(AccountId::from("stable_coin"), U128(6000))],
+        vec![
+            (AccountId::from("bnear"), 1000),
+            (AccountId::from("wnear"), 2500 * 10u128.pow(18)),
+        ],
+        vec![
+            (AccountId::from("bnear"), D128::new_exp(5, 0)),
+            (AccountId::from("wnear"), D128::new_exp(2, 0)),
+        ],
+        vec![
+            (AccountId::from("bnear"), D128::new_exp(6, -1)),
+            (AccountId::from("wnear"), D128::new_exp(6, -1)),
+        ],
+    );
+
+    // (6000 - 4800) / (8820 - 4800) of each
+    assert_eq!(
+        vec![
+            (AccountId::from("bnear"), 298),
+            (AccountId::from("wnear"), 746268650 * 10u128.pow(12)),
+        ],
+        amounts
+    );
+}
+
+#[test]
+#[should_panic(expected = "Collateral prices and max LTVs do not match the collaterals")]
+fn liquidation_amount_with_mismatched_prices() {
+    let (_, contract) = setup_contract();
+
+    contract.get_liquidation_amount(
+        U128(3000),
+        U128(2999),
+        vec![(AccountId::from("stable_coin"), U128(3000))],
+        vec![(AccountId::from("bnear"), 1000)],
+        vec![(AccountId::from("wnear"), D128::new_exp(5, 0))],
+        vec![(AccountId::from("bnear"), D128::new_exp(6, -1))],
+    );
+}
+
+#[test]
+fn expected_premium_without_bids() {
+    let (_, contract) = setup_contract();
+
+    assert_eq!(
+        D128::zero(),
+        contract.internal_expected_premium_rate(&AccountId::from("bnear"), 0, D128::new_exp(5, 0))
+    );
+    assert_eq!(
+        D128::new_exp(1, -1),
+        contract.internal_expected_premium_rate(&AccountId::from("bnear"), 1000, D128::new_exp(5, 0))
+    );
+}
+
+#[test]
+fn expected_premium_from_lowest_slot() {
+    let (mut context, mut contract) = setup_contract();
+    // 2475 at 4.95 buys 500, 9600 at 4.8 buys 2000
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 1, 2475);
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 4, 9600);
+    assert_eq!(
+        D128::new_exp(4, -2),
+        contract.interanl_read_bid_pool(&AccountId::from("bnear"), 4).unwrap().premium_rate
+    );
+
+    // the bids of the first slot cover it all
+    assert_eq!(
+        D128::new_exp(1, -2),
+        contract.internal_expected_premium_rate(&AccountId::from("bnear"), 500, D128::new_exp(5, 0))
+    );
+    // (500 * 1% + 500 * 4%) / 1000
+    assert_eq!(
+        D128::new_exp(25, -3),
+        contract.internal_expected_premium_rate(&AccountId::from("bnear"), 1000, D128::new_exp(5, 0))
+    );
+    // (500 * 1% + 2000 * 4% + 500 * 10%) / 3000, the rest at the highest slot
+    assert_eq!(
+        D128::new_exp(45, -3),
+        contract.internal_expected_premium_rate(&AccountId::from("bnear"), 3000, D128::new_exp(5, 0))
+    );
+}
@@ -0,0 +1,132 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::Value;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::token_receiver::FungibleTokenReceiver;
+use crate::*;
+
+pub mod bid_ut;
+pub mod liquidation_amount_ut;
+pub mod price_ut;
+pub mod repay_ut;
+pub mod upgrade_ut;
+pub mod views_ut;
+
+pub(crate) fn setup_contract() -> (VMContextBuilder, Contract) {
+    let mut context = VMContextBuilder::new();
+    testing_env!(context
+        .current_account_id(ValidAccountId::try_from("liquidation").unwrap())
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    let mut contract = Contract::new(
+        ValidAccountId::try_from("owner").unwrap(),
+        ValidAccountId::try_from("stable_coin").unwrap(),
+        6,
+        ValidAccountId::try_from("requester").unwrap(),
+        ValidAccountId::try_from("oracle_payment_token").unwrap(),
+        ValidAccountId::try_from("overseer").unwrap(),
+        D128::new_exp(8, -1),
+        D128::new_exp(1, -2),
+        D128::new_exp(1, -2),
+        200,
+        U64(60),
+        U64(60),
+    );
+
+    register_collateral(&mut contract, "bnear", "custody_bnear", 6, D128::new_exp(5, 0));
+
+    (context, contract)
+}
+
+/// registers a collateral with ten premium slots of 1%, priced at `price`
+pub(crate) fn register_collateral(
+    contract: &mut Contract,
+    collateral_token: &str,
+    custody_contract: &str,
+    decimals: u8,
+    price: D128,
+) {
+    contract.register_collateral(
+        ValidAccountId::try_from(collateral_token).unwrap(),
+        ValidAccountId::try_from(custody_contract).unwrap(),
+        decimals,
+        U128(1_000_000_000_000),
+        10,
+        D128::new_exp(1, -2),
+        PriceFeed {
+            end_point: String::from("https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd"),
+            source_path: String::from("near.usd"),
+        },
+    );
+    contract.price_responses.insert(
+        &AccountId::from(collateral_token),
+        &PriceResponse {
+            price,
+            last_updated_at: 0,
+        },
+    );
+}
+
+/// bids `amount` of the stable coin on a premium slot of the collateral, the bid
+/// is active at once while the bids of the collateral are under its threshold
+pub(crate) fn submit_bid(
+    context: &mut VMContextBuilder,
+    contract: &mut Contract,
+    bidder: &str,
+    collateral_token: &str,
+    premium_slot: u8,
+    amount: u128,
+) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("stable_coin").unwrap())
+        .build());
+    contract.ft_on_transfer(
+        AccountId::from(bidder),
+        U128(amount),
+        format!(
+            "{{\"collateral_token\": \"{}\", \"premium_slot\": {}}}",
+            collateral_token, premium_slot
+        ),
+    );
+}
+
+/// the custody of the collateral sells `amount` of it to the bids
+pub(crate) fn execute_liquidation(
+    context: &mut VMContextBuilder,
+    contract: &mut Contract,
+    collateral_token: &str,
+    custody_contract: &str,
+    amount: u128,
+) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from(collateral_token).unwrap())
+        .build());
+    contract.ft_on_transfer(
+        AccountId::from(custody_contract),
+        U128(amount),
+        String::from("{\"liquidator\": \"liquidator\"}"),
+    );
+}
+
+/// receiver, method, arguments and gas of every function call the contract made
+pub(crate) fn created_function_calls() -> Vec<(String, String, Value, u64)> {
+    let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+
+    let mut function_calls = vec![];
+    for receipt in receipts.as_array().unwrap() {
+        for action in receipt["actions"].as_array().unwrap() {
+            let function_call = &action["FunctionCall"];
+            function_calls.push((
+                receipt["receiver_id"].as_str().unwrap().to_string(),
+                function_call["method_name"].as_str().unwrap().to_string(),
+                serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap(),
+                function_call["gas"].as_u64().unwrap(),
+            ));
+        }
+    }
+
+    function_calls
+}
@@ -0,0 +1,123 @@
+use flux_sdk::DataRequestDetails;
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::{json, Value};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::testing::{created_function_calls, setup_contract};
+use crate::*;
+
+/// the request of bNEAR as the requester contract keeps it
+fn bnear_request(status: Value) -> DataRequestDetails {
+    serde_json::from_value(json!({
+        "amount": "1000000000000000000000000",
+        "payload": {
+            "sources": [],
+            "tags": ["pricing", "bnear", "liquidation/bnear"],
+            "description": null,
+            "outcomes": null,
+            "challenge_period": "120000000000",
+            "data_type": {"Number": "100000000"},
+            "creator": "liquidation",
+        },
+        "tags": ["pricing", "bnear", "liquidation/bnear"],
+        "status": status,
+        "creator": "liquidation",
+        "has_withdrawn_validity_bond": false,
+    }))
+    .unwrap()
+}
+
+fn callback_context(context: &mut VMContextBuilder, prepaid_gas: Gas) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("liquidation").unwrap())
+        .block_timestamp(1_000)
+        .prepaid_gas(prepaid_gas)
+        .build());
+}
+
+#[test]
+fn update_price_stores_finalized_price() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("keeper").unwrap())
+        .attached_deposit(1)
+        .prepaid_gas(300_000_000_000_000)
+        .build());
+    contract.update_price(AccountId::from("bnear"));
+
+    let function_calls = created_function_calls();
+    assert_eq!("requester", function_calls[0].0);
+    assert_eq!("get_data_request", function_calls[0].1);
+    assert_eq!("liquidation/bnear", function_calls[0].2["request_id"]);
+    assert_eq!(GAS_FOR_PRICE_QUERY, function_calls[0].3);
+    assert_eq!("callback_get_price_response", function_calls[1].1);
+    assert!(function_calls[1].3 >= DR_NEW_GAS + GAS_FOR_PRICE_UPDATE);
+
+    callback_context(&mut context, function_calls[1].3);
+    contract.callback_get_price_response(
+        AccountId::from("bnear"),
+        Some(bnear_request(json!({
+            "Finalized": {"Answer": {"Number": {
+                "value": "700000000",
+                "multiplier": "100000000",
+                "negative": false,
+            }}}
+        }))),
+    );
+
+    let price = contract.price_responses.get(&AccountId::from("bnear")).unwrap();
+    assert_eq!(D128::new_exp(7, 0), price.price);
+    assert_eq!(1_000, price.last_updated_at);
+
+    // the next request of bNEAR is paid to the requester contract
+    let function_calls = created_function_calls();
+    assert_eq!("oracle_payment_token", function_calls[0].0);
+    assert_eq!("ft_transfer_call", function_calls[0].1);
+    assert_eq!("requester", function_calls[0].2["receiver_id"]);
+    let msg: Value = serde_json::from_str(function_calls[0].2["msg"].as_str().unwrap()).unwrap();
+    assert_eq!(json!(["pricing", "bnear"]), msg["tags"]);
+}
+
+#[test]
+fn update_price_keeps_price_of_pending_request() {
+    let (mut context, mut contract) = setup_contract();
+    callback_context(&mut context, 300_000_000_000_000);
+
+    contract.callback_get_price_response(AccountId::from("bnear"), Some(bnear_request(json!("Pending"))));
+
+    let price = contract.price_responses.get(&AccountId::from("bnear")).unwrap();
+    assert_eq!(D128::new_exp(5, 0), price.price);
+    assert!(created_function_calls().is_empty());
+}
+
+#[test]
+fn update_price_requests_missing_price() {
+    let (mut context, mut contract) = setup_contract();
+    callback_context(&mut context, 300_000_000_000_000);
+
+    contract.callback_get_price_response(AccountId::from("bnear"), None);
+
+    let function_calls = created_function_calls();
+    assert_eq!("oracle_payment_token", function_calls[0].0);
+    assert_eq!("ft_transfer_call", function_calls[0].1);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to request the next price")]
+fn update_price_without_gas_for_next_request() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context.attached_deposit(1).prepaid_gas(DR_NEW_GAS).build());
+
+    contract.update_price(AccountId::from("bnear"));
+}
+
+#[test]
+#[should_panic(expected = "Collateral weth is not registered")]
+fn update_price_of_unknown_collateral() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context.attached_deposit(1).prepaid_gas(300_000_000_000_000).build());
+
+    contract.update_price(AccountId::from("weth"));
+}
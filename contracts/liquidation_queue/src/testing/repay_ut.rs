@@ -0,0 +1,101 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{testing_env, MockedBlockchain, PromiseResult, RuntimeFeesConfig, VMConfig};
+use std::convert::TryFrom;
+
+use crate::testing::{created_function_calls, setup_contract, submit_bid};
+use crate::token_receiver::FungibleTokenReceiver;
+use crate::*;
+
+#[test]
+fn repay_market_and_refund_borrower() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bnear").unwrap())
+        .prepaid_gas(150_000_000_000_000)
+        .build());
+    contract.ft_on_transfer(
+        AccountId::from("custody_bnear"),
+        U128(100_000_000),
+        String::from("{\"liquidator\": \"liquidator\", \"repay_address\": \"market\", \"fee_address\": \"overseer\", \"repay_msg\": \"{\\\"repay_stable\\\":{\\\"borrower\\\":\\\"borrower\\\"}}\", \"refund_address\": \"borrower\"}"),
+    );
+
+    let function_calls = created_function_calls();
+    let repay = function_calls
+        .iter()
+        .find(|function_call| function_call.1 == "ft_transfer_call")
+        .unwrap();
+    assert_eq!("stable_coin", repay.0);
+    assert_eq!("market", repay.2["receiver_id"]);
+    // the repayment gets the prepaid gas left once the fees and the refund are covered
+    assert!(repay.3 > 90_000_000_000_000 && repay.3 <= 95_000_000_000_000);
+
+    let refund = function_calls
+        .iter()
+        .find(|function_call| function_call.1 == "callback_refund_liquidation")
+        .unwrap();
+    assert_eq!("borrower", refund.2["refund_address"]);
+    assert_eq!(repay.2["amount"], refund.2["amount"]);
+    assert_eq!(GAS_FOR_REFUND, refund.3);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to repay the liquidation")]
+fn repay_market_without_gas() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bnear").unwrap())
+        .prepaid_gas(50_000_000_000_000)
+        .build());
+    contract.ft_on_transfer(
+        AccountId::from("custody_bnear"),
+        U128(100_000_000),
+        String::from("{\"liquidator\": \"liquidator\", \"repay_address\": \"market\", \"repay_msg\": \"{}\"}"),
+    );
+}
+
+#[test]
+fn refund_proceeds_exceeding_loan() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(
+        context
+            .predecessor_account_id(ValidAccountId::try_from("liquidation").unwrap())
+            .build(),
+        VMConfig::default(),
+        RuntimeFeesConfig::default(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"\"300\"".to_vec())]
+    );
+
+    contract.callback_refund_liquidation(AccountId::from("borrower"), U128(500));
+
+    let function_calls = created_function_calls();
+    assert_eq!(1, function_calls.len());
+    assert_eq!(
+        (String::from("stable_coin"), String::from("ft_transfer")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("borrower", function_calls[0].2["receiver_id"]);
+    assert_eq!("200", function_calls[0].2["amount"]);
+}
+
+#[test]
+fn keep_fully_used_repayment() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(
+        context
+            .predecessor_account_id(ValidAccountId::try_from("liquidation").unwrap())
+            .build(),
+        VMConfig::default(),
+        RuntimeFeesConfig::default(),
+        Default::default(),
+        vec![PromiseResult::Successful(b"\"500\"".to_vec())]
+    );
+
+    contract.callback_refund_liquidation(AccountId::from("borrower"), U128(500));
+
+    assert!(created_function_calls().is_empty());
+}
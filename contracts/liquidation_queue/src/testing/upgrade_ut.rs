@@ -0,0 +1,209 @@
+use near_sdk::collections::{LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json;
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::testing::{execute_liquidation, submit_bid};
+use crate::upgrade::{BidV0, CollateralInfoV0, ConfigV0, ContractV0};
+use crate::*;
+
+/// a single collateral queue with an active bid of 1000 on the first slot, and a
+/// waiting bid of 500 on the second
+fn setup_contract_v0() -> VMContextBuilder {
+    let mut context = VMContextBuilder::new();
+    testing_env!(context
+        .current_account_id(ValidAccountId::try_from("liquidation").unwrap())
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+
+    let bid_pool = BidPool {
+        sum_snapshot: D128::zero(),
+        product_snapshot: D128::one(),
+        total_bid_amount: U128(1_000_000_000),
+        premium_rate: D128::zero(),
+        current_epoch: U128(0),
+        current_scale: U128(0),
+        residue_collateral: D128::zero(),
+        residue_bid: D128::zero(),
+    };
+    let mut bid_pools: UnorderedMap<u8, BidPool> = UnorderedMap::new(StorageKeys::BidPools);
+    bid_pools.insert(&0, &bid_pool);
+    // the pool of a slot is created by its first bid, waiting or not
+    bid_pools.insert(
+        &1,
+        &BidPool {
+            total_bid_amount: U128(0),
+            premium_rate: D128::new_exp(1, -2),
+            ..bid_pool.clone()
+        },
+    );
+
+    let mut bids: LookupMap<U128, BidV0> = LookupMap::new(StorageKeys::Bids);
+    let mut idx_set: UnorderedSet<U128> = UnorderedSet::new(StorageKeys::Account {
+        account_hash: env::sha256(b"bidder"),
+    });
+    for (idx, premium_slot, amount, wait_end) in
+        vec![(1, 0, 1_000_000_000, None), (2, 1, 500_000_000, Some(U64(60)))]
+    {
+        bids.insert(
+            &U128(idx),
+            &BidV0 {
+                idx: U128(idx),
+                premium_slot,
+                bidder: AccountId::from("bidder"),
+                amount: U128(amount),
+                product_snapshot: D128::one(),
+                sum_snapshot: D128::zero(),
+                pending_liquidated_collateral: U128(0),
+                wait_end,
+                epoch_snapshot: U128(0),
+                scale_snapshot: U128(0),
+            },
+        );
+        idx_set.insert(&U128(idx));
+    }
+    let mut bids_indexer_by_user: LookupMap<AccountId, UnorderedSet<U128>> =
+        LookupMap::new(StorageKeys::Indexer);
+    bids_indexer_by_user.insert(&AccountId::from("bidder"), &idx_set);
+
+    env::state_write(&ContractV0 {
+        config: ConfigV0 {
+            owner: AccountId::from("owner"),
+            stable_coin_contract: AccountId::from("stable_coin"),
+            requester_contract: AccountId::from("requester"),
+            oracle_payment_token: AccountId::from("oracle_payment_token"),
+            overseer_contract: AccountId::from("overseer"),
+            custody_contract: AccountId::from("custody_bnear"),
+            safe_ratio: D128::new_exp(8, -1),
+            bid_fee: D128::new_exp(1, -2),
+            liquidator_fee: D128::new_exp(1, -2),
+            liquidation_threshold: 200,
+            waiting_period: 60,
+            collateral_info: CollateralInfoV0 {
+                bnear_contract: AccountId::from("bnear"),
+                bid_threshold: U128(1_000_000_000_000),
+                max_slot: 10,
+                premium_rate_per_slot: D128::new_exp(1, -2),
+            },
+        },
+        bids_indexer_by_user,
+        bids,
+        bid_pools,
+        epoch_scale_sum: LookupMap::new(StorageKeys::EpochScaleSum),
+        bid_idx: U128(3),
+        total_bids: U128(1_000_000_000),
+        last_price_response: PriceResponse {
+            price: D128::new_exp(5, 0),
+            last_updated_at: 0,
+        },
+    });
+
+    context
+}
+
+#[test]
+fn proper_migrate_from_v0() {
+    let mut context = setup_contract_v0();
+    let contract = Contract::migrate();
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(6, contract.get_config().stable_coin_decimals);
+    assert_eq!(3600, contract.get_config().price_timeframe);
+    let collateral_info = contract.get_collateral_info(AccountId::from("bnear"));
+    assert_eq!(AccountId::from("custody_bnear"), collateral_info.custody_contract);
+    assert_eq!(24, collateral_info.decimals);
+    assert_eq!(10, collateral_info.max_slot);
+    assert_eq!(U128(1_000_000_000), contract.get_total_bids(AccountId::from("bnear")));
+    assert_eq!(D128::new_exp(5, 0), contract.get_price_status(AccountId::from("bnear")).price);
+
+    // the pools are keyed by the collateral
+    assert_eq!(
+        U128(1_000_000_000),
+        contract.get_bid_pool(AccountId::from("bnear"), 0).total_bid_amount
+    );
+
+    // the legacy bids are read as bids for the collateral
+    let bids = contract.get_bids_by_user(AccountId::from("bidder"), None, None);
+    assert_eq!(2, bids.len());
+    assert!(bids.iter().all(|bid| bid.collateral_token == AccountId::from("bnear")));
+    assert_eq!(U128(500_000_000), bids[1].amount);
+    assert_eq!(Some(U64(60)), bids[1].wait_end);
+
+    // new bids carry on from the last index
+    let mut contract = contract;
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    assert_eq!(AccountId::from("bidder"), contract.get_bid(U128(3)).bidder);
+    assert_eq!(3, contract.get_bids_by_user(AccountId::from("bidder"), None, None).len());
+}
+
+#[test]
+fn legacy_bids_are_liquidated_and_claimed() {
+    let mut context = setup_contract_v0();
+    let mut contract = Contract::migrate();
+
+    // 100 bNEAR at 5 take 500 of the legacy bid
+    execute_liquidation(
+        &mut context,
+        &mut contract,
+        "bnear",
+        "custody_bnear",
+        100_000_000_000_000_000_000_000_000,
+    );
+    assert_eq!(U128(500_000_000), contract.get_total_bids(AccountId::from("bnear")));
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.claim_liquidations(AccountId::from("bnear"), Some(vec![U128(1)]));
+
+    let receipts = serde_json::to_string(&get_created_receipts()).unwrap();
+    assert!(receipts.contains("\"receiver_id\":\"bnear\""));
+    assert!(receipts.contains("\\\"amount\\\":\\\"100000000000000000000000000\\\""));
+
+    // the claimed bid is stored again in the current layout
+    assert!(contract.legacy_bids.get(&U128(1)).is_none());
+    assert_eq!(U128(500_000_000), contract.get_bid(U128(1)).amount);
+    assert_eq!(AccountId::from("bnear"), contract.get_bid(U128(1)).collateral_token);
+}
+
+#[test]
+fn legacy_waiting_bid_is_activated() {
+    let mut context = setup_contract_v0();
+    let mut contract = Contract::migrate();
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .block_timestamp(61 * SECOND_TO_NANO)
+        .build());
+    contract.activate_bids(AccountId::from("bnear"), Some(vec![U128(2)]));
+
+    assert_eq!(None, contract.get_bid(U128(2)).wait_end);
+    assert!(contract.legacy_bids.get(&U128(2)).is_none());
+    assert_eq!(U128(1_500_000_000), contract.get_total_bids(AccountId::from("bnear")));
+    assert_eq!(
+        U128(500_000_000),
+        contract.get_bid_pool(AccountId::from("bnear"), 1).total_bid_amount
+    );
+}
+
+#[test]
+fn migrate_current_state() {
+    let mut context = setup_contract_v0();
+    let mut contract = Contract::migrate();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    env::state_write(&contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    let contract = Contract::migrate();
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(U128(2_000_000_000), contract.get_total_bids(AccountId::from("bnear")));
+    assert_eq!(3, contract.get_bids_by_user(AccountId::from("bidder"), None, None).len());
+}
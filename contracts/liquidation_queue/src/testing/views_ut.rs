@@ -0,0 +1,159 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{testing_env, MockedBlockchain};
+use std::convert::TryFrom;
+
+use crate::testing::{execute_liquidation, register_collateral, setup_contract, submit_bid};
+use crate::*;
+
+fn slots(bid_pools: &[(u8, BidPool)]) -> Vec<u8> {
+    bid_pools.iter().map(|(premium_slot, _)| *premium_slot).collect()
+}
+
+#[test]
+fn bid_pools_of_a_collateral_by_slot() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    register_collateral(&mut contract, "wnear", "custody_wnear", 24, D128::new_exp(2, 0));
+    for premium_slot in vec![7, 0, 3, 10] {
+        submit_bid(&mut context, &mut contract, "bidder", "bnear", premium_slot, 1_000_000);
+    }
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", 5, 2_000_000);
+
+    let bid_pools = contract.get_bid_pools(AccountId::from("bnear"), None, None);
+    assert_eq!(vec![0, 3, 7, 10], slots(&bid_pools));
+    assert_eq!(D128::new_exp(3, -2), bid_pools[1].1.premium_rate);
+    assert_eq!(U128(1_000_000), bid_pools[1].1.total_bid_amount);
+
+    let bid_pools = contract.get_bid_pools(AccountId::from("wnear"), None, None);
+    assert_eq!(vec![5], slots(&bid_pools));
+    assert_eq!(U128(2_000_000), bid_pools[0].1.total_bid_amount);
+}
+
+#[test]
+fn bid_pools_a_page_at_a_time() {
+    let (mut context, mut contract) = setup_contract();
+    for premium_slot in vec![0, 3, 7, 10] {
+        submit_bid(&mut context, &mut contract, "bidder", "bnear", premium_slot, 1_000_000);
+    }
+
+    let page = contract.get_bid_pools(AccountId::from("bnear"), None, Some(2));
+    assert_eq!(vec![0, 3], slots(&page));
+    let page = contract.get_bid_pools(AccountId::from("bnear"), Some(3), Some(2));
+    assert_eq!(vec![7, 10], slots(&page));
+    let page = contract.get_bid_pools(AccountId::from("bnear"), Some(10), Some(2));
+    assert!(page.is_empty());
+    // a slot without pool can start a page
+    let page = contract.get_bid_pools(AccountId::from("bnear"), Some(1), None);
+    assert_eq!(vec![3, 7, 10], slots(&page));
+}
+
+#[test]
+fn bid_pools_of_unknown_collateral() {
+    let (_, contract) = setup_contract();
+
+    assert!(contract.get_bid_pools(AccountId::from("wnear"), None, None).is_empty());
+}
+
+#[test]
+fn bid_pools_above_lowered_max_slot() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 10, 1_000_000);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.update_collateral(ValidAccountId::try_from("bnear").unwrap(), None, None, Some(5), None);
+
+    // the pool still holds the bid
+    assert_eq!(vec![10], slots(&contract.get_bid_pools(AccountId::from("bnear"), None, None)));
+}
+
+#[test]
+fn claimable_of_waiting_and_active_bids() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    register_collateral(&mut contract, "wnear", "custody_wnear", 24, D128::new_exp(2, 0));
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.update_collateral(
+        ValidAccountId::try_from("wnear").unwrap(),
+        None,
+        Some(U128(0)),
+        None,
+        None,
+    );
+    submit_bid(&mut context, &mut contract, "bidder", "wnear", 0, 500_000_000);
+
+    // 100 bNEAR of 6 decimals at 5 take 500 of the bNEAR bid
+    execute_liquidation(&mut context, &mut contract, "bnear", "custody_bnear", 100_000_000);
+
+    assert_eq!(
+        vec![
+            ClaimableResponse {
+                bid_idx: U128(1),
+                collateral_token: AccountId::from("bnear"),
+                remaining_bid: U128(500_000_000),
+                liquidated_collateral: U128(100_000_000),
+            },
+            // a waiting bid has bought nothing
+            ClaimableResponse {
+                bid_idx: U128(2),
+                collateral_token: AccountId::from("wnear"),
+                remaining_bid: U128(500_000_000),
+                liquidated_collateral: U128(0),
+            },
+        ],
+        contract.get_claimable(AccountId::from("bidder"), None, None)
+    );
+
+    // a page after the first bid
+    assert_eq!(
+        vec![U128(2)],
+        contract
+            .get_claimable(AccountId::from("bidder"), Some(U128(1)), None)
+            .into_iter()
+            .map(|claimable| claimable.bid_idx)
+            .collect::<Vec<U128>>()
+    );
+}
+
+#[test]
+fn claimable_after_claim() {
+    let (mut context, mut contract) = setup_contract();
+    submit_bid(&mut context, &mut contract, "bidder", "bnear", 0, 1_000_000_000);
+    execute_liquidation(&mut context, &mut contract, "bnear", "custody_bnear", 100_000_000);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("bidder").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.claim_liquidations(AccountId::from("bnear"), None);
+
+    let claimable = contract.get_claimable(AccountId::from("bidder"), None, None);
+    assert_eq!(U128(500_000_000), claimable[0].remaining_bid);
+    assert_eq!(U128(0), claimable[0].liquidated_collateral);
+
+    // the bid keeps buying from the sum it claimed at
+    execute_liquidation(&mut context, &mut contract, "bnear", "custody_bnear", 50_000_000);
+
+    let claimable = contract.get_claimable(AccountId::from("bidder"), None, None);
+    assert_eq!(U128(250_000_000), claimable[0].remaining_bid);
+    assert_eq!(U128(50_000_000), claimable[0].liquidated_collateral);
+}
+
+#[test]
+fn claimable_of_unknown_bidder() {
+    let (_, contract) = setup_contract();
+
+    assert!(contract.get_claimable(AccountId::from("bidder"), None, None).is_empty());
+}
@@ -2,15 +2,20 @@ use crate::*;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
-pub struct BnearReceiverPayload {
+pub struct CollateralReceiverPayload {
     pub liquidator: AccountId,
     pub repay_address: Option<AccountId>,
-    pub fee_address: Option<AccountId>
+    pub fee_address: Option<AccountId>,
+    // passed on to the repay address with the proceeds, e.g. the market repay message
+    pub repay_msg: Option<String>,
+    // receives the proceeds the repay address did not use, e.g. the liquidated borrower
+    pub refund_address: Option<AccountId>,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub struct StableReceiverPayload {
+    pub collateral_token: AccountId,
     pub premium_slot: u8,
 }
 
@@ -31,21 +36,22 @@ impl FungibleTokenReceiver for Contract {
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        if env::predecessor_account_id() == self.config.collateral_info.bnear_contract {
-            let payload: BnearReceiverPayload =
+        let token: AccountId = env::predecessor_account_id();
+        if self.collateral_infos.get(&token).is_some() {
+            let payload: CollateralReceiverPayload =
                 serde_json::from_str(&msg).expect("Failed to parse the payload, invalid `msg` format");
 
             let repay_address: AccountId = payload.repay_address.unwrap_or(sender_id.clone());
             let fee_address: AccountId = payload.fee_address.unwrap_or(sender_id.clone());
             
-            self.on_receive_execute_liquidation(sender_id, payload.liquidator, repay_address, fee_address, amount);
+            self.on_receive_execute_liquidation(token, sender_id, payload.liquidator, repay_address, fee_address, payload.repay_msg, payload.refund_address, amount);
 
             return PromiseOrValue::Value(U128(0));
-        } else if env::predecessor_account_id() == self.config.stable_coin_contract {
+        } else if token == self.config.stable_coin_contract {
             let payload: StableReceiverPayload =
                 serde_json::from_str(&msg).expect("Failed to parse the payload, invalid `msg` format");
 
-            self.on_receive_submit_bid(sender_id, payload.premium_slot, amount);
+            self.on_receive_submit_bid(sender_id, payload.collateral_token, payload.premium_slot, amount);
 
             return PromiseOrValue::Value(U128(0));
         } else {
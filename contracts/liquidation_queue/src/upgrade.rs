@@ -0,0 +1,216 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+// unit: seconds
+const DEFAULT_PRICE_TIMEFRAME: u64 = 3600;
+
+/// Layouts the contract state has been persisted with. The version is kept
+/// under `STATE_VERSION_KEY`; the deployment made before it existed is `V0`,
+/// converted into the current layout by `migrate`.
+pub enum VersionedContract {
+    V0(ContractV0),
+    V1(Contract),
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CollateralInfoV0 {
+    pub bnear_contract: AccountId,
+    pub bid_threshold: U128,
+    pub max_slot: u8,
+    pub premium_rate_per_slot: D128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ConfigV0 {
+    pub owner: AccountId,
+    pub stable_coin_contract: AccountId,
+    pub requester_contract: AccountId,
+    pub oracle_payment_token: AccountId,
+    pub overseer_contract: AccountId,
+    pub custody_contract: AccountId,
+    pub safe_ratio: D128,
+    pub bid_fee: D128,
+    pub liquidator_fee: D128,
+    pub liquidation_threshold: Balance,
+    pub waiting_period: u64,
+    pub collateral_info: CollateralInfoV0,
+}
+
+/// Bid of the single collateral queue, for `legacy_collateral`
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct BidV0 {
+    pub idx: U128,
+    pub premium_slot: u8,
+    pub bidder: AccountId,
+    pub amount: U128,
+    pub product_snapshot: D128,
+    pub sum_snapshot: D128,
+    pub pending_liquidated_collateral: U128,
+    pub wait_end: Option<U64>,
+    pub epoch_snapshot: U128,
+    pub scale_snapshot: U128,
+}
+
+impl BidV0 {
+    pub(crate) fn into_bid(self, collateral_token: AccountId) -> Bid {
+        Bid {
+            idx: self.idx,
+            collateral_token,
+            premium_slot: self.premium_slot,
+            bidder: self.bidder,
+            amount: self.amount,
+            product_snapshot: self.product_snapshot,
+            sum_snapshot: self.sum_snapshot,
+            pending_liquidated_collateral: self.pending_liquidated_collateral,
+            wait_end: self.wait_end,
+            epoch_snapshot: self.epoch_snapshot,
+            scale_snapshot: self.scale_snapshot,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+    pub config: ConfigV0,
+    pub bids_indexer_by_user: LookupMap<AccountId, UnorderedSet<U128>>,
+    pub bids: LookupMap<U128, BidV0>,
+    pub bid_pools: UnorderedMap<u8, BidPool>,
+    pub epoch_scale_sum: LookupMap<(u8, U128, U128), D128>,
+    pub bid_idx: U128,
+    pub total_bids: U128,
+    pub last_price_response: PriceResponse,
+}
+
+impl From<ContractV0> for Contract {
+    fn from(old: ContractV0) -> Self {
+        // the collateral of the single collateral queue becomes its first registered collateral
+        let collateral_token: AccountId = old.config.collateral_info.bnear_contract;
+        let mut collateral_infos = UnorderedMap::new(StorageKeys::CollateralInfos);
+        collateral_infos.insert(
+            &collateral_token,
+            &CollateralInfo {
+                custody_contract: old.config.custody_contract,
+                // the queue only liquidated bNEAR before the decimals were stored
+                decimals: 24,
+                bid_threshold: old.config.collateral_info.bid_threshold,
+                max_slot: old.config.collateral_info.max_slot,
+                premium_rate_per_slot: old.config.collateral_info.premium_rate_per_slot,
+                price_feed: PriceFeed {
+                    end_point: String::from(
+                        "https://api.coingecko.com/api/v3/simple/price?ids=tether%2Cnear&vs_currencies=usd",
+                    ),
+                    source_path: String::from("near.usd"),
+                },
+            },
+        );
+
+        // there are at most max_slot + 1 pools, they are keyed again under the same prefix
+        let mut old_bid_pools = old.bid_pools;
+        let pools: Vec<(u8, BidPool)> = old_bid_pools.to_vec();
+        old_bid_pools.clear();
+        let mut bid_pools = UnorderedMap::new(StorageKeys::BidPools);
+        for (premium_slot, bid_pool) in pools.iter() {
+            bid_pools.insert(&(collateral_token.clone(), *premium_slot), bid_pool);
+        }
+
+        let mut total_bids = LookupMap::new(StorageKeys::TotalBids);
+        total_bids.insert(&collateral_token, &old.total_bids);
+        let mut price_responses = LookupMap::new(StorageKeys::PriceResponses);
+        price_responses.insert(&collateral_token, &old.last_price_response);
+
+        Self {
+            config: Config {
+                owner: old.config.owner,
+                stable_coin_contract: old.config.stable_coin_contract,
+                // the stable coin of the V0 market was USDT
+                stable_coin_decimals: 6,
+                requester_contract: old.config.requester_contract,
+                oracle_payment_token: old.config.oracle_payment_token,
+                overseer_contract: old.config.overseer_contract,
+                safe_ratio: old.config.safe_ratio,
+                bid_fee: old.config.bid_fee,
+                liquidator_fee: old.config.liquidator_fee,
+                liquidation_threshold: old.config.liquidation_threshold,
+                waiting_period: old.config.waiting_period,
+                // the V0 queue never checked the age of its price
+                price_timeframe: DEFAULT_PRICE_TIMEFRAME,
+            },
+            collateral_infos,
+            bids_indexer_by_user: old.bids_indexer_by_user,
+            bids: LookupMap::new(StorageKeys::CollateralBids),
+            bid_pools,
+            epoch_scale_sum: LookupMap::new(StorageKeys::CollateralEpochScaleSum),
+            bid_idx: old.bid_idx,
+            total_bids,
+            price_responses,
+            legacy_collateral: Some(collateral_token),
+            legacy_bids: old.bids,
+            legacy_epoch_scale_sum: old.epoch_scale_sum,
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_read_state_version() -> u8 {
+        env::storage_read(STATE_VERSION_KEY)
+            .map(|value| value[0])
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+        match Self::internal_read_state_version() {
+            0 => VersionedContract::V0(env::state_read().expect("Failed to read the V0 state")),
+            CURRENT_STATE_VERSION => {
+                VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+            }
+            _ => env::panic(b"Unknown state version"),
+        }
+    }
+
+    pub(crate) fn internal_write_state_version() {
+        env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the stored state into the current layout. Called by `upgrade`
+    /// right after the new code is deployed, or by the owner directly.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let contract: Contract = match Self::internal_read_versioned_state() {
+            VersionedContract::V0(old) => old.into(),
+            VersionedContract::V1(contract) => contract,
+        };
+
+        if env::predecessor_account_id() != env::current_account_id() {
+            contract.assert_owner();
+        }
+
+        Self::internal_write_state_version();
+
+        contract
+    }
+
+    /// Deploys the wasm blob attached as the call input and migrates the state.
+    pub fn upgrade(&self) -> Promise {
+        self.assert_owner();
+
+        let code = env::input().expect("Error: No code attached");
+        let migrate_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+            .expect("Not enough gas attached to migrate after the upgrade");
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), b"{}".to_vec(), 0, migrate_gas)
+    }
+
+    pub fn get_state_version(&self) -> u8 {
+        Self::internal_read_state_version()
+    }
+}
@@ -1,8 +1,7 @@
 use crate::*;
 
-use flux_sdk::consts::{DR_NEW_GAS, GAS_BASE_TRANSFER};
 use flux_sdk::{DataRequestDetails, RequestStatus, Outcome, AnswerType};
-use near_sdk::{ext_contract, AccountId};
+use near_sdk::{ext_contract, AccountId, PromiseResult};
 
 #[ext_contract(fungible_token)]
 pub trait FungibleToken {
@@ -24,12 +23,17 @@ pub trait FungibleToken {
 
 #[ext_contract(requester)]
 pub trait RequesterContract {
-    fn get_data_request(&self, request_id: ValidAccountId) -> Option<DataRequestDetails>;
+    fn get_data_request(&self, request_id: String) -> Option<DataRequestDetails>;
 }
 
 #[ext_contract(ext_self)]
 pub trait Contract {
-    fn callback_get_price_response(&mut self, #[callback] result: Option<DataRequestDetails>);
+    fn callback_get_price_response(
+        &mut self,
+        collateral_token: AccountId,
+        #[callback] result: Option<DataRequestDetails>,
+    );
+    fn callback_refund_liquidation(&mut self, refund_address: AccountId, amount: U128);
 }
 
 pub fn fungible_token_transfer(
@@ -53,6 +57,7 @@ pub fn fungible_token_transfer_call(
     receiver_id: AccountId,
     value: u128,
     msg: String,
+    gas: Gas,
 ) -> Promise {
     fungible_token::ft_transfer_call(
         receiver_id,
@@ -62,30 +67,64 @@ pub fn fungible_token_transfer_call(
         // Near params
         &token_account_id,
         1,
-        DR_NEW_GAS,
+        gas,
     )
 }
 
 #[near_bindgen]
 impl Contract {
     #[private]
-    pub fn callback_get_price_response(&mut self, #[callback] result: Option<DataRequestDetails>) {
-        let result: DataRequestDetails = result.expect("ERR: There is no response.");
-        
+    pub fn callback_get_price_response(
+        &mut self,
+        collateral_token: AccountId,
+        #[callback] result: Option<DataRequestDetails>,
+    ) {
+        // the first request, or one the requester stored before requests were kept per asset
+        let result: DataRequestDetails = match result {
+            Some(result) => result,
+            None => {
+                self.internal_create_new_price_request(&collateral_token);
+                return;
+            }
+        };
+
         let status: RequestStatus = result.status;
 
         if let RequestStatus::Finalized(outcome) = status {
             if let Outcome::Answer(answer_type) = outcome {
                 if let AnswerType::Number(number) = answer_type {
                     // store latest price response
-                    self.last_price_response = PriceResponse{
-                        price: D128::new(number.value.0),
-                        last_updated_at: env::block_timestamp(),
-                    };
+                    self.price_responses.insert(
+                        &collateral_token,
+                        &PriceResponse{
+                            price: D128::new(number.value.0),
+                            last_updated_at: env::block_timestamp(),
+                        },
+                    );
                     // create new price request
-                    self.internal_create_new_price_request();
+                    self.internal_create_new_price_request(&collateral_token);
                 }
             }
         }
     }
+
+    /// Sends the refund address the part of the repayment the repay address did not use
+    #[private]
+    pub fn callback_refund_liquidation(&mut self, refund_address: AccountId, amount: U128) {
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+        // `ft_transfer_call` resolves to the amount the repay address used
+        let used_amount: Balance = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => 0,
+            PromiseResult::Successful(result) => {
+                serde_json::from_slice::<U128>(&result).unwrap().0
+            }
+        };
+
+        let refund_amount = amount.0.saturating_sub(used_amount);
+        if refund_amount != 0 {
+            fungible_token_transfer(self.config.stable_coin_contract.clone(), refund_address, refund_amount);
+        }
+    }
 }
@@ -2,4 +2,163 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-}
\ No newline at end of file
+    pub fn get_config(&self) -> Config {
+        self.config.clone()
+    }
+
+    pub fn get_collateral_info(&self, collateral_token: AccountId) -> CollateralInfo {
+        self.internal_read_collateral_info(&collateral_token)
+    }
+
+    pub fn get_bid(&self, bid_idx: U128) -> Bid {
+        self.internal_read_bid(bid_idx)
+    }
+
+    pub fn get_bids_by_user(&self, bidder: AccountId, start_after: Option<U128>, limit: Option<u8>) -> Vec<Bid> {
+        self.internal_read_bids_by_user(&bidder, None, start_after, limit)
+    }
+
+    pub fn get_bid_pool(&self, collateral_token: AccountId, premium_slot: u8) -> BidPool {
+        self.interanl_read_bid_pool(&collateral_token, premium_slot)
+            .expect("No bid pool with the specified information exist")
+    }
+
+    /// Pools of the collateral with their premium slot, in ascending order of slot
+    pub fn get_bid_pools(&self, collateral_token: AccountId, start_after: Option<u8>, limit: Option<u8>) -> Vec<(u8, BidPool)> {
+        self.internal_read_bid_pools(&collateral_token, start_after, limit)
+    }
+
+    /// Amount of the active bids for the collateral
+    pub fn get_total_bids(&self, collateral_token: AccountId) -> U128 {
+        self.internal_read_total_bids(&collateral_token)
+    }
+
+    /// Stable coin left in each bid of the bidder and the collateral it bought so far,
+    /// as `retract_bid` and `claim_liquidations` would compute them. The residues of
+    /// the pools are left out, they go to whoever claims next
+    pub fn get_claimable(&self, bidder: AccountId, start_after: Option<U128>, limit: Option<u8>) -> Vec<ClaimableResponse> {
+        self.internal_read_bids_by_user(&bidder, None, start_after, limit)
+            .into_iter()
+            .map(|bid| {
+                let (remaining_bid, liquidated_collateral): (U128, U128) = if bid.wait_end.is_some() {
+                    // a waiting bid has not bought anything yet
+                    (bid.amount, bid.pending_liquidated_collateral)
+                } else {
+                    let bid_pool: BidPool = self.interanl_read_bid_pool(&bid.collateral_token, bid.premium_slot)
+                        .expect("No bid pool with the specified information exist");
+                    let (remaining_bid, _) = self.internal_calculate_remaining_bid(&bid, &bid_pool);
+                    let (liquidated_collateral, _) = self.internal_calculate_liquidated_collateral(&bid);
+
+                    (remaining_bid, (bid.pending_liquidated_collateral.0 + liquidated_collateral.0).into())
+                };
+
+                ClaimableResponse {
+                    bid_idx: bid.idx,
+                    collateral_token: bid.collateral_token,
+                    remaining_bid,
+                    liquidated_collateral,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_price_status(&self, collateral_token: AccountId) -> PriceStatus {
+        let price_response: PriceResponse = self.internal_read_price_response(&collateral_token);
+        let age: u64 = env::block_timestamp().saturating_sub(price_response.last_updated_at);
+
+        PriceStatus {
+            price: price_response.price,
+            last_updated_at: price_response.last_updated_at,
+            age: age / SECOND_TO_NANO,
+            is_fresh: age <= self.config.price_timeframe * SECOND_TO_NANO,
+        }
+    }
+
+    /// Amount of each collateral the overseer has to sell so that the remaining loan
+    /// is back at `safe_ratio` of the remaining borrow limit. Every collateral is sold
+    /// in the same proportion, and the proceeds are what the queue pays after the
+    /// premium of the bids and the bid and liquidator fees.
+    /// Loans under `liquidation_threshold` are liquidated whole. The proceeds only
+    /// repay the loan in the stable coin of the queue, `loan_values` holds the value
+    /// of the loan in each stable asset and caps the sale at this one
+    pub fn get_liquidation_amount(
+        &self,
+        borrow_amount: U128,
+        borrow_limit: U128,
+        loan_values: Vec<(AccountId, U128)>,
+        collaterals: Vec<(AccountId, Balance)>,
+        collateral_prices: Vec<(AccountId, D128)>,
+        max_ltvs: Vec<(AccountId, D128)>,
+    ) -> Vec<(AccountId, Balance)> {
+        assert!(
+            collaterals.len() == collateral_prices.len() && collaterals.len() == max_ltvs.len(),
+            "Collateral prices and max LTVs do not match the collaterals"
+        );
+
+        if borrow_amount.0 <= borrow_limit.0 {
+            return collaterals.into_iter().map(|collateral| (collateral.0, 0)).collect();
+        }
+
+        let config: Config = self.config.clone();
+        let repayable_value: u128 = loan_values
+            .iter()
+            .find(|(stable_asset, _)| *stable_asset == config.stable_coin_contract)
+            .map_or(0, |(_, value)| value.0);
+        if repayable_value == 0 {
+            return collaterals.into_iter().map(|collateral| (collateral.0, 0)).collect();
+        }
+
+        let base_fee_deductor: D128 = (D128::one() - config.bid_fee) * (D128::one() - config.liquidator_fee);
+
+        // limit the collaterals give after the liquidation, and the stable the bids pay for them
+        let mut safe_borrow_limit: u128 = 0;
+        let mut repay_value: u128 = 0;
+        for ((collateral, price), max_ltv) in collaterals.iter().zip(collateral_prices.iter()).zip(max_ltvs.iter()) {
+            assert!(
+                collateral.0 == price.0 && collateral.0 == max_ltv.0,
+                "Collateral prices and max LTVs do not match the collaterals"
+            );
+
+            let collateral_info: CollateralInfo = self.internal_read_collateral_info(&collateral.0);
+            let collateral_value: D128 = value_of(collateral.1, collateral_info.decimals, price.1, VALUE_DECIMALS);
+            let premium_rate: D128 = self.internal_expected_premium_rate(&collateral.0, collateral.1, price.1);
+
+            safe_borrow_limit += (collateral_value * max_ltv.1 * config.safe_ratio).as_u128();
+            repay_value += (collateral_value * (D128::one() - premium_rate) * base_fee_deductor).as_u128();
+        }
+
+        // selling a share r of every collateral repays r * repay_value and leaves
+        // (1 - r) * safe_borrow_limit to cover the rest of the loan
+        let liquidation_ratio: D128 = if borrow_amount.0 < config.liquidation_threshold
+            || repay_value <= safe_borrow_limit
+            || borrow_amount.0 >= repay_value
+        {
+            D128::one()
+        } else if borrow_amount.0 <= safe_borrow_limit {
+            D128::zero()
+        } else {
+            D128::ratio(borrow_amount.0 - safe_borrow_limit, repay_value - safe_borrow_limit)
+        };
+        // the proceeds cannot repay the loans in other stable assets, a borrower holding
+        // some sells no more than its loan in the stable coin
+        let liquidation_ratio: D128 = if borrow_amount.0 > repayable_value && repay_value > repayable_value {
+            let repayable_ratio: D128 = D128::ratio(repayable_value, repay_value);
+            if repayable_ratio < liquidation_ratio { repayable_ratio } else { liquidation_ratio }
+        } else {
+            liquidation_ratio
+        };
+
+        collaterals
+            .into_iter()
+            .map(|collateral| {
+                let amount: u128 = if liquidation_ratio == D128::one() {
+                    collateral.1
+                } else {
+                    (collateral.1 * liquidation_ratio).as_u128()
+                };
+
+                (collateral.0, amount)
+            })
+            .collect()
+    }
+}
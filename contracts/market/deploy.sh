@@ -10,10 +10,13 @@ near deploy \
             "num": "100000000",
             "decimal": 100000000
         },
-        "stable_coin_contract": "stable_coin.testnet",
         "overseer_contract": "overseer.synchro.testnet",
         "collector_contract": "token.synchro.testnet",
-        "distributor_contract": "token.synchro.testnet",
+        "distributor_contract": "token.synchro.testnet"
+    }'
+
+near call market.synchro.testnet register_reserve '{
+        "stable_asset": "stable_coin.testnet",
         "base_rate": {
             "num": "10000000",
             "decimal": 100000000
@@ -37,5 +40,8 @@ near deploy \
         "decrement_multiplier": {
             "num": "90000000",
             "decimal": 100000000
-        }
-    }'
\ No newline at end of file
+        },
+        "atoken_contract": "ausdt.market.synchro.testnet"
+    }' \
+    --accountId blockwave.testnet \
+    --depositYocto 1
@@ -9,10 +9,13 @@ near dev-deploy \
             "num": "100000000",
             "decimal": 100000000
         },
-        "stable_coin_contract": "stable_coin.testnet",
         "overseer_contract": "overseer.synchro.testnet",
         "collector_contract": "token.synchro.testnet",
-        "distributor_contract": "token.synchro.testnet",
+        "distributor_contract": "token.synchro.testnet"
+    }'
+
+near call $(cat neardev/dev-account) register_reserve '{
+        "stable_asset": "stable_coin.testnet",
         "base_rate": {
             "num": "10000000",
             "decimal": 100000000
@@ -36,5 +39,8 @@ near dev-deploy \
         "decrement_multiplier": {
             "num": "90000000",
             "decimal": 100000000
-        }
-    }'
\ No newline at end of file
+        },
+        "atoken_contract": "ausdt.'$(cat neardev/dev-account)'"
+    }' \
+    --accountId blockwave.testnet \
+    --depositYocto 1
@@ -2,139 +2,223 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-  pub fn borrow_stable(&mut self, borrow_amount: Balance) {
+  /// The attached yocto pays the transfer of the stable asset
+  #[payable]
+  pub fn borrow_stable(&mut self, stable_asset: AccountId, borrow_amount: Balance) {
+    assert_one_yocto();
+    self.internal_borrow_stable(
+      env::predecessor_account_id(),
+      stable_asset,
+      borrow_amount,
+      None,
+    );
+  }
+
+  /// Borrows for a borrower who just locked collaterals through a transfer to
+  /// their custody, the stable goes to the borrower. The overseer computed the
+  /// borrow limit of the collaterals with the lock, the market pays the yocto of
+  /// the transfer
+  pub fn borrow_stable_for(
+    &mut self,
+    borrower: AccountId,
+    stable_asset: AccountId,
+    borrow_amount: Balance,
+    borrow_limit: U128,
+  ) {
+    self.assert_overseer();
+
+    self.internal_borrow_stable(borrower, stable_asset, borrow_amount, Some(borrow_limit));
+  }
+
+  pub fn claim_reward(&mut self, stable_asset: AccountId, to: Option<AccountId>) {
     let borrower = env::predecessor_account_id();
+    self.assert_registered(&borrower);
+    let block_height = env::block_index();
 
-    let mut liability: BorrowerInfo = self.get_borrower_info_map(&borrower);
+    self.compute_interest(&stable_asset, block_height);
 
-    self.compute_interest(env::block_index(), None);
-    self.compute_borrower_interest(&mut liability);
+    let mut reserve = self.get_reserve_map(&stable_asset);
+    let mut liability: BorrowerInfo = reserve.get_borrower_info_map(&borrower);
 
-    self.compute_reward(env::block_index());
-    self.compute_borrower_reward(&mut liability);
+    reserve.compute_borrower_interest(&mut liability);
 
-    ext_overseer::get_borrow_limit(
-      borrower,
-      Some(env::block_timestamp()),
-      &self.config.overseer_contract,
+    reserve.compute_reward(block_height);
+    reserve.compute_borrower_reward(&mut liability);
+
+    let claim_amount: Balance = liability.pending_rewards.as_u128();
+    liability.pending_rewards = liability.pending_rewards - claim_amount;
+
+    reserve.add_borrower_info_map(&borrower, &liability);
+    self.add_reserve_map(&stable_asset, &reserve);
+
+    ext_distributor::spend(
+      if let Some(to) = to { to } else { borrower },
+      claim_amount,
+      &self.config.distributor_contract,
       NO_DEPOSIT,
       SINGLE_CALL_GAS,
-    )
-    .then(ext_self::callback_borrow_stable(
+    );
+  }
+}
+
+impl Contract {
+  pub(crate) fn internal_borrow_stable(
+    &mut self,
+    borrower: AccountId,
+    stable_asset: AccountId,
+    borrow_amount: Balance,
+    borrow_limit: Option<U128>,
+  ) {
+    // the loan is stored with the registration of the borrower
+    self.assert_storage_covered(&borrower);
+    assert!(
+      env::prepaid_gas() >= GAS_FOR_BORROW,
+      "Not enough gas to borrow"
+    );
+    let block_height = env::block_index();
+
+    self.compute_interest(&stable_asset, block_height);
+
+    let mut reserve = self.get_reserve_map(&stable_asset);
+    let mut liability: BorrowerInfo = reserve.get_borrower_info_map(&borrower);
+
+    reserve.compute_borrower_interest(&mut liability);
+
+    reserve.compute_reward(block_height);
+    reserve.compute_borrower_reward(&mut liability);
+
+    self.add_reserve_map(&stable_asset, &reserve);
+
+    // the borrow limit is shared by every reserve, so the loans of the other
+    // reserves are valued together with the new one
+    let mut loans = self.internal_get_borrower_loans(&borrower);
+    loans.retain(|loan| loan.0 != stable_asset);
+    loans.push((stable_asset.clone(), liability.loan_amount + borrow_amount));
+
+    let loan_value = ext_overseer::get_loan_value(
+      loans,
+      &self.config.overseer_contract,
+      NO_DEPOSIT,
+      GAS_FOR_VIEW,
+    );
+    // the borrow limit is read from the overseer unless it came with the borrow
+    let valuation = if borrow_limit.is_some() {
+      loan_value
+    } else {
+      ext_overseer::get_borrow_limit(
+        borrower.clone(),
+        Some(env::block_timestamp()),
+        &self.config.overseer_contract,
+        NO_DEPOSIT,
+        GAS_FOR_VIEW,
+      )
+      .and(loan_value)
+    };
+    valuation.then(ext_self::callback_borrow_stable(
+      borrower,
+      stable_asset,
       borrow_amount,
       liability,
+      borrow_limit,
       &env::current_account_id(),
       NO_DEPOSIT,
-      SINGLE_CALL_GAS,
+      GAS_FOR_BORROW_CALLBACK,
     ));
   }
 
-  pub fn repay_stable_from_liquidation(&mut self, borrower: AccountId, prev_balance: Balance) {
-    self.assert_overseer();
 
-    let cur_balance: Balance = env::account_balance();
-    let balance_diff: Balance = cur_balance - prev_balance;
-    self.repay_stable(borrower, balance_diff);
-  }
-
-  pub fn repay_stable(&mut self, borrower: AccountId, amount: Balance) {
+  // Executor: stable asset contract, returns the amount exceeding the loan
+  pub(crate) fn repay_stable(
+    &mut self,
+    stable_asset: AccountId,
+    borrower: AccountId,
+    amount: Balance,
+  ) -> Balance {
     if amount == 0 {
       env::panic("".as_bytes());
     }
 
-    let mut liability: BorrowerInfo = self.get_borrower_info_map(&borrower);
-
     let block_height = env::block_index();
 
-    self.compute_interest(block_height, Some(amount));
-    self.compute_borrower_interest(&mut liability);
+    self.compute_interest(&stable_asset, block_height);
 
-    self.compute_reward(block_height);
-    self.compute_borrower_reward(&mut liability);
+    let mut reserve = self.get_reserve_map(&stable_asset);
+    let mut liability: BorrowerInfo = reserve.get_borrower_info_map(&borrower);
+
+    reserve.compute_borrower_interest(&mut liability);
+
+    reserve.compute_reward(block_height);
+    reserve.compute_borrower_reward(&mut liability);
 
     let repay_amount: Balance;
     if liability.loan_amount < amount {
       repay_amount = liability.loan_amount;
       liability.loan_amount = 0;
-
-      fungible_token::ft_transfer(
-        borrower.clone(),
-        U128::from(amount - repay_amount),
-        None,
-        &self.config.stable_coin_contract,
-        1,
-        SINGLE_CALL_GAS,
-      );
     } else {
       repay_amount = amount;
       liability.loan_amount = liability.loan_amount - repay_amount;
     }
 
-    self.state.total_liabilities = self.state.total_liabilities - repay_amount;
-
-    self.add_borrower_info_map(&borrower, &liability);
-  }
-
-  pub fn claim_reward(&mut self, to: Option<AccountId>) {
-    let borrower = env::predecessor_account_id();
-    let mut liability: BorrowerInfo = self.get_borrower_info_map(&borrower);
-
-    let block_height = env::block_index();
-
-    self.compute_interest(block_height, None);
-    self.compute_borrower_interest(&mut liability);
-
-    self.compute_reward(block_height);
-    self.compute_borrower_reward(&mut liability);
-
-    let claim_amount: Balance = liability.pending_rewards.as_u128();
-    liability.pending_rewards = liability.pending_rewards - claim_amount;
+    reserve.state.total_liabilities = reserve.state.total_liabilities - repay_amount;
+    reserve.state.balance += repay_amount;
 
-    self.add_borrower_info_map(&borrower, &liability);
+    reserve.add_borrower_info_map(&borrower, &liability);
+    self.add_reserve_map(&stable_asset, &reserve);
+    self.internal_report_isolated_debt(&borrower);
 
-    ext_distributor::spend(
-      if let Some(to) = to { to } else { borrower },
-      claim_amount,
-      &self.config.distributor_contract,
-      NO_DEPOSIT,
-      SINGLE_CALL_GAS,
-    );
+    amount - repay_amount
   }
 
-  pub(crate) fn compute_interest(
-    &self,
-    block_height: BlockHeight,
-    deposit_amount: Option<Balance>,
-  ) {
-    if self.state.last_interest_updated >= block_height {
+  pub(crate) fn compute_interest(&self, stable_asset: &AccountId, block_height: BlockHeight) {
+    let reserve = self.get_reserve_map(stable_asset);
+    if reserve.state.last_interest_updated >= block_height {
       return;
     }
 
-    fungible_token::ft_total_supply(
-      &self.config.stable_coin_contract,
-      NO_DEPOSIT,
-      SINGLE_CALL_GAS,
-    )
-    .and(fungible_token::ft_balance_of(
-      env::current_account_id(),
-      &self.config.stable_coin_contract,
-      NO_DEPOSIT,
-      SINGLE_CALL_GAS,
-    ))
-    .and(ext_overseer::get_target_deposit_rate(
+    ext_overseer::get_target_deposit_rate(
+      stable_asset.clone(),
       &self.config.overseer_contract,
       NO_DEPOSIT,
-      SINGLE_CALL_GAS,
-    ))
+      GAS_FOR_VIEW,
+    )
     .then(ext_self::callback_compute_interset(
+      stable_asset.clone(),
       block_height,
-      deposit_amount,
       &env::current_account_id(),
       NO_DEPOSIT,
-      SINGLE_CALL_GAS,
+      GAS_FOR_INTEREST_CALLBACK,
     ));
   }
 
+  /// loans of the borrower in every reserve, with the interest accrued so far
+  pub(crate) fn internal_get_borrower_loans(&self, borrower: &AccountId) -> Vec<(AccountId, Balance)> {
+    let mut loans: Vec<(AccountId, Balance)> = vec![];
+
+    for (stable_asset, reserve) in self.collection.reserve_map.iter() {
+      if let Some(mut liability) = reserve.borrower_info_map.get(borrower) {
+        reserve.compute_borrower_interest(&mut liability);
+        if liability.loan_amount > 0 {
+          loans.push((stable_asset, liability.loan_amount));
+        }
+      }
+    }
+
+    loans
+  }
+
+  /// the overseer counts the loans against the debt ceiling of an isolated collateral
+  pub(crate) fn internal_report_isolated_debt(&self, borrower: &AccountId) {
+    ext_overseer::update_isolated_debt(
+      borrower.clone(),
+      self.internal_get_borrower_loans(borrower),
+      &self.config.overseer_contract,
+      NO_DEPOSIT,
+      GAS_FOR_ISOLATED_DEBT,
+    );
+  }
+}
+
+impl Reserve {
   pub(crate) fn compute_interest_raw(
     &mut self,
     block_height: BlockHeight,
@@ -205,15 +289,17 @@ impl Contract {
     liability.reward_index = self.state.global_reward_index;
   }
 
-  pub fn assert_max_borrow_factor(&self, current_balance: Balance, borrow_amount: Balance) {
+  pub fn assert_max_borrow_factor(&self, max_borrow_factor: D128, borrow_amount: Balance) {
+    let current_balance = self.state.balance;
+
     if self.state.total_liabilities + borrow_amount
       > (current_balance + self.state.total_liabilities - self.state.total_reserves)
-        * self.config.max_borrow_factor
+        * max_borrow_factor
     {
       env::panic("Max Borrow Factor Reached".as_bytes());
     }
 
-    if borrow_amount + self.state.total_reserves - current_balance > D128::zero() {
+    if borrow_amount + self.state.total_reserves > D128::new(current_balance * 100_000_000) {
       env::panic("No Stable Available".as_bytes());
     }
   }
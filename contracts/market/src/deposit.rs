@@ -1,68 +1,62 @@
 use crate::*;
 
 impl Contract {
-  pub fn deposit_stable(&mut self, deposit_amount: Balance) {
+  // Executor: stable asset contract, the deposit is refunded if the aToken
+  // refuses to mint for the depositor
+  pub(crate) fn deposit_stable(
+    &mut self,
+    stable_asset: AccountId,
+    depositor: AccountId,
+    deposit_amount: Balance,
+  ) -> Promise {
     if deposit_amount == 0 {
       env::panic("Zero Deposit".as_bytes());
     }
 
-    let depositor = env::predecessor_account_id();
-
     let block_height = env::block_index();
 
-    self.compute_interest(block_height, Some(deposit_amount));
-    self.compute_reward(block_height);
-
-    self
-      .compute_exchange_rate(Some(deposit_amount))
-      .then(ext_self::callback_deposit_stable(
-        deposit_amount,
-        depositor,
-        &env::current_account_id(),
-        NO_DEPOSIT,
-        SINGLE_CALL_GAS,
-      ));
-  }
+    self.compute_interest(&stable_asset, block_height);
 
-  pub fn redeem_stable(&mut self, burn_amount: Balance) {
-    let block_height = env::block_index();
+    let mut reserve = self.get_reserve_map(&stable_asset);
+    reserve.compute_reward(block_height);
 
-    self.compute_interest(block_height, None);
-    self.compute_reward(block_height);
+    let exchange_rate = reserve.compute_exchange_rate();
+    let mint_amount: Balance = (deposit_amount / exchange_rate).as_u128();
 
-    let redeemer = env::predecessor_account_id();
+    reserve.state.atoken_total_supply += mint_amount;
+    reserve.state.prev_stable_coin_total_supply += mint_amount;
+    reserve.state.balance += deposit_amount;
 
-    self
-      .compute_exchange_rate(None)
-      .then(ext_self::callback_redeem_stable(
-        burn_amount,
-        redeemer,
-        &env::current_account_id(),
-        NO_DEPOSIT,
-        SINGLE_CALL_GAS,
-      ));
-  }
-
-  pub fn assert_redeem_amount(&self, current_balance: Balance, redeem_amount: D128) {
-    if redeem_amount + self.state.total_reserves > D128::new(current_balance * 100_000_000) {
-      env::panic("".as_bytes());
-    }
-  }
+    self.add_reserve_map(&stable_asset, &reserve);
 
-  pub fn compute_exchange_rate(&self, deposit_amount: Option<Balance>) -> Promise {
-    fungible_token::ft_total_supply(
-      &self.config.stable_coin_contract,
+    ext_atoken::mint(
+      depositor,
+      U128::from(mint_amount),
+      &reserve.atoken_contract,
       NO_DEPOSIT,
-      SINGLE_CALL_GAS,
+      ATOKEN_CALL_GAS,
     )
-    .then(ext_self::callback_compute_exchange_rate(
-      env::account_balance(),
+    .then(ext_self::callback_deposit_stable(
+      stable_asset,
       deposit_amount,
-      &self.config.stable_coin_contract,
+      mint_amount,
+      &env::current_account_id(),
       NO_DEPOSIT,
-      SINGLE_CALL_GAS,
+      ATOKEN_CALL_GAS,
     ))
   }
+}
+
+impl Reserve {
+  pub fn assert_redeem_amount(&self, redeem_amount: D128) {
+    if redeem_amount + self.state.total_reserves > D128::new(self.state.balance * 100_000_000) {
+      env::panic("".as_bytes());
+    }
+  }
+
+  pub fn compute_exchange_rate(&self) -> D128 {
+    self.compute_exchange_rate_raw(self.state.atoken_total_supply, self.state.balance)
+  }
 
   pub fn compute_exchange_rate_raw(
     &self,
@@ -78,3 +72,61 @@ impl Contract {
     (balance * self.state.total_liabilities - self.state.total_reserves) / stable_coin_total_supply
   }
 }
+
+#[near_bindgen]
+impl Contract {
+  // Executor: aToken holder
+  #[payable]
+  pub fn redeem_stable(&mut self, stable_asset: AccountId, burn_amount: U128) {
+    assert_one_yocto();
+    let burn_amount: Balance = burn_amount.0;
+    if burn_amount == 0 {
+      env::panic("Zero Redeem".as_bytes());
+    }
+
+    let block_height = env::block_index();
+
+    self.compute_interest(&stable_asset, block_height);
+
+    let mut reserve = self.get_reserve_map(&stable_asset);
+    reserve.compute_reward(block_height);
+
+    let redeemer = env::predecessor_account_id();
+
+    let exchange_rate = reserve.compute_exchange_rate();
+    let redeem_amount = burn_amount * exchange_rate;
+
+    reserve.assert_redeem_amount(redeem_amount);
+
+    reserve.state.atoken_total_supply = reserve
+      .state
+      .atoken_total_supply
+      .checked_sub(burn_amount)
+      .expect("The burn amount exceeds the aToken supply");
+    reserve.state.prev_stable_coin_total_supply =
+      reserve.state.prev_stable_coin_total_supply - burn_amount;
+
+    let redeem_amount = redeem_amount.as_u128();
+    reserve.state.balance -= redeem_amount;
+
+    self.add_reserve_map(&stable_asset, &reserve);
+
+    // the stable asset is only sent once the aToken burned the redeemed amount
+    ext_atoken::burn(
+      redeemer.clone(),
+      U128::from(burn_amount),
+      &reserve.atoken_contract,
+      NO_DEPOSIT,
+      ATOKEN_CALL_GAS,
+    )
+    .then(ext_self::callback_redeem_stable(
+      stable_asset,
+      redeemer,
+      burn_amount,
+      redeem_amount,
+      &env::current_account_id(),
+      NO_DEPOSIT,
+      ATOKEN_CALL_GAS + SINGLE_CALL_GAS,
+    ));
+  }
+}
@@ -9,8 +9,7 @@ pub struct DistributionModelConfig {
   pub decrement_multiplier: D128,
 }
 
-#[near_bindgen]
-impl Contract {
+impl DistributionModelConfig {
   pub fn get_emission_rate(
     &self,
     deposit_rate: D128,
@@ -24,20 +23,42 @@ impl Contract {
     let low_trigger = (mid_rate + threshold_deposit_rate) / half_dec;
 
     let emission_rate = if deposit_rate < low_trigger {
-      current_emission_rate * self.distribution_model_config.increment_multiplier
+      current_emission_rate * self.increment_multiplier
     } else if deposit_rate > high_trigger {
-      current_emission_rate * self.distribution_model_config.decrement_multiplier
+      current_emission_rate * self.decrement_multiplier
     } else {
       current_emission_rate
     };
 
-    let emission_rate = if emission_rate > self.distribution_model_config.emission_cap {
-      self.distribution_model_config.emission_cap
-    } else if emission_rate < self.distribution_model_config.emission_floor {
-      self.distribution_model_config.emission_floor
+    let emission_rate = if emission_rate > self.emission_cap {
+      self.emission_cap
+    } else if emission_rate < self.emission_floor {
+      self.emission_floor
     } else {
       emission_rate
     };
     emission_rate
   }
 }
+
+#[near_bindgen]
+impl Contract {
+  pub fn get_emission_rate(
+    &self,
+    stable_asset: AccountId,
+    deposit_rate: D128,
+    target_deposit_rate: D128,
+    threshold_deposit_rate: D128,
+    current_emission_rate: D128,
+  ) -> D128 {
+    self
+      .get_reserve_map(&stable_asset)
+      .distribution_model_config
+      .get_emission_rate(
+        deposit_rate,
+        target_deposit_rate,
+        threshold_deposit_rate,
+        current_emission_rate,
+      )
+  }
+}
@@ -1,5 +1,15 @@
 use crate::*;
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum StableReceiverMsg {
+    DepositStable,
+    RepayStable { borrower: Option<AccountId> },
+    // interest of the overseer buffer distributed to the depositors
+    DistributeInterest,
+}
+
 pub trait FungibleTokenReceiver {
     fn ft_on_transfer(
         &mut self,
@@ -17,13 +27,40 @@ impl FungibleTokenReceiver for Contract {
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
-        if env::predecessor_account_id() == self.config.stable_coin_contract {
-            self.redeem_stable(amount.0);
-            return PromiseOrValue::Value(U128(0));
-        } else {
+        let stable_asset = env::predecessor_account_id();
+        if self.collection.reserve_map.get(&stable_asset).is_none() {
             env::log(b"Only whitelisted tokens can transfer_call to this");
 
             return PromiseOrValue::Value(amount);
         }
+
+        // an empty msg keeps the plain transfer a deposit
+        let payload: StableReceiverMsg = if msg.is_empty() {
+            StableReceiverMsg::DepositStable
+        } else {
+            near_sdk::serde_json::from_str(&msg)
+                .expect("Failed to parse the payload, invalid `msg` format")
+        };
+
+        match payload {
+            StableReceiverMsg::DepositStable => {
+                PromiseOrValue::Promise(self.deposit_stable(stable_asset, sender_id, amount.0))
+            }
+            StableReceiverMsg::RepayStable { borrower } => {
+                let borrower = borrower.unwrap_or(sender_id);
+                let unused_amount = self.repay_stable(stable_asset, borrower, amount.0);
+                PromiseOrValue::Value(U128(unused_amount))
+            }
+            StableReceiverMsg::DistributeInterest => {
+                assert_eq!(
+                    sender_id, self.config.overseer_contract,
+                    "Only the overseer distributes interest"
+                );
+                let mut reserve = self.get_reserve_map(&stable_asset);
+                reserve.state.balance += amount.0;
+                self.add_reserve_map(&stable_asset, &reserve);
+                PromiseOrValue::Value(U128(0))
+            }
+        }
     }
 }
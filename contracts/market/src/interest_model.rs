@@ -7,8 +7,7 @@ pub struct InterestModelConfig {
   pub interest_multiplier: D128,
 }
 
-#[near_bindgen]
-impl Contract {
+impl InterestModelConfig {
   pub fn get_borrow_rate(
     &self,
     market_balance: Balance,
@@ -23,8 +22,23 @@ impl Contract {
       total_liabilities / total_value_in_market
     };
 
-    let rate = utilization_ratio * self.interest_model_config.interest_multiplier
-      + self.interest_model_config.base_rate;
+    let rate = utilization_ratio * self.interest_multiplier + self.base_rate;
     rate
   }
 }
+
+#[near_bindgen]
+impl Contract {
+  pub fn get_borrow_rate(
+    &self,
+    stable_asset: AccountId,
+    market_balance: Balance,
+    total_liabilities: D128,
+    total_reserves: D128,
+  ) -> D128 {
+    self
+      .get_reserve_map(&stable_asset)
+      .interest_model_config
+      .get_borrow_rate(market_balance, total_liabilities, total_reserves)
+  }
+}
@@ -17,4 +17,9 @@ impl Contract {
       "Can only be called by the overseer"
     );
   }
+
+  /// the registration pays for every entry of an account
+  pub(crate) fn storage_bytes_of(&self, _account_id: &AccountId) -> StorageUsage {
+    STORAGE_BYTES_PER_ACCOUNT
+  }
 }
@@ -1,10 +1,10 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LookupMap;
-use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     assert_one_yocto, env, ext_contract, near_bindgen, AccountId, Balance, BlockHeight,
-    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, Timestamp,
+    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, StorageUsage,
 };
 
 use uint::construct_uint;
@@ -13,8 +13,8 @@ use crate::distribution_model::DistributionModelConfig;
 // use crate::fraction::Fraction;
 use crate::interest_model::InterestModelConfig;
 use crate::math::D128;
-use crate::state::{BorrowerInfo, Collection, Config, State};
-use crate::utils::{ext_distributor, ext_overseer, ext_self, fungible_token};
+use crate::state::{BorrowerInfo, Collection, Config, Reserve, State};
+use crate::utils::{ext_atoken, ext_distributor, ext_overseer, ext_self, fungible_token};
 
 mod borrow;
 mod deposit;
@@ -26,14 +26,51 @@ mod internal;
 mod math;
 mod owner;
 mod state;
+#[path = "../../common/storage.rs"]
+mod storage;
 #[cfg(test)]
 mod testing;
+mod upgrade;
 mod utils;
 mod view;
 
 const NO_DEPOSIT: Balance = 0;
 
 const SINGLE_CALL_GAS: Gas = 100_000_000_000_000;
+// a mint or a burn of an aToken, and the callback settling it
+const ATOKEN_CALL_GAS: Gas = 10_000_000_000_000;
+
+// a view of the overseer, as its target deposit rate or a borrow limit
+const GAS_FOR_VIEW: Gas = 10_000_000_000_000;
+
+// the interest of a reserve accrued with the target deposit rate of the overseer
+const GAS_FOR_INTEREST_CALLBACK: Gas = 10_000_000_000_000;
+
+// a stable asset sent to a borrower or to the collector
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+
+// the loans of a borrower reported to the overseer for the debt ceilings
+const GAS_FOR_ISOLATED_DEBT: Gas = 10_000_000_000_000;
+
+// kept by each step of a borrow for its receipts
+const GAS_FOR_BORROW_STEP: Gas = 20_000_000_000_000;
+
+// the callback of a borrow sends the loan and reports it
+const GAS_FOR_BORROW_CALLBACK: Gas =
+    GAS_FOR_BORROW_STEP + GAS_FOR_FT_TRANSFER + GAS_FOR_ISOLATED_DEBT;
+
+// least gas of a borrow: a step accruing the interest and one valuing the loans against
+// the borrow limit, and the callback
+const GAS_FOR_BORROW: Gas = 2 * GAS_FOR_BORROW_STEP
+    + GAS_FOR_VIEW
+    + GAS_FOR_INTEREST_CALLBACK
+    + 2 * GAS_FOR_VIEW
+    + GAS_FOR_BORROW_CALLBACK;
+
+// a registration and the loans of a borrower in a few reserves
+const STORAGE_BYTES_PER_ACCOUNT: StorageUsage = 600;
+// entries of an account do not grow with its activity
+const MAX_STORAGE_BYTES_PER_ACCOUNT: Option<StorageUsage> = Some(STORAGE_BYTES_PER_ACCOUNT);
 
 construct_uint! {
     /// 256-bit unsigned integer.
@@ -42,17 +79,18 @@ construct_uint! {
 
 #[derive(BorshStorageKey, BorshSerialize)]
 pub(crate) enum StorageKey {
+    // borrowers of the market deployed before reserves, see `upgrade`
     BorrowerInfo,
+    Reserve,
+    ReserveBorrowerInfo { stable_asset: AccountId },
+    StorageBalance,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     config: Config,
-    state: State,
     collection: Collection,
-    interest_model_config: InterestModelConfig,
-    distribution_model_config: DistributionModelConfig,
 }
 
 #[near_bindgen]
@@ -61,18 +99,9 @@ impl Contract {
     pub fn new(
         owner_id: AccountId,
         max_borrow_factor: D128,
-        stable_coin_contract: AccountId,
         overseer_contract: AccountId,
         collector_contract: AccountId,
         distributor_contract: AccountId,
-
-        base_rate: D128,
-        interest_multiplier: D128,
-
-        emission_cap: D128,
-        emission_floor: D128,
-        increment_multiplier: D128,
-        decrement_multiplier: D128,
     ) -> Self {
         assert!(!env::state_exists(), "The contract is already initialized");
         assert!(
@@ -83,98 +112,108 @@ impl Contract {
         let config = Config {
             owner_id,
             max_borrow_factor,
-            stable_coin_contract,
             overseer_contract,
             collector_contract,
             distributor_contract,
         };
 
-        let state = State {
-            anc_emission_rate: D128::one(),
-            total_liabilities: D128::zero(),
-            total_reserves: D128::zero(),
-            last_interest_updated: 0,
-            global_interest_index: D128::zero(),
-            prev_exchange_rate: D128::one(),
-            prev_stable_coin_total_supply: 0,
-            last_reward_updated: 0,
-            global_reward_index: D128::zero(),
-        };
-
         let collection = Collection {
-            borrower_info_map: LookupMap::new(StorageKey::BorrowerInfo),
+            reserve_map: UnorderedMap::new(StorageKey::Reserve),
+            storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
         };
 
-        let interest_model_config = InterestModelConfig {
-            base_rate,
-            interest_multiplier,
-        };
+        Self::internal_write_state_version();
 
-        let distribution_model_config = DistributionModelConfig {
-            emission_cap,
-            emission_floor,
-            increment_multiplier,
-            decrement_multiplier,
-        };
-
-        Self {
-            config,
-            state,
-            collection,
-            interest_model_config,
-            distribution_model_config,
-        }
+        Self { config, collection }
     }
 
+    /// Returns the ANC emission rate set for the coming epoch
     pub fn execute_epoch_operations(
         &mut self,
+        stable_asset: AccountId,
         deposit_rate: D128,
         target_deposit_rate: D128,
         threshold_deposit_rate: D128,
-        distributed_intereset: U128,
-    ) {
+    ) -> D128 {
         self.assert_overseer();
-        fungible_token::ft_total_supply(
-            &self.config.stable_coin_contract,
-            NO_DEPOSIT,
-            SINGLE_CALL_GAS,
-        )
-        .then(ext_self::callback_execute_epoch_operations(
+
+        let mut reserve = self.get_reserve_map(&stable_asset);
+
+        let stable_coin_total_supply = reserve.state.atoken_total_supply;
+        // the distributed interest is credited once the overseer transferred it
+        let balance: Balance = reserve.state.balance;
+
+        let borrow_rate = reserve.interest_model_config.get_borrow_rate(
+            balance,
+            reserve.state.total_liabilities,
+            reserve.state.total_reserves,
+        );
+        let block_height = env::block_index();
+
+        reserve.compute_interest_raw(
+            block_height,
+            balance,
+            stable_coin_total_supply,
+            borrow_rate,
+            target_deposit_rate,
+        );
+
+        reserve.state.prev_exchange_rate =
+            reserve.compute_exchange_rate_raw(stable_coin_total_supply, reserve.state.balance);
+
+        reserve.compute_reward(block_height);
+
+        let total_reserves = reserve.state.total_reserves.as_u128();
+
+        reserve.state.anc_emission_rate = reserve.distribution_model_config.get_emission_rate(
             deposit_rate,
             target_deposit_rate,
             threshold_deposit_rate,
-            distributed_intereset,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            SINGLE_CALL_GAS,
-        ));
+            reserve.state.anc_emission_rate,
+        );
+
+        if total_reserves != 0 && balance > total_reserves {
+            reserve.state.total_reserves = reserve.state.total_reserves - total_reserves;
+            reserve.state.balance -= total_reserves;
+            fungible_token::ft_transfer(
+                self.config.collector_contract.clone(),
+                total_reserves.into(),
+                None,
+                &stable_asset,
+                1,
+                GAS_FOR_FT_TRANSFER,
+            );
+        }
+
+        let anc_emission_rate = reserve.state.anc_emission_rate;
+        self.add_reserve_map(&stable_asset, &reserve);
+
+        anc_emission_rate
     }
 
     pub fn get_epoch_state(
         &mut self,
+        stable_asset: AccountId,
         block_height: Option<BlockHeight>,
         distributed_intereset: Option<U128>,
     ) -> Promise {
         let distributed_intereset = distributed_intereset.unwrap_or(U128::from(0));
-        let balance: Balance = env::account_balance() - distributed_intereset.0;
+        let balance: Balance = self.get_reserve_map(&stable_asset).state.balance;
 
         ext_overseer::get_target_deposit_rate(
+            stable_asset.clone(),
             &self.config.overseer_contract,
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            GAS_FOR_VIEW,
         )
-        .and(fungible_token::ft_total_supply(
-            &self.config.stable_coin_contract,
-            NO_DEPOSIT,
-            SINGLE_CALL_GAS,
-        ))
         .then(ext_self::callback_get_epoch_state(
+            stable_asset,
             block_height,
             balance,
             distributed_intereset,
             &env::current_account_id(),
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            GAS_FOR_INTEREST_CALLBACK,
         ))
     }
 }
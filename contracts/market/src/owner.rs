@@ -6,7 +6,6 @@ impl Contract {
   pub fn update_config(
     &mut self,
     owner_id: Option<AccountId>,
-    stable_coin_contract: Option<AccountId>,
     max_borrow_factor: Option<D128>,
     overseer_contract: Option<AccountId>,
     collector_contract: Option<AccountId>,
@@ -18,9 +17,6 @@ impl Contract {
     if let Some(owner_id) = owner_id {
       self.config.owner_id = owner_id;
     }
-    if let Some(stable_coin_contract) = stable_coin_contract {
-      self.config.stable_coin_contract = stable_coin_contract;
-    }
     if let Some(max_borrow_factor) = max_borrow_factor {
       self.config.max_borrow_factor = max_borrow_factor;
     }
@@ -35,27 +31,80 @@ impl Contract {
     }
   }
 
+  #[payable]
+  pub fn register_reserve(
+    &mut self,
+    stable_asset: AccountId,
+
+    base_rate: D128,
+    interest_multiplier: D128,
+
+    emission_cap: D128,
+    emission_floor: D128,
+    increment_multiplier: D128,
+    decrement_multiplier: D128,
+
+    atoken_contract: AccountId,
+  ) {
+    assert_one_yocto();
+    self.assert_owner();
+    assert!(
+      self.collection.reserve_map.get(&stable_asset).is_none(),
+      "Reserve already registered"
+    );
+    assert!(
+      env::is_valid_account_id(atoken_contract.as_bytes()),
+      "The aToken account ID is invalid"
+    );
+
+    let interest_model_config = InterestModelConfig {
+      base_rate,
+      interest_multiplier,
+    };
+
+    let distribution_model_config = DistributionModelConfig {
+      emission_cap,
+      emission_floor,
+      increment_multiplier,
+      decrement_multiplier,
+    };
+
+    let reserve = Reserve::new(
+      &stable_asset,
+      interest_model_config,
+      distribution_model_config,
+      atoken_contract,
+    );
+    self.add_reserve_map(&stable_asset, &reserve);
+  }
+
   #[payable]
   pub fn update_interest_model_config(
     &mut self,
+    stable_asset: AccountId,
     base_rate: Option<D128>,
     interest_multiplier: Option<D128>,
   ) {
     assert_one_yocto();
     self.assert_owner();
 
+    let mut reserve = self.get_reserve_map(&stable_asset);
+
     if let Some(base_rate) = base_rate {
-      self.interest_model_config.base_rate = base_rate;
+      reserve.interest_model_config.base_rate = base_rate;
     }
 
     if let Some(interest_multiplier) = interest_multiplier {
-      self.interest_model_config.interest_multiplier = interest_multiplier;
+      reserve.interest_model_config.interest_multiplier = interest_multiplier;
     }
+
+    self.add_reserve_map(&stable_asset, &reserve);
   }
 
   #[payable]
   pub fn update_distribution_model_config(
     &mut self,
+    stable_asset: AccountId,
     emission_cap: Option<D128>,
     emission_floor: Option<D128>,
     increment_multiplier: Option<D128>,
@@ -64,20 +113,24 @@ impl Contract {
     assert_one_yocto();
     self.assert_owner();
 
+    let mut reserve = self.get_reserve_map(&stable_asset);
+
     if let Some(emission_cap) = emission_cap {
-      self.distribution_model_config.emission_cap = emission_cap;
+      reserve.distribution_model_config.emission_cap = emission_cap;
     }
 
     if let Some(emission_floor) = emission_floor {
-      self.distribution_model_config.emission_floor = emission_floor;
+      reserve.distribution_model_config.emission_floor = emission_floor;
     }
 
     if let Some(increment_multiplier) = increment_multiplier {
-      self.distribution_model_config.increment_multiplier = increment_multiplier;
+      reserve.distribution_model_config.increment_multiplier = increment_multiplier;
     }
 
     if let Some(decrement_multiplier) = decrement_multiplier {
-      self.distribution_model_config.decrement_multiplier = decrement_multiplier;
+      reserve.distribution_model_config.decrement_multiplier = decrement_multiplier;
     }
+
+    self.add_reserve_map(&stable_asset, &reserve);
   }
 }
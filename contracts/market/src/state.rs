@@ -4,7 +4,6 @@ use crate::*;
 #[serde(crate = "near_sdk::serde")]
 pub struct Config {
   pub owner_id: AccountId,
-  pub stable_coin_contract: AccountId,
   pub max_borrow_factor: D128,
   pub overseer_contract: AccountId,
   pub collector_contract: AccountId,
@@ -25,6 +24,9 @@ pub struct State {
   pub prev_stable_coin_total_supply: u128,
   pub last_reward_updated: BlockHeight,
   pub global_reward_index: D128,
+  pub atoken_total_supply: Balance,
+  // stable asset held by the market for this reserve
+  pub balance: Balance,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -36,25 +38,96 @@ pub struct BorrowerInfo {
   pub pending_rewards: D128,
 }
 
+/// Money market of a single stable asset
 #[derive(BorshDeserialize, BorshSerialize)]
-pub struct Collection {
+pub struct Reserve {
+  pub state: State,
+  pub interest_model_config: InterestModelConfig,
+  pub distribution_model_config: DistributionModelConfig,
+  // NEP-141 aToken of the reserve, minted and burned by the market only
+  pub atoken_contract: AccountId,
   pub borrower_info_map: LookupMap<AccountId, BorrowerInfo>,
 }
 
-#[near_bindgen]
-impl Contract {
-  pub fn add_borrower_info_map(&mut self, key: &String, value: &BorrowerInfo) {
-    self.collection.borrower_info_map.insert(&key, value);
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Collection {
+  pub reserve_map: UnorderedMap<AccountId, Reserve>,
+  // NEP-145 storage balance of the registered accounts
+  pub storage_balance_map: LookupMap<AccountId, Balance>,
+}
+
+impl Reserve {
+  pub fn new(
+    stable_asset: &AccountId,
+    interest_model_config: InterestModelConfig,
+    distribution_model_config: DistributionModelConfig,
+    atoken_contract: AccountId,
+  ) -> Self {
+    let state = State {
+      anc_emission_rate: D128::one(),
+      total_liabilities: D128::zero(),
+      total_reserves: D128::zero(),
+      last_interest_updated: 0,
+      global_interest_index: D128::zero(),
+      prev_exchange_rate: D128::one(),
+      prev_stable_coin_total_supply: 0,
+      last_reward_updated: 0,
+      global_reward_index: D128::zero(),
+      atoken_total_supply: 0,
+      balance: 0,
+    };
+
+    Self {
+      state,
+      interest_model_config,
+      distribution_model_config,
+      atoken_contract,
+      borrower_info_map: LookupMap::new(StorageKey::ReserveBorrowerInfo {
+        stable_asset: stable_asset.clone(),
+      }),
+    }
   }
 
   pub fn get_borrower_info_map(&self, key: &String) -> BorrowerInfo {
-    match self.collection.borrower_info_map.get(&key) {
+    match self.borrower_info_map.get(&key) {
       Some(value) => {
         let log_message = format!("Value from LookupMap is {:?}", value.clone());
         env::log(log_message.as_bytes());
         value
       }
-      None => env::panic("".as_bytes()),
+      // created on the first borrow of a registered account
+      None => BorrowerInfo {
+        interest_index: D128::one(),
+        reward_index: D128::zero(),
+        loan_amount: 0,
+        pending_rewards: D128::zero(),
+      },
     }
   }
+
+  pub(crate) fn add_borrower_info_map(&mut self, key: &String, value: &BorrowerInfo) {
+    self.borrower_info_map.insert(key, value);
+  }
+}
+
+impl Contract {
+  pub(crate) fn add_reserve_map(&mut self, key: &String, value: &Reserve) {
+    self.collection.reserve_map.insert(&key, value);
+  }
+
+  pub(crate) fn get_reserve_map(&self, key: &String) -> Reserve {
+    match self.collection.reserve_map.get(&key) {
+      Some(value) => value,
+      None => env::panic("Reserve not found".as_bytes()),
+    }
+  }
+}
+
+#[near_bindgen]
+impl Contract {
+  pub fn get_borrower_info_map(&self, stable_asset: &String, key: &String) -> BorrowerInfo {
+    self
+      .get_reserve_map(stable_asset)
+      .get_borrower_info_map(key)
+  }
 }
@@ -1,6 +1,11 @@
-use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::{self, json, Value};
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
 use near_sdk::{testing_env, MockedBlockchain};
 
+use crate::testing::{
+  callback_context, created_function_call_gas, created_function_calls, register,
+};
 use crate::*;
 
 pub fn setup_contract() -> (VMContextBuilder, Contract) {
@@ -9,26 +14,30 @@ pub fn setup_contract() -> (VMContextBuilder, Contract) {
     .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
     .attached_deposit(1)
     .build());
-  let contract = Contract::new(
+  let mut contract = Contract::new(
     AccountId::from("owner"),
     D128::zero(),
-    AccountId::from("stable_coin"),
     AccountId::from("overseer"),
     AccountId::from("collector"),
     AccountId::from("distributor"),
+  );
+  contract.register_reserve(
+    AccountId::from("stable_coin"),
     D128::new_exp(1, -1),
     D128::new_exp(1, -1),
     D128::new_exp(100, 0),
     D128::new_exp(10, 0),
     D128::new_exp(11, -1),
     D128::new_exp(9, -1),
+    AccountId::from("atoken"),
   );
   (context, contract)
 }
 
 #[test]
 fn proper_compute_borrower_interest() {
-  let (context, mut contract) = setup_contract();
+  let (context, contract) = setup_contract();
+  let mut reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
   let mock_state = State {
     total_liabilities: D128::new(1000000u128 * 100_000_000),
     total_reserves: D128::zero(),
@@ -39,6 +48,8 @@ fn proper_compute_borrower_interest() {
     anc_emission_rate: D128::one(),
     prev_stable_coin_total_supply: 0,
     prev_exchange_rate: D128::one(),
+    atoken_total_supply: 0,
+    balance: 0,
   };
   let mut liability1 = BorrowerInfo {
     interest_index: D128::one(),
@@ -46,8 +57,8 @@ fn proper_compute_borrower_interest() {
     loan_amount: 0,
     pending_rewards: D128::zero(),
   };
-  contract.state = mock_state;
-  contract.compute_borrower_interest(&mut liability1);
+  reserve.state = mock_state;
+  reserve.compute_borrower_interest(&mut liability1);
   let liability2 = BorrowerInfo {
     interest_index: D128::one(),
     reward_index: D128::zero(),
@@ -66,6 +77,8 @@ fn proper_compute_borrower_interest() {
     anc_emission_rate: D128::zero(),
     prev_stable_coin_total_supply: 0,
     prev_exchange_rate: D128::one(),
+    atoken_total_supply: 0,
+    balance: 0,
   };
   let mut liability3 = BorrowerInfo {
     interest_index: D128::new(4 * 100_000_000),
@@ -73,8 +86,8 @@ fn proper_compute_borrower_interest() {
     loan_amount: 80,
     pending_rewards: D128::zero(),
   };
-  contract.state = mock_state2;
-  contract.compute_borrower_interest(&mut liability3);
+  reserve.state = mock_state2;
+  reserve.compute_borrower_interest(&mut liability3);
   let liability4 = BorrowerInfo {
     interest_index: D128::new(2 * 100_000_000),
     reward_index: D128::zero(),
@@ -86,18 +99,9 @@ fn proper_compute_borrower_interest() {
 
 #[test]
 fn proper_compute_interest() {
-  let (mut context, mut contract) = setup_contract();
-
-  let mock_config = Config {
-    owner_id: AccountId::from("owner"),
-    stable_coin_contract: AccountId::from("stable_coin"),
-    max_borrow_factor: D128::one(),
-    overseer_contract: AccountId::from("overseer"),
-    collector_contract: AccountId::from("collector"),
-    distributor_contract: AccountId::from("distributor"),
-  };
+  let (context, mut contract) = setup_contract();
 
-  let mut mock_state = State {
+  let mock_state = State {
     total_liabilities: D128::new(1000000u128 * 100_000_000),
     total_reserves: D128::zero(),
     last_interest_updated: context.context.block_index,
@@ -107,12 +111,14 @@ fn proper_compute_interest() {
     anc_emission_rate: D128::one(),
     prev_stable_coin_total_supply: 0,
     prev_exchange_rate: D128::one(),
+    atoken_total_supply: 0,
+    balance: 0,
   };
-  contract.state = mock_state;
+  let mut reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
+  reserve.state = mock_state;
+  contract.add_reserve_map(&AccountId::from("stable_coin"), &reserve);
 
-  let mock_deposit_amount = Some(1000u128);
-
-  contract.compute_interest(context.context.block_index, mock_deposit_amount);
+  contract.compute_interest(&AccountId::from("stable_coin"), context.context.block_index);
 
   assert_eq!(
     mock_state,
@@ -126,6 +132,8 @@ fn proper_compute_interest() {
       anc_emission_rate: D128::one(),
       prev_stable_coin_total_supply: 0,
       prev_exchange_rate: D128::one(),
+      atoken_total_supply: 0,
+      balance: 0,
     }
   );
 
@@ -150,3 +158,214 @@ fn proper_compute_interest() {
   //   }
   // );
 }
+
+#[test]
+#[should_panic(expected = "Can only be called by the overseer")]
+fn borrow_stable_for_from_someone() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("someone").unwrap())
+    .build());
+
+  contract.borrow_stable_for(
+    AccountId::from("borrower"),
+    AccountId::from("stable_coin"),
+    30,
+    U128(1000),
+  );
+}
+
+fn setup_borrower() -> (VMContextBuilder, Contract) {
+  let (mut context, mut contract) = setup_contract();
+  contract.config.max_borrow_factor = D128::one();
+  let mut reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
+  reserve.state.global_interest_index = D128::one();
+  contract.add_reserve_map(&AccountId::from("stable_coin"), &reserve);
+  register(&mut context, &mut contract, "borrower");
+
+  (context, contract)
+}
+
+#[test]
+fn borrow_stable_for_within_forwarded_gas() {
+  let (mut context, mut contract) = setup_borrower();
+  // the overseer forwards the least gas of a borrow, the interest accrues first
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .attached_deposit(0)
+    .block_index(1)
+    .prepaid_gas(GAS_FOR_BORROW)
+    .build());
+
+  contract.borrow_stable_for(
+    AccountId::from("borrower"),
+    AccountId::from("stable_coin"),
+    30,
+    U128(1000),
+  );
+
+  // the borrow limit came with the borrow, only the loans are valued
+  let methods: Vec<String> = created_function_calls()
+    .into_iter()
+    .map(|function_call| function_call.1)
+    .collect();
+  assert_eq!(
+    vec![
+      "get_target_deposit_rate",
+      "callback_compute_interset",
+      "get_loan_value",
+      "callback_borrow_stable"
+    ],
+    methods
+  );
+  assert_eq!(
+    vec![
+      GAS_FOR_VIEW,
+      GAS_FOR_INTEREST_CALLBACK,
+      GAS_FOR_VIEW,
+      GAS_FOR_BORROW_CALLBACK
+    ],
+    created_function_call_gas()
+  );
+  assert_eq!("1000", created_function_calls()[3].2["borrow_limit"]);
+}
+
+#[test]
+fn borrow_stable_reads_borrow_limit() {
+  let (mut context, mut contract) = setup_borrower();
+  testing_env!(context
+    .attached_deposit(1)
+    .prepaid_gas(GAS_FOR_BORROW)
+    .build());
+
+  contract.borrow_stable(AccountId::from("stable_coin"), 30);
+
+  let function_calls = created_function_calls();
+  assert_eq!("get_borrow_limit", function_calls[0].1);
+  assert_eq!("get_loan_value", function_calls[1].1);
+  assert_eq!(Value::Null, function_calls[2].2["borrow_limit"]);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to borrow")]
+fn borrow_stable_without_gas() {
+  let (mut context, mut contract) = setup_borrower();
+  testing_env!(context
+    .attached_deposit(1)
+    .prepaid_gas(GAS_FOR_BORROW - 1)
+    .build());
+
+  contract.borrow_stable(AccountId::from("stable_coin"), 30);
+}
+
+#[test]
+#[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+fn borrow_stable_without_yocto() {
+  let (mut context, mut contract) = setup_borrower();
+  testing_env!(context.attached_deposit(0).build());
+
+  contract.borrow_stable(AccountId::from("stable_coin"), 30);
+}
+
+/// Runs the callback of a borrow of 30 valued at `loan_value` within a borrow
+/// limit of 1000, from a reserve holding `balance`
+fn callback_borrow(
+  context: &mut VMContextBuilder,
+  contract: &mut Contract,
+  balance: Balance,
+  loan_value: u128,
+) {
+  let mut reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
+  reserve.state.balance = balance;
+  contract.add_reserve_map(&AccountId::from("stable_coin"), &reserve);
+  let mut liability = contract.get_borrower_info_map(
+    &AccountId::from("stable_coin"),
+    &AccountId::from("borrower"),
+  );
+
+  context.prepaid_gas(GAS_FOR_BORROW_CALLBACK);
+  callback_context(
+    context,
+    vec![PromiseResult::Successful(
+      serde_json::to_vec(&U128(loan_value)).unwrap(),
+    )],
+  );
+  contract.callback_borrow_stable(
+    AccountId::from("borrower"),
+    AccountId::from("stable_coin"),
+    30,
+    &mut liability,
+    Some(U128(1000)),
+  );
+}
+
+#[test]
+fn callback_borrow_stable_sends_loan() {
+  let (mut context, mut contract) = setup_borrower();
+
+  callback_borrow(&mut context, &mut contract, 1000, 30);
+
+  assert_eq!(
+    30,
+    contract
+      .get_borrower_info_map(
+        &AccountId::from("stable_coin"),
+        &AccountId::from("borrower")
+      )
+      .loan_amount
+  );
+  let state = contract.get_state(AccountId::from("stable_coin"));
+  assert_eq!(970, state.balance);
+  assert_eq!(D128::new_exp(30, 0), state.total_liabilities);
+
+  let function_calls = created_function_calls();
+  assert_eq!(
+    (
+      String::from("overseer"),
+      String::from("update_isolated_debt")
+    ),
+    (function_calls[0].0.clone(), function_calls[0].1.clone())
+  );
+  assert_eq!(
+    (
+      String::from("stable_coin"),
+      String::from("ft_transfer"),
+      json!({"receiver_id": "borrower", "amount": "30", "memo": null})
+    ),
+    function_calls[1]
+  );
+  assert_eq!(
+    vec![GAS_FOR_ISOLATED_DEBT, GAS_FOR_FT_TRANSFER],
+    created_function_call_gas()
+  );
+  // the transfer of the stable asset requires one yocto
+  let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!(1, receipts[1]["actions"][0]["FunctionCall"]["deposit"]);
+}
+
+#[test]
+#[should_panic(expected = "Borrow exceed limit")]
+fn callback_borrow_stable_over_limit() {
+  let (mut context, mut contract) = setup_borrower();
+
+  callback_borrow(&mut context, &mut contract, 1000, 1001);
+}
+
+#[test]
+#[should_panic(expected = "Max Borrow Factor Reached")]
+fn callback_borrow_stable_over_max_borrow_factor() {
+  let (mut context, mut contract) = setup_borrower();
+  contract.config.max_borrow_factor = D128::new_exp(5, -1);
+
+  callback_borrow(&mut context, &mut contract, 50, 30);
+}
+
+#[test]
+#[should_panic(expected = "No Stable Available")]
+fn callback_borrow_stable_without_stable() {
+  let (mut context, mut contract) = setup_borrower();
+  // a factor above one lends more than the reserve holds
+  contract.config.max_borrow_factor = D128::new_exp(2, 0);
+
+  callback_borrow(&mut context, &mut contract, 20, 30);
+}
@@ -1,6 +1,10 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::json;
 use near_sdk::test_utils::{accounts, VMContextBuilder};
 use near_sdk::{testing_env, MockedBlockchain};
 
+use crate::fungible_token_handler::FungibleTokenReceiver;
+use crate::testing::{callback_context, created_function_calls};
 use crate::*;
 
 pub fn setup_contract() -> (VMContextBuilder, Contract) {
@@ -9,48 +13,150 @@ pub fn setup_contract() -> (VMContextBuilder, Contract) {
     .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
     .attached_deposit(1)
     .build());
-  let contract = Contract::new(
+  let mut contract = Contract::new(
     AccountId::from("owner"),
     D128::zero(),
-    AccountId::from("stable_coin"),
     AccountId::from("overseer"),
     AccountId::from("collector"),
     AccountId::from("distributor"),
+  );
+  contract.register_reserve(
+    AccountId::from("stable_coin"),
     D128::new_exp(1, -1),
     D128::new_exp(1, -1),
     D128::new_exp(100, 0),
     D128::new_exp(10, 0),
     D128::new_exp(11, -1),
     D128::new_exp(9, -1),
+    AccountId::from("atoken"),
   );
   (context, contract)
 }
 
 #[test]
 fn proper_compute_exchange_rate() {
-  let (context, mut contract) = setup_contract();
-  let mock_config = Config {
-    owner_id: AccountId::from("owner"),
-    stable_coin_contract: AccountId::from("stable_coin"),
-    max_borrow_factor: D128::one(),
-    overseer_contract: AccountId::from("overseer"),
-    collector_contract: AccountId::from("collector"),
-    distributor_contract: AccountId::from("distributor"),
-  };
-  let mock_state = State {
-    total_liabilities: D128::new(50000u128 * 100_000_000),
-    total_reserves: D128::new(550000u128 * 100_000_000),
-    last_interest_updated: context.context.block_index,
-    last_reward_updated: context.context.block_index,
-    global_interest_index: D128::one(),
-    global_reward_index: D128::zero(),
-    anc_emission_rate: D128::one(),
-    prev_stable_coin_total_supply: 0,
-    prev_exchange_rate: D128::one(),
-  };
-  let mock_deposit_amount = Some(1000000u128);
-
-  // let exchange_rate = contract.compute_exchange_rate(mock_deposit_amount);
-
-  // assert_eq!(exchange_rate, D128::new_exp(5, -1));
+  let (_, contract) = setup_contract();
+  let reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
+
+  // no aToken minted yet
+  assert_eq!(D128::one(), reserve.compute_exchange_rate());
+}
+
+/// bob deposits 1000 of the stable coin, at an exchange rate of one
+fn deposit(context: &mut VMContextBuilder, contract: &mut Contract) {
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("stable_coin").unwrap())
+    .attached_deposit(0)
+    .build());
+  contract.ft_on_transfer(accounts(1).into(), U128(1000), String::from(""));
+}
+
+#[test]
+fn deposit_mints_atoken() {
+  let (mut context, mut contract) = setup_contract();
+
+  deposit(&mut context, &mut contract);
+
+  let calls = created_function_calls();
+  assert_eq!(
+    (
+      String::from("atoken"),
+      String::from("mint"),
+      json!({"account_id": "bob", "amount": "1000"})
+    ),
+    calls[0]
+  );
+  assert_eq!("callback_deposit_stable", calls[1].1);
+
+  let state = contract.get_state(AccountId::from("stable_coin"));
+  assert_eq!(1000, state.atoken_total_supply);
+  assert_eq!(1000, state.balance);
+}
+
+#[test]
+fn refund_deposit_refused_by_atoken() {
+  let (mut context, mut contract) = setup_contract();
+  deposit(&mut context, &mut contract);
+
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+  let unused_amount = contract.callback_deposit_stable(AccountId::from("stable_coin"), 1000, 1000);
+
+  assert_eq!(U128(1000), unused_amount);
+  let state = contract.get_state(AccountId::from("stable_coin"));
+  assert_eq!(0, state.atoken_total_supply);
+  assert_eq!(0, state.prev_stable_coin_total_supply);
+  assert_eq!(0, state.balance);
+}
+
+#[test]
+fn redeem_after_the_burn() {
+  let (mut context, mut contract) = setup_contract();
+  deposit(&mut context, &mut contract);
+
+  testing_env!(context
+    .predecessor_account_id(accounts(1))
+    .attached_deposit(1)
+    .build());
+  contract.redeem_stable(AccountId::from("stable_coin"), U128(400));
+
+  let calls = created_function_calls();
+  assert_eq!(
+    (
+      String::from("atoken"),
+      String::from("burn"),
+      json!({"account_id": "bob", "amount": "400"})
+    ),
+    calls[0]
+  );
+  assert_eq!("callback_redeem_stable", calls[1].1);
+  let redeem_amount = calls[1].2["redeem_amount"].as_u64().unwrap() as Balance;
+  let state = contract.get_state(AccountId::from("stable_coin"));
+  assert_eq!(600, state.atoken_total_supply);
+  assert_eq!(1000 - redeem_amount, state.balance);
+
+  callback_context(&mut context, vec![PromiseResult::Successful(vec![])]);
+  contract.callback_redeem_stable(
+    AccountId::from("stable_coin"),
+    accounts(1).into(),
+    400,
+    redeem_amount,
+  );
+
+  assert_eq!(
+    vec![(
+      String::from("stable_coin"),
+      String::from("ft_transfer"),
+      json!({"receiver_id": "bob", "amount": redeem_amount.to_string(), "memo": null})
+    )],
+    created_function_calls()
+  );
+}
+
+#[test]
+fn restore_redeem_refused_by_atoken() {
+  let (mut context, mut contract) = setup_contract();
+  deposit(&mut context, &mut contract);
+
+  testing_env!(context
+    .predecessor_account_id(accounts(1))
+    .attached_deposit(1)
+    .build());
+  contract.redeem_stable(AccountId::from("stable_coin"), U128(400));
+  let redeem_amount = created_function_calls()[1].2["redeem_amount"]
+    .as_u64()
+    .unwrap() as Balance;
+
+  callback_context(&mut context, vec![PromiseResult::Failed]);
+  contract.callback_redeem_stable(
+    AccountId::from("stable_coin"),
+    accounts(1).into(),
+    400,
+    redeem_amount,
+  );
+
+  assert!(created_function_calls().is_empty());
+  let state = contract.get_state(AccountId::from("stable_coin"));
+  assert_eq!(1000, state.atoken_total_supply);
+  assert_eq!(1000, state.prev_stable_coin_total_supply);
+  assert_eq!(1000, state.balance);
 }
@@ -1,3 +1,69 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::{self, Value};
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain, RuntimeFeesConfig, VMConfig};
+
+use crate::*;
+
 mod borrow_ut;
 mod deposit_ut;
+mod reserve_ut;
+mod storage_ut;
 mod tests;
+mod upgrade_ut;
+
+/// Registers the account with the minimum storage balance, as the caller
+pub(crate) fn register(context: &mut VMContextBuilder, contract: &mut Contract, account_id: &str) {
+  let min_balance = contract.storage_balance_bounds().min.0;
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from(account_id).unwrap())
+    .attached_deposit(min_balance)
+    .build());
+  contract.storage_deposit(None, None);
+}
+
+/// Receiver, method and arguments of every function call the contract made
+pub(crate) fn created_function_calls() -> Vec<(String, String, Value)> {
+  let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+
+  let mut function_calls = vec![];
+  for receipt in receipts.as_array().unwrap() {
+    for action in receipt["actions"].as_array().unwrap() {
+      let function_call = &action["FunctionCall"];
+      function_calls.push((
+        receipt["receiver_id"].as_str().unwrap().to_string(),
+        function_call["method_name"].as_str().unwrap().to_string(),
+        serde_json::from_str(function_call["args"].as_str().unwrap()).unwrap(),
+      ));
+    }
+  }
+
+  function_calls
+}
+
+/// Gas attached to every function call the contract made
+pub(crate) fn created_function_call_gas() -> Vec<Gas> {
+  let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+  receipts
+    .as_array()
+    .unwrap()
+    .iter()
+    .flat_map(|receipt| receipt["actions"].as_array().unwrap().clone())
+    .map(|action| action["FunctionCall"]["gas"].as_u64().unwrap())
+    .collect()
+}
+
+/// Runs the next call as a callback of the market receiving `results`
+pub(crate) fn callback_context(context: &mut VMContextBuilder, results: Vec<PromiseResult>) {
+  let market = ValidAccountId::try_from(context.context.current_account_id.as_str()).unwrap();
+  testing_env!(
+    context
+      .predecessor_account_id(market)
+      .attached_deposit(0)
+      .build(),
+    VMConfig::default(),
+    RuntimeFeesConfig::default(),
+    Default::default(),
+    results
+  );
+}
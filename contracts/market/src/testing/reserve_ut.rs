@@ -0,0 +1,258 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+
+use crate::fungible_token_handler::FungibleTokenReceiver;
+use crate::testing::{created_function_call_gas, created_function_calls};
+use crate::*;
+
+fn register_reserve(contract: &mut Contract, stable_asset: &str, atoken: &str) {
+  contract.register_reserve(
+    AccountId::from(stable_asset),
+    D128::new_exp(1, -1),
+    D128::new_exp(1, -1),
+    D128::new_exp(100, 0),
+    D128::new_exp(10, 0),
+    D128::new_exp(11, -1),
+    D128::new_exp(9, -1),
+    AccountId::from(atoken),
+  );
+}
+
+pub fn setup_contract() -> (VMContextBuilder, Contract) {
+  let mut context = VMContextBuilder::new();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .attached_deposit(1)
+    .build());
+  let mut contract = Contract::new(
+    AccountId::from("owner"),
+    D128::one(),
+    AccountId::from("overseer"),
+    AccountId::from("collector"),
+    AccountId::from("distributor"),
+  );
+  register_reserve(&mut contract, "usdt", "ausdt");
+  register_reserve(&mut contract, "usdc", "ausdc");
+  (context, contract)
+}
+
+#[test]
+fn proper_register_reserve() {
+  let (_, contract) = setup_contract();
+
+  let mut reserves = contract.get_reserves();
+  reserves.sort();
+  assert_eq!(
+    vec![AccountId::from("usdc"), AccountId::from("usdt")],
+    reserves
+  );
+  assert_eq!(
+    D128::new_exp(1, -1),
+    contract
+      .get_interest_model_config(AccountId::from("usdc"))
+      .base_rate
+  );
+  assert_eq!(
+    AccountId::from("ausdc"),
+    contract.get_atoken_contract(AccountId::from("usdc"))
+  );
+}
+
+#[test]
+#[should_panic(expected = "Reserve already registered")]
+fn register_reserve_twice() {
+  let (_, mut contract) = setup_contract();
+
+  register_reserve(&mut contract, "usdt", "ausdt");
+}
+
+#[test]
+#[should_panic(expected = "Reserve not found")]
+fn unknown_reserve() {
+  let (_, contract) = setup_contract();
+
+  contract.get_state(AccountId::from("dai"));
+}
+
+#[test]
+fn deposit_into_its_own_reserve() {
+  let (mut context, mut contract) = setup_contract();
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("usdt").unwrap())
+    .build());
+  contract.ft_on_transfer(accounts(1).into(), U128(1000), String::from(""));
+  assert_eq!(
+    (String::from("ausdt"), String::from("mint")),
+    (
+      created_function_calls()[0].0.clone(),
+      created_function_calls()[0].1.clone()
+    )
+  );
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("usdc").unwrap())
+    .build());
+  contract.ft_on_transfer(
+    accounts(1).into(),
+    U128(300),
+    String::from("\"deposit_stable\""),
+  );
+  assert_eq!(
+    (String::from("ausdc"), String::from("mint")),
+    (
+      created_function_calls()[0].0.clone(),
+      created_function_calls()[0].1.clone()
+    )
+  );
+
+  assert_eq!(1000, contract.get_balance(AccountId::from("usdt")));
+  assert_eq!(300, contract.get_balance(AccountId::from("usdc")));
+  assert_eq!(
+    1000,
+    contract
+      .get_state(AccountId::from("usdt"))
+      .atoken_total_supply
+  );
+  assert_eq!(
+    300,
+    contract
+      .get_state(AccountId::from("usdc"))
+      .atoken_total_supply
+  );
+}
+
+#[test]
+fn refuse_unknown_stable_asset() {
+  let (mut context, mut contract) = setup_contract();
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("dai").unwrap())
+    .build());
+  let result = contract.ft_on_transfer(accounts(1).into(), U128(1000), String::from(""));
+
+  match result {
+    PromiseOrValue::Value(unused_amount) => assert_eq!(U128(1000), unused_amount),
+    PromiseOrValue::Promise(_) => panic!("Unexpected promise"),
+  }
+}
+
+#[test]
+fn repay_in_its_own_reserve() {
+  let (mut context, mut contract) = setup_contract();
+
+  for stable_asset in ["usdt", "usdc"] {
+    let mut reserve = contract.get_reserve_map(&AccountId::from(stable_asset));
+    reserve.state.global_interest_index = D128::one();
+    reserve.state.total_liabilities = D128::new_exp(500, 0);
+    reserve.borrower_info_map.insert(
+      &accounts(1).into(),
+      &BorrowerInfo {
+        interest_index: D128::one(),
+        reward_index: D128::zero(),
+        loan_amount: 500,
+        pending_rewards: D128::zero(),
+      },
+    );
+    contract.add_reserve_map(&AccountId::from(stable_asset), &reserve);
+  }
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("usdc").unwrap())
+    .build());
+  let result = contract.ft_on_transfer(
+    accounts(2).into(),
+    U128(600),
+    format!("{{\"repay_stable\": {{\"borrower\": \"{}\"}}}}", accounts(1)),
+  );
+
+  // the excess is refunded by the stable asset
+  match result {
+    PromiseOrValue::Value(unused_amount) => assert_eq!(U128(100), unused_amount),
+    PromiseOrValue::Promise(_) => panic!("Unexpected promise"),
+  }
+
+  assert_eq!(
+    vec![(AccountId::from("usdt"), 500)],
+    contract.get_borrower_loans(accounts(1).into())
+  );
+  assert_eq!(500, contract.get_balance(AccountId::from("usdc")));
+  assert_eq!(
+    D128::zero(),
+    contract
+      .get_state(AccountId::from("usdc"))
+      .total_liabilities
+  );
+
+  // the loans left are reported to the overseer for the debt ceilings
+  assert_eq!(
+    (
+      String::from("overseer"),
+      String::from("update_isolated_debt")
+    ),
+    (
+      created_function_calls()[0].0.clone(),
+      created_function_calls()[0].1.clone()
+    )
+  );
+  assert_eq!(vec![GAS_FOR_ISOLATED_DEBT], created_function_call_gas());
+}
+
+#[test]
+fn distribute_interest_into_its_own_reserve() {
+  let (mut context, mut contract) = setup_contract();
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("usdc").unwrap())
+    .build());
+  let result = contract.ft_on_transfer(
+    AccountId::from("overseer"),
+    U128(300),
+    String::from("\"distribute_interest\""),
+  );
+
+  match result {
+    PromiseOrValue::Value(unused_amount) => assert_eq!(U128(0), unused_amount),
+    PromiseOrValue::Promise(_) => panic!("Unexpected promise"),
+  }
+  assert_eq!(300, contract.get_state(AccountId::from("usdc")).balance);
+  assert_eq!(0, contract.get_state(AccountId::from("usdt")).balance);
+}
+
+#[test]
+#[should_panic(expected = "Only the overseer distributes interest")]
+fn distribute_interest_from_someone() {
+  let (mut context, mut contract) = setup_contract();
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("usdc").unwrap())
+    .build());
+  contract.ft_on_transfer(
+    accounts(1).into(),
+    U128(300),
+    String::from("\"distribute_interest\""),
+  );
+}
+
+#[test]
+fn execute_epoch_keeps_balance() {
+  let (mut context, mut contract) = setup_contract();
+  let mut reserve = contract.get_reserve_map(&AccountId::from("usdc"));
+  reserve.state.balance = 1000;
+  reserve.state.global_interest_index = D128::one();
+  contract.add_reserve_map(&AccountId::from("usdc"), &reserve);
+
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+    .build());
+  contract.execute_epoch_operations(
+    AccountId::from("usdc"),
+    D128::zero(),
+    D128::zero(),
+    D128::zero(),
+  );
+
+  // the interest distributed by the overseer is only credited once received
+  assert_eq!(1000, contract.get_state(AccountId::from("usdc")).balance);
+}
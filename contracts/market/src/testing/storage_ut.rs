@@ -0,0 +1,76 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::get_created_receipts;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use crate::testing::borrow_ut::setup_contract;
+use crate::testing::register;
+use crate::*;
+
+#[test]
+fn proper_storage_deposit() {
+  let (mut context, mut contract) = setup_contract();
+  assert_eq!(
+    None,
+    contract.storage_balance_of(AccountId::from("borrower"))
+  );
+
+  register(&mut context, &mut contract, "borrower");
+
+  let storage_balance = contract
+    .storage_balance_of(AccountId::from("borrower"))
+    .unwrap();
+  assert_eq!(contract.storage_balance_bounds().min, storage_balance.total);
+  assert_eq!(0, storage_balance.available.0);
+}
+
+#[test]
+fn first_borrow_of_registered_account() {
+  let (mut context, mut contract) = setup_contract();
+  let mut reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
+  reserve.state.global_interest_index = D128::one();
+  contract.add_reserve_map(&AccountId::from("stable_coin"), &reserve);
+  register(&mut context, &mut contract, "borrower");
+
+  testing_env!(context
+    .attached_deposit(1)
+    .prepaid_gas(10u64.pow(15))
+    .build());
+  contract.borrow_stable(AccountId::from("stable_coin"), 30);
+
+  let receipts = near_sdk::serde_json::to_value(get_created_receipts()).unwrap();
+  assert_eq!(
+    "get_borrow_limit",
+    receipts[0]["actions"][0]["FunctionCall"]["method_name"]
+  );
+  assert_eq!(
+    0,
+    contract
+      .get_borrower_info_map(
+        &AccountId::from("stable_coin"),
+        &AccountId::from("borrower")
+      )
+      .loan_amount
+  );
+}
+
+#[test]
+#[should_panic(expected = "The account borrower is not registered")]
+fn borrow_of_unregistered_account() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .build());
+
+  contract.borrow_stable(AccountId::from("stable_coin"), 30);
+}
+
+#[test]
+#[should_panic(expected = "The account borrower is not registered")]
+fn claim_reward_of_unregistered_account() {
+  let (mut context, mut contract) = setup_contract();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+    .build());
+
+  contract.claim_reward(AccountId::from("stable_coin"), None);
+}
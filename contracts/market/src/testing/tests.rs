@@ -1,4 +1,5 @@
-use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
 use near_sdk::{testing_env, MockedBlockchain};
 
 use crate::*;
@@ -9,19 +10,22 @@ pub fn setup_contract() -> (VMContextBuilder, Contract) {
     .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
     .attached_deposit(1)
     .build());
-  let contract = Contract::new(
+  let mut contract = Contract::new(
     AccountId::from("owner"),
     D128::zero(),
-    AccountId::from("stable_coin"),
     AccountId::from("overseer"),
     AccountId::from("collector"),
     AccountId::from("distributor"),
+  );
+  contract.register_reserve(
+    AccountId::from("stable_coin"),
     D128::new_exp(1, -1),
     D128::new_exp(1, -1),
     D128::new_exp(100, 0),
     D128::new_exp(10, 0),
     D128::new_exp(11, -1),
     D128::new_exp(9, -1),
+    AccountId::from("atoken"),
   );
   (context, contract)
 }
@@ -32,38 +36,40 @@ fn proper_initialization() {
 
   assert_eq!(AccountId::from("owner"), contract.config.owner_id);
   assert_eq!(D128::zero(), contract.config.max_borrow_factor);
-  assert_eq!(
-    AccountId::from("stable_coin"),
-    contract.config.stable_coin_contract
-  );
   assert_eq!(
     AccountId::from("overseer"),
     contract.config.overseer_contract
   );
-  assert_eq!(D128::zero(), contract.state.anc_emission_rate);
+  assert_eq!(
+    vec![AccountId::from("stable_coin")],
+    contract.get_reserves()
+  );
+
+  let reserve = contract.get_reserve_map(&AccountId::from("stable_coin"));
+  assert_eq!(D128::zero(), reserve.state.anc_emission_rate);
   assert_eq!(
     D128::new_exp(1, -1),
-    contract.interest_model_config.base_rate
+    reserve.interest_model_config.base_rate
   );
   assert_eq!(
     D128::new_exp(1, -1),
-    contract.interest_model_config.interest_multiplier
+    reserve.interest_model_config.interest_multiplier
   );
   assert_eq!(
     D128::new_exp(100, 0),
-    contract.distribution_model_config.emission_cap
+    reserve.distribution_model_config.emission_cap
   );
   assert_eq!(
     D128::new_exp(10, 0),
-    contract.distribution_model_config.emission_floor
+    reserve.distribution_model_config.emission_floor
   );
   assert_eq!(
     D128::new_exp(11, -1),
-    contract.distribution_model_config.increment_multiplier
+    reserve.distribution_model_config.increment_multiplier
   );
   assert_eq!(
     D128::new_exp(9, -1),
-    contract.distribution_model_config.decrement_multiplier
+    reserve.distribution_model_config.decrement_multiplier
   );
 }
 
@@ -73,7 +79,6 @@ fn update_config() {
 
   contract.update_config(
     None,
-    Some(AccountId::from("stable_coin1")),
     Some(D128::one()),
     Some(AccountId::from("overseer1")),
     Some(AccountId::from("collector1")),
@@ -81,10 +86,6 @@ fn update_config() {
   );
 
   assert_eq!(D128::one(), contract.config.max_borrow_factor);
-  assert_eq!(
-    AccountId::from("stable_coin1"),
-    contract.config.stable_coin_contract
-  );
   assert_eq!(
     AccountId::from("overseer1"),
     contract.config.overseer_contract
@@ -98,14 +99,7 @@ fn update_config() {
     contract.config.collector_contract
   );
 
-  contract.update_config(
-    Some(AccountId::from("owner1")),
-    None,
-    None,
-    None,
-    None,
-    None,
-  );
+  contract.update_config(Some(AccountId::from("owner1")), None, None, None, None);
 
   assert_eq!(AccountId::from("owner1"), contract.config.owner_id);
 }
@@ -115,22 +109,8 @@ fn update_config() {
 fn assert_owner() {
   let (_, mut contract) = setup_contract();
 
-  contract.update_config(
-    Some(AccountId::from("owner1")),
-    None,
-    None,
-    None,
-    None,
-    None,
-  );
-  contract.update_config(
-    Some(AccountId::from("owner2")),
-    None,
-    None,
-    None,
-    None,
-    None,
-  );
+  contract.update_config(Some(AccountId::from("owner1")), None, None, None, None);
+  contract.update_config(Some(AccountId::from("owner2")), None, None, None, None);
 }
 
 #[test]
@@ -144,13 +124,19 @@ fn proper_borrow_rate() {
   let (_, contract) = setup_contract();
 
   let rate = contract.get_borrow_rate(
+    AccountId::from("stable_coin"),
     1000000u128,
     D128::new_exp(500000, 0),
     D128::new_exp(100000, 0),
   );
   assert_eq!(D128::ratio(19, 140), rate);
 
-  let rate = contract.get_borrow_rate(0u128, D128::zero(), D128::zero());
+  let rate = contract.get_borrow_rate(
+    AccountId::from("stable_coin"),
+    0u128,
+    D128::zero(),
+    D128::zero(),
+  );
   assert_eq!(D128::new_exp(1, -1), rate);
 }
 
@@ -162,6 +148,7 @@ fn proper_emission_rate() {
   // low = 6.75
   // no changes
   let rate = contract.get_emission_rate(
+    AccountId::from("stable_coin"),
     D128::new_exp(7, -2),
     D128::new_exp(1, -1),
     D128::new_exp(5, -2),
@@ -171,6 +158,7 @@ fn proper_emission_rate() {
 
   // increment
   let rate = contract.get_emission_rate(
+    AccountId::from("stable_coin"),
     D128::new_exp(5, -2),
     D128::new_exp(1, -1),
     D128::new_exp(5, -2),
@@ -180,6 +168,7 @@ fn proper_emission_rate() {
 
   // cap
   let rate = contract.get_emission_rate(
+    AccountId::from("stable_coin"),
     D128::new_exp(5, -2),
     D128::new_exp(1, -1),
     D128::new_exp(5, -2),
@@ -189,6 +178,7 @@ fn proper_emission_rate() {
 
   // decrement
   let rate = contract.get_emission_rate(
+    AccountId::from("stable_coin"),
     D128::new_exp(9, -2),
     D128::new_exp(1, -1),
     D128::new_exp(5, -2),
@@ -198,6 +188,7 @@ fn proper_emission_rate() {
 
   // floor
   let rate = contract.get_emission_rate(
+    AccountId::from("stable_coin"),
     D128::new_exp(9, -2),
     D128::new_exp(1, -1),
     D128::new_exp(5, -2),
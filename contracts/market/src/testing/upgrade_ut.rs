@@ -0,0 +1,122 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use crate::upgrade::{CollectionV0, ConfigV0, ContractV0, StateV0};
+use crate::*;
+
+fn setup_v0_state() -> VMContextBuilder {
+  let mut context = VMContextBuilder::new();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+    .build());
+
+  let mut borrower_info_map = LookupMap::new(StorageKey::BorrowerInfo);
+  borrower_info_map.insert(
+    &AccountId::from("borrower"),
+    &BorrowerInfo {
+      interest_index: D128::one(),
+      reward_index: D128::zero(),
+      loan_amount: 1000,
+      pending_rewards: D128::zero(),
+    },
+  );
+
+  let old = ContractV0 {
+    config: ConfigV0 {
+      owner_id: AccountId::from("owner"),
+      stable_coin_contract: AccountId::from("stable_coin"),
+      max_borrow_factor: D128::one(),
+      overseer_contract: AccountId::from("overseer"),
+      collector_contract: AccountId::from("collector"),
+      distributor_contract: AccountId::from("distributor"),
+    },
+    state: StateV0 {
+      anc_emission_rate: D128::one(),
+      total_liabilities: D128::new_exp(1000, 0),
+      total_reserves: D128::zero(),
+      last_interest_updated: 10,
+      global_interest_index: D128::one(),
+      prev_exchange_rate: D128::one(),
+      prev_stable_coin_total_supply: 5000,
+      last_reward_updated: 10,
+      global_reward_index: D128::zero(),
+    },
+    collection: CollectionV0 { borrower_info_map },
+    interest_model_config: InterestModelConfig {
+      base_rate: D128::new_exp(1, -1),
+      interest_multiplier: D128::new_exp(1, -1),
+    },
+    distribution_model_config: DistributionModelConfig {
+      emission_cap: D128::new_exp(100, 0),
+      emission_floor: D128::new_exp(10, 0),
+      increment_multiplier: D128::new_exp(11, -1),
+      decrement_multiplier: D128::new_exp(9, -1),
+    },
+  };
+  env::state_write(&old);
+
+  context
+}
+
+#[test]
+fn proper_migrate_from_v0() {
+  setup_v0_state();
+
+  let contract = Contract::migrate();
+  let stable_coin = AccountId::from("stable_coin");
+
+  assert_eq!(1, contract.get_state_version());
+  assert_eq!(AccountId::from("owner"), contract.config.owner_id);
+  assert_eq!(vec![stable_coin.clone()], contract.get_reserves());
+
+  let state = contract.get_state(stable_coin.clone());
+  assert_eq!(D128::new_exp(1000, 0), state.total_liabilities);
+  assert_eq!(5000, state.prev_stable_coin_total_supply);
+  assert_eq!(0, state.atoken_total_supply);
+  assert_eq!(0, state.balance);
+  assert_eq!(
+    stable_coin.clone(),
+    contract.get_atoken_contract(stable_coin.clone())
+  );
+  assert_eq!(
+    1000,
+    contract
+      .get_borrower_info_map(&stable_coin, &AccountId::from("borrower"))
+      .loan_amount
+  );
+  assert_eq!(
+    D128::new_exp(9, -1),
+    contract
+      .get_distribution_model_config(stable_coin)
+      .decrement_multiplier
+  );
+}
+
+#[test]
+fn proper_migrate_current_version() {
+  setup_v0_state();
+
+  let contract = Contract::migrate();
+  env::state_write(&contract);
+
+  let contract = Contract::migrate();
+  assert_eq!(1, contract.get_state_version());
+  assert_eq!(
+    D128::new_exp(1000, 0),
+    contract
+      .get_state(AccountId::from("stable_coin"))
+      .total_liabilities
+  );
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the owner")]
+fn migrate_only_owner() {
+  let mut context = setup_v0_state();
+  testing_env!(context
+    .predecessor_account_id(ValidAccountId::try_from("someone").unwrap())
+    .build());
+
+  Contract::migrate();
+}
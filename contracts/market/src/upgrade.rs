@@ -0,0 +1,162 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+/// Layouts the contract state has been persisted with. The version is kept
+/// under `STATE_VERSION_KEY`; the deployment made before it existed is `V0`,
+/// converted into the current layout by `migrate`.
+pub enum VersionedContract {
+  V0(ContractV0),
+  V1(Contract),
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ConfigV0 {
+  pub owner_id: AccountId,
+  pub stable_coin_contract: AccountId,
+  pub max_borrow_factor: D128,
+  pub overseer_contract: AccountId,
+  pub collector_contract: AccountId,
+  pub distributor_contract: AccountId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StateV0 {
+  pub anc_emission_rate: D128,
+  pub total_liabilities: D128,
+  pub total_reserves: D128,
+  pub last_interest_updated: BlockHeight,
+  pub global_interest_index: D128,
+  pub prev_exchange_rate: D128,
+  pub prev_stable_coin_total_supply: u128,
+  pub last_reward_updated: BlockHeight,
+  pub global_reward_index: D128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CollectionV0 {
+  pub borrower_info_map: LookupMap<AccountId, BorrowerInfo>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+  pub config: ConfigV0,
+  pub state: StateV0,
+  pub collection: CollectionV0,
+  pub interest_model_config: InterestModelConfig,
+  pub distribution_model_config: DistributionModelConfig,
+}
+
+impl From<ContractV0> for Contract {
+  fn from(old: ContractV0) -> Self {
+    let stable_asset = old.config.stable_coin_contract;
+
+    let config = Config {
+      owner_id: old.config.owner_id,
+      max_borrow_factor: old.config.max_borrow_factor,
+      overseer_contract: old.config.overseer_contract,
+      collector_contract: old.config.collector_contract,
+      distributor_contract: old.config.distributor_contract,
+    };
+
+    // the stable asset held so far was never tracked by the market, it is
+    // accounted from the first deposit, repay or distribution after the upgrade
+    let state = State {
+      anc_emission_rate: old.state.anc_emission_rate,
+      total_liabilities: old.state.total_liabilities,
+      total_reserves: old.state.total_reserves,
+      last_interest_updated: old.state.last_interest_updated,
+      global_interest_index: old.state.global_interest_index,
+      prev_exchange_rate: old.state.prev_exchange_rate,
+      prev_stable_coin_total_supply: old.state.prev_stable_coin_total_supply,
+      last_reward_updated: old.state.last_reward_updated,
+      global_reward_index: old.state.global_reward_index,
+      atoken_total_supply: 0,
+      balance: 0,
+    };
+
+    // the single market of the old layout becomes the reserve of its stable
+    // asset, keeping the storage of its borrowers. Its deposits were minted by
+    // the stable coin contract, which stays the aToken they are redeemed from
+    let reserve = Reserve {
+      state,
+      interest_model_config: old.interest_model_config,
+      distribution_model_config: old.distribution_model_config,
+      atoken_contract: stable_asset.clone(),
+      borrower_info_map: old.collection.borrower_info_map,
+    };
+
+    let mut collection = Collection {
+      reserve_map: UnorderedMap::new(StorageKey::Reserve),
+      // the borrowers of the old layout keep their entries without a registration
+      storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
+    };
+    collection.reserve_map.insert(&stable_asset, &reserve);
+
+    Self { config, collection }
+  }
+}
+
+impl Contract {
+  pub(crate) fn internal_read_state_version() -> u8 {
+    env::storage_read(STATE_VERSION_KEY)
+      .map(|value| value[0])
+      .unwrap_or(0)
+  }
+
+  pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+    match Self::internal_read_state_version() {
+      0 => VersionedContract::V0(env::state_read().expect("Failed to read the V0 state")),
+      CURRENT_STATE_VERSION => {
+        VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+      }
+      _ => env::panic("Unknown state version".as_bytes()),
+    }
+  }
+
+  pub(crate) fn internal_write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+  }
+}
+
+#[near_bindgen]
+impl Contract {
+  /// Converts the stored state into the current layout. Called by `upgrade`
+  /// right after the new code is deployed, or by the owner directly.
+  #[init(ignore_state)]
+  pub fn migrate() -> Self {
+    let contract: Contract = match Self::internal_read_versioned_state() {
+      VersionedContract::V0(old) => old.into(),
+      VersionedContract::V1(contract) => contract,
+    };
+
+    if env::predecessor_account_id() != env::current_account_id() {
+      contract.assert_owner();
+    }
+
+    Self::internal_write_state_version();
+
+    contract
+  }
+
+  /// Deploys the wasm blob attached as the call input and migrates the state.
+  pub fn upgrade(&self) -> Promise {
+    self.assert_owner();
+
+    let code = env::input().expect("Error: No code attached");
+    let migrate_gas = env::prepaid_gas()
+      .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+      .expect("Not enough gas attached to migrate after the upgrade");
+
+    Promise::new(env::current_account_id())
+      .deploy_contract(code)
+      .function_call(b"migrate".to_vec(), b"{}".to_vec(), NO_DEPOSIT, migrate_gas)
+  }
+
+  pub fn get_state_version(&self) -> u8 {
+    Self::internal_read_state_version()
+  }
+}
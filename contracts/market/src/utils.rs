@@ -2,15 +2,16 @@ use crate::*;
 
 #[ext_contract(fungible_token)]
 pub trait FungibleToken {
-  fn ft_total_supply(&self) -> U128;
-
   fn ft_balance_of(&self, account_id: AccountId) -> U128;
 
   fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
 
-  fn mint(&mut self, account_id: AccountId, amount: Balance);
+#[ext_contract(ext_atoken)]
+pub trait ATokenContract {
+  fn mint(&mut self, account_id: AccountId, amount: U128);
 
-  fn burn(&mut self, account_id: AccountId, amount: Balance);
+  fn burn(&mut self, account_id: AccountId, amount: U128);
 }
 
 #[ext_contract(ext_overseer)]
@@ -21,7 +22,11 @@ pub trait OverseerContract {
     block_time: Option<BlockHeight>,
   ) -> (AccountId, U128);
 
-  fn get_target_deposit_rate(&self) -> D128;
+  fn get_loan_value(&self, loans: Vec<(AccountId, Balance)>) -> U128;
+
+  fn get_target_deposit_rate(&self, stable_asset: AccountId) -> D128;
+
+  fn update_isolated_debt(&mut self, borrower: AccountId, loans: Vec<(AccountId, Balance)>);
 }
 
 #[ext_contract(ext_distributor)]
@@ -31,97 +36,45 @@ pub trait CollectorContract {
 
 #[ext_contract(ext_self)]
 pub trait Contract {
-  fn callback_compute_interset(
-    &mut self,
-    block_height: BlockHeight,
-    deposit_amount: Option<Balance>,
-  );
-
-  fn callback_borrow_stable(&self, borrow_amount: Balance, liability: BorrowerInfo);
-
-  fn callback_compute_exchange_rate(
-    &self,
-    amount: Balance,
-    deposit_amount: Option<Balance>,
-  ) -> D128;
+  fn callback_compute_interset(&mut self, stable_asset: AccountId, block_height: BlockHeight);
 
-  fn callback_deposit_stable(&self, deposit_amount: Balance, depositor: AccountId);
-
-  fn callback_redeem_stable(&self, burn_amount: Balance, redeemer: AccountId);
-
-  fn callback_execute_epoch_operations(
+  fn callback_borrow_stable(
     &self,
-    deposit_rate: D128,
-    target_deposit_rate: D128,
-    threshold_deposit_rate: D128,
-    distributed_intereset: U128,
+    borrower: AccountId,
+    stable_asset: AccountId,
+    borrow_amount: Balance,
+    liability: BorrowerInfo,
+    borrow_limit: Option<U128>,
   );
 
   fn callback_get_epoch_state(
     &mut self,
+    stable_asset: AccountId,
     block_height: Option<BlockHeight>,
     balance: Balance,
     distributed_intereset: U128,
   ) -> (D128, U128);
-}
 
-#[near_bindgen]
-impl Contract {
-  #[private]
-  pub fn callback_compute_interset(
+  fn callback_deposit_stable(
     &mut self,
-    block_height: BlockHeight,
-    deposit_amount: Option<Balance>,
-  ) {
-    assert_eq!(env::promise_results_count(), 3, "This is a callback method");
+    stable_asset: AccountId,
+    deposit_amount: Balance,
+    mint_amount: Balance,
+  ) -> U128;
 
-    match env::promise_result(0) {
-      PromiseResult::NotReady => unreachable!(),
-      PromiseResult::Failed => {
-        env::panic("Failed Promise".as_bytes());
-      }
-      PromiseResult::Successful(result) => {
-        let stable_coin_total_supply = near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0;
-        match env::promise_result(1) {
-          PromiseResult::NotReady => unreachable!(),
-          PromiseResult::Failed => {
-            env::panic("Failed Promise".as_bytes());
-          }
-          PromiseResult::Successful(result) => {
-            let balance = near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0
-              - deposit_amount.unwrap_or(0);
-
-            match env::promise_result(2) {
-              PromiseResult::NotReady => unreachable!(),
-              PromiseResult::Failed => {
-                env::panic("Failed Promise".as_bytes());
-              }
-              PromiseResult::Successful(result) => {
-                let target_deposit_rate =
-                  near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
-                let borrow_rate = self.get_borrow_rate(
-                  balance,
-                  self.state.total_liabilities,
-                  self.state.total_reserves,
-                );
-
-                self.compute_interest_raw(
-                  block_height,
-                  balance,
-                  stable_coin_total_supply,
-                  borrow_rate,
-                  target_deposit_rate,
-                )
-              }
-            }
-          }
-        }
-      }
-    }
-  }
+  fn callback_redeem_stable(
+    &mut self,
+    stable_asset: AccountId,
+    redeemer: AccountId,
+    burn_amount: Balance,
+    redeem_amount: Balance,
+  );
+}
 
+#[near_bindgen]
+impl Contract {
   #[private]
-  pub fn callback_borrow_stable(&mut self, borrow_amount: Balance, liability: &mut BorrowerInfo) {
+  pub fn callback_compute_interset(&mut self, stable_asset: AccountId, block_height: BlockHeight) {
     assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
     match env::promise_result(0) {
@@ -130,237 +83,198 @@ impl Contract {
         env::panic("Failed Promise".as_bytes());
       }
       PromiseResult::Successful(result) => {
-        let (borrower, borrow_limit_raw) =
-          near_sdk::serde_json::from_slice::<(AccountId, U128)>(&result).unwrap();
-        let borrow_limit = borrow_limit_raw.0;
-
-        if borrow_limit < borrow_amount + liability.loan_amount {
-          env::panic("Borrow exceed limit".as_bytes());
-        }
-
-        let current_balance = env::account_balance();
-
-        self.assert_max_borrow_factor(current_balance, borrow_amount);
-
-        liability.loan_amount += borrow_amount;
-        self.state.total_liabilities = self.state.total_liabilities + borrow_amount;
+        let target_deposit_rate = near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
 
-        self.add_borrower_info_map(&borrower, liability);
+        let mut reserve = self.get_reserve_map(&stable_asset);
+        let balance = reserve.state.balance;
+        let borrow_rate = reserve.interest_model_config.get_borrow_rate(
+          balance,
+          reserve.state.total_liabilities,
+          reserve.state.total_reserves,
+        );
 
-        fungible_token::ft_transfer(
-          borrower,
-          borrow_amount.into(),
-          None,
-          &self.config.stable_coin_contract,
-          NO_DEPOSIT,
-          SINGLE_CALL_GAS,
+        let stable_coin_total_supply = reserve.state.atoken_total_supply;
+        reserve.compute_interest_raw(
+          block_height,
+          balance,
+          stable_coin_total_supply,
+          borrow_rate,
+          target_deposit_rate,
         );
+
+        self.add_reserve_map(&stable_asset, &reserve);
       }
     }
   }
 
+  /// Lends once the loans of the borrower are valued within the borrow limit,
+  /// which the overseer computed with a lock or is read from it
   #[private]
-  pub fn callback_compute_exchange_rate(
-    &self,
-    amount: Balance,
-    deposit_amount: Option<Balance>,
-  ) -> D128 {
-    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
-
-    match env::promise_result(0) {
-      PromiseResult::NotReady => unreachable!(),
-      PromiseResult::Failed => {
-        env::panic("fail".as_bytes());
+  pub fn callback_borrow_stable(
+    &mut self,
+    borrower: AccountId,
+    stable_asset: AccountId,
+    borrow_amount: Balance,
+    liability: &mut BorrowerInfo,
+    borrow_limit: Option<U128>,
+  ) {
+    let results_count = if borrow_limit.is_some() { 1 } else { 2 };
+    assert_eq!(
+      env::promise_results_count(),
+      results_count,
+      "This is a callback method"
+    );
+
+    let results: Vec<Vec<u8>> = (0..results_count)
+      .map(|index| match env::promise_result(index) {
+        PromiseResult::NotReady => unreachable!(),
+        PromiseResult::Failed => env::panic("Failed Promise".as_bytes()),
+        PromiseResult::Successful(result) => result,
+      })
+      .collect();
+
+    let borrow_limit = match borrow_limit {
+      Some(borrow_limit) => borrow_limit.0,
+      None => {
+        let (_, borrow_limit) =
+          near_sdk::serde_json::from_slice::<(AccountId, U128)>(&results[0]).unwrap();
+        borrow_limit.0
       }
-      PromiseResult::Successful(result) => {
-        let stable_coin_total_supply = near_sdk::serde_json::from_slice::<U128>(&result).unwrap();
+    };
+    let loan_value = near_sdk::serde_json::from_slice::<U128>(results.last().unwrap())
+      .unwrap()
+      .0;
 
-        let balance = amount - deposit_amount.unwrap_or(0);
-
-        self.compute_exchange_rate_raw(stable_coin_total_supply.into(), balance)
-      }
+    if borrow_limit < loan_value {
+      env::panic("Borrow exceed limit".as_bytes());
     }
-  }
 
-  #[private]
-  pub fn callback_deposit_stable(&mut self, deposit_amount: Balance, depositor: AccountId) {
-    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+    let mut reserve = self.get_reserve_map(&stable_asset);
 
-    match env::promise_result(0) {
-      PromiseResult::NotReady => unreachable!(),
-      PromiseResult::Failed => {
-        env::panic("fail".as_bytes());
-      }
-      PromiseResult::Successful(result) => {
-        let exchange_rate = near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
-        let mint_amount: Balance = (deposit_amount / exchange_rate).as_u128();
+    reserve.assert_max_borrow_factor(self.config.max_borrow_factor, borrow_amount);
 
-        self.state.prev_stable_coin_total_supply += mint_amount;
+    liability.loan_amount += borrow_amount;
+    reserve.state.total_liabilities = reserve.state.total_liabilities + borrow_amount;
+    reserve.state.balance -= borrow_amount;
 
-        fungible_token::mint(
-          depositor,
-          mint_amount,
-          &self.config.stable_coin_contract,
-          NO_DEPOSIT,
-          SINGLE_CALL_GAS,
-        );
-      }
-    }
+    reserve.add_borrower_info_map(&borrower, liability);
+    self.add_reserve_map(&stable_asset, &reserve);
+    self.internal_report_isolated_debt(&borrower);
+
+    fungible_token::ft_transfer(
+      borrower,
+      borrow_amount.into(),
+      None,
+      &stable_asset,
+      1,
+      GAS_FOR_FT_TRANSFER,
+    );
   }
 
   #[private]
-  pub fn callback_redeem_stable(&mut self, burn_amount: Balance, redeemer: AccountId) {
+  pub fn callback_get_epoch_state(
+    &mut self,
+    stable_asset: AccountId,
+    block_height: Option<BlockHeight>,
+    balance: Balance,
+    distributed_intereset: U128,
+  ) -> (D128, U128) {
     assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
     match env::promise_result(0) {
       PromiseResult::NotReady => unreachable!(),
       PromiseResult::Failed => {
-        env::panic("fail".as_bytes());
+        env::panic("Failed Promise".as_bytes());
       }
       PromiseResult::Successful(result) => {
-        let exchange_rate = near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
-        let redeem_amount = burn_amount * exchange_rate;
+        let target_deposit_rate = near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
 
-        let current_balance = env::account_balance();
+        let mut reserve = self.get_reserve_map(&stable_asset);
+        let stable_coin_total_supply = reserve.state.atoken_total_supply;
 
-        self.assert_redeem_amount(current_balance, redeem_amount);
+        if let Some(block_height) = block_height {
+          if block_height < reserve.state.last_interest_updated {
+            env::panic("block_height must bigger than last_interest_updated".as_bytes());
+          }
+          let borrow_rate = reserve.interest_model_config.get_borrow_rate(
+            balance,
+            reserve.state.total_liabilities,
+            reserve.state.total_reserves,
+          );
 
-        self.state.prev_stable_coin_total_supply =
-          self.state.prev_stable_coin_total_supply - burn_amount;
+          reserve.compute_interest_raw(
+            block_height,
+            balance,
+            stable_coin_total_supply,
+            borrow_rate,
+            target_deposit_rate,
+          );
 
-        fungible_token::burn(
-          redeemer.clone(),
-          burn_amount,
-          &self.config.stable_coin_contract,
-          NO_DEPOSIT,
-          SINGLE_CALL_GAS,
-        )
-        .and(fungible_token::ft_transfer(
-          redeemer,
-          U128::from(redeem_amount.as_u128()),
-          None,
-          &self.config.stable_coin_contract,
-          NO_DEPOSIT,
-          SINGLE_CALL_GAS,
-        ));
+          self.add_reserve_map(&stable_asset, &reserve);
+        }
+        let exchange_rate = reserve
+          .compute_exchange_rate_raw(stable_coin_total_supply, balance + distributed_intereset.0);
+
+        (exchange_rate, U128::from(stable_coin_total_supply))
       }
     }
   }
-
+  /// Returns the deposit the stable asset refunds, all of it when the aToken
+  /// refused to mint
   #[private]
-  pub fn callback_execute_epoch_operations(
+  pub fn callback_deposit_stable(
     &mut self,
-    deposit_rate: D128,
-    target_deposit_rate: D128,
-    threshold_deposit_rate: D128,
-    distributed_intereset: U128,
-  ) {
+    stable_asset: AccountId,
+    deposit_amount: Balance,
+    mint_amount: Balance,
+  ) -> U128 {
     assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
     match env::promise_result(0) {
       PromiseResult::NotReady => unreachable!(),
+      PromiseResult::Successful(_) => U128(0),
       PromiseResult::Failed => {
-        env::panic("fail".as_bytes());
-      }
-      PromiseResult::Successful(result) => {
-        let stable_coin_total_supply = near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0;
-        let balance: Balance = env::account_balance() - distributed_intereset.0;
-
-        let borrow_rate = self.get_borrow_rate(
-          balance,
-          self.state.total_liabilities,
-          self.state.total_reserves,
-        );
-        let block_height = env::block_index();
+        let mut reserve = self.get_reserve_map(&stable_asset);
+        reserve.state.atoken_total_supply -= mint_amount;
+        reserve.state.prev_stable_coin_total_supply -= mint_amount;
+        reserve.state.balance -= deposit_amount;
+        self.add_reserve_map(&stable_asset, &reserve);
 
-        self.compute_interest_raw(
-          block_height,
-          balance,
-          stable_coin_total_supply,
-          borrow_rate,
-          target_deposit_rate,
-        );
-
-        self.state.prev_exchange_rate = self
-          .compute_exchange_rate_raw(stable_coin_total_supply, balance + distributed_intereset.0);
-
-        self.compute_reward(block_height);
-
-        let total_reserves = self.state.total_reserves.as_u128();
-
-        self.state.anc_emission_rate = self.get_emission_rate(
-          deposit_rate,
-          target_deposit_rate,
-          threshold_deposit_rate,
-          self.state.anc_emission_rate,
-        );
-
-        if total_reserves != 0 && balance > total_reserves {
-          self.state.total_reserves = self.state.total_reserves - total_reserves;
-          fungible_token::ft_transfer(
-            self.config.collector_contract.clone(),
-            total_reserves.into(),
-            None,
-            &self.config.stable_coin_contract,
-            NO_DEPOSIT,
-            SINGLE_CALL_GAS,
-          );
-        }
+        U128(deposit_amount)
       }
     }
   }
 
+  /// Sends the redeemed stable asset once the aToken burned, or restores the
+  /// reserve when it refused to
   #[private]
-  pub fn callback_get_epoch_state(
+  pub fn callback_redeem_stable(
     &mut self,
-    block_height: Option<BlockHeight>,
-    balance: Balance,
-    distributed_intereset: U128,
-  ) -> (D128, U128) {
-    assert_eq!(env::promise_results_count(), 2, "This is a callback method");
+    stable_asset: AccountId,
+    redeemer: AccountId,
+    burn_amount: Balance,
+    redeem_amount: Balance,
+  ) {
+    assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
     match env::promise_result(0) {
       PromiseResult::NotReady => unreachable!(),
-      PromiseResult::Failed => {
-        env::panic("Failed Promise".as_bytes());
+      PromiseResult::Successful(_) => {
+        fungible_token::ft_transfer(
+          redeemer,
+          U128::from(redeem_amount),
+          None,
+          &stable_asset,
+          1,
+          SINGLE_CALL_GAS,
+        );
       }
-      PromiseResult::Successful(result) => {
-        let target_deposit_rate = near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
-        match env::promise_result(1) {
-          PromiseResult::NotReady => unreachable!(),
-          PromiseResult::Failed => {
-            env::panic("Failed Promise".as_bytes());
-          }
-          PromiseResult::Successful(result) => {
-            let stable_coin_total_supply =
-              near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0;
-
-            if let Some(block_height) = block_height {
-              if block_height < self.state.last_interest_updated {
-                env::panic("block_height must bigger than last_interest_updated".as_bytes());
-              }
-              let borrow_rate = self.get_borrow_rate(
-                balance,
-                self.state.total_liabilities,
-                self.state.total_reserves,
-              );
-
-              self.compute_interest_raw(
-                block_height,
-                balance,
-                stable_coin_total_supply,
-                borrow_rate,
-                target_deposit_rate,
-              );
-            }
-            let exchange_rate = self.compute_exchange_rate_raw(
-              stable_coin_total_supply,
-              balance + distributed_intereset.0,
-            );
-
-            (exchange_rate, U128::from(stable_coin_total_supply))
-          }
-        }
+      PromiseResult::Failed => {
+        let mut reserve = self.get_reserve_map(&stable_asset);
+        reserve.state.atoken_total_supply += burn_amount;
+        reserve.state.prev_stable_coin_total_supply += burn_amount;
+        reserve.state.balance += redeem_amount;
+        self.add_reserve_map(&stable_asset, &reserve);
       }
     }
   }
@@ -6,36 +6,74 @@ impl Contract {
     self.config.clone()
   }
 
-  pub fn get_state(&self) -> State {
-    self.state
+  pub fn get_reserves(&self) -> Vec<AccountId> {
+    self.collection.reserve_map.keys().collect()
   }
 
-  pub fn get_balance(&self) -> Balance {
-    env::account_balance()
+  pub fn get_state(&self, stable_asset: AccountId) -> State {
+    self.get_reserve_map(&stable_asset).state
+  }
+
+  pub fn get_interest_model_config(&self, stable_asset: AccountId) -> InterestModelConfig {
+    self.get_reserve_map(&stable_asset).interest_model_config
+  }
+
+  pub fn get_distribution_model_config(&self, stable_asset: AccountId) -> DistributionModelConfig {
+    self.get_reserve_map(&stable_asset).distribution_model_config
+  }
+
+  pub fn get_atoken_contract(&self, stable_asset: AccountId) -> AccountId {
+    self.get_reserve_map(&stable_asset).atoken_contract
+  }
+
+  pub fn get_balance(&self, stable_asset: AccountId) -> Balance {
+    self.get_reserve_map(&stable_asset).state.balance
   }
 
   pub fn get_borrower_info(
     &mut self,
+    stable_asset: AccountId,
     borrower: AccountId,
     block_height: Option<BlockHeight>,
   ) -> BorrowerInfo {
-    let mut borrwer_info: BorrowerInfo = self.get_borrower_info_map(&borrower);
-
     let block_height = if let Some(block_height) = block_height {
       block_height
     } else {
       env::block_index()
     };
 
-    self.compute_interest(block_height, None);
-    self.compute_borrower_interest(&mut borrwer_info);
+    self.compute_interest(&stable_asset, block_height);
+
+    let mut reserve = self.get_reserve_map(&stable_asset);
+    let mut borrwer_info: BorrowerInfo = reserve.get_borrower_info_map(&borrower);
 
-    self.compute_reward(block_height);
-    self.compute_borrower_reward(&mut borrwer_info);
+    reserve.compute_borrower_interest(&mut borrwer_info);
+
+    reserve.compute_reward(block_height);
+    reserve.compute_borrower_reward(&mut borrwer_info);
 
     borrwer_info
   }
 
+  /// loans of the borrower across every reserve
+  pub fn get_borrower_loans(&self, borrower: AccountId) -> Vec<(AccountId, Balance)> {
+    self.internal_get_borrower_loans(&borrower)
+  }
+
+  /// loans of several borrowers at once, in the order they were asked for
+  pub fn get_borrowers_loans(
+    &self,
+    borrowers: Vec<AccountId>,
+  ) -> Vec<(AccountId, Vec<(AccountId, Balance)>)> {
+    borrowers
+      .into_iter()
+      .map(|borrower| {
+        let loans = self.internal_get_borrower_loans(&borrower);
+        (borrower, loans)
+      })
+      .collect()
+  }
+
   // pub fn get_borrower_infos(
   //   &mut self,
   //   start_after: Option<AccountId>,
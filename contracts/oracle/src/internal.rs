@@ -2,6 +2,15 @@ use crate::*;
 
 // Private methods
 impl Contract {
+    /// a creator keeps one pending request per asset it prices, named by the last tag
+    /// of its payload, so the request of one asset never overwrites another's
+    pub(crate) fn request_id(creator: &AccountId, tags: &[String]) -> String {
+        match tags.last() {
+            Some(asset) => format!("{}/{}", creator, asset),
+            None => creator.clone(),
+        }
+    }
+
     pub(crate) fn assert_caller(&self, expected_caller: &AccountId) {
         assert_eq!(
             &env::predecessor_account_id(),
@@ -6,13 +6,14 @@ use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, UnorderedSet};
 use near_sdk::json_types::{ValidAccountId, U64};
 use near_sdk::serde_json::json;
-use near_sdk::{env, log, near_bindgen, AccountId, Balance, Promise};
+use near_sdk::{env, log, near_bindgen, AccountId, Balance, Gas, Promise};
 
 mod fungible_token_handler;
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests;
 mod internal;
+mod upgrade;
 mod views;
 
 
@@ -24,7 +25,7 @@ pub struct Contract {
     pub oracle: AccountId,
     pub payment_token: AccountId,
     pub nonce: Nonce,
-    pub data_requests: LookupMap<AccountId, DataRequestDetails>,
+    pub data_requests: LookupMap<String, DataRequestDetails>,
     pub whitelist: UnorderedSet<AccountId>, // accounts allowed to call create_data_request(). if len() == 0, no whitelist (any account can make data request)
 }
 
@@ -42,6 +43,8 @@ impl Contract {
         payment_token: AccountId,
         whitelist: Option<Vec<ValidAccountId>>,
     ) -> Self {
+        Self::internal_write_state_version();
+
         let mut requester_instance = Self {
             oracle,
             payment_token,
@@ -68,7 +71,7 @@ impl Contract {
         payload: NewDataRequestArgs,
     ) -> Promise {
         self.assert_caller(&self.payment_token);
-        let request_id = creator.clone();
+        let request_id = Self::request_id(&creator, &payload.tags);
 
         // insert request_id into tags
         let mut payload = payload;
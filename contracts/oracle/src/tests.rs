@@ -1,6 +1,6 @@
 use super::*;
 use crate::fungible_token_handler::FungibleTokenReceiver;
-use flux_sdk::DataRequestDataType;
+use flux_sdk::{AnswerType, DataRequestDataType};
 use near_sdk::json_types::U128;
 use near_sdk::serde_json;
 use near_sdk::MockedBlockchain;
@@ -148,7 +148,7 @@ fn ri_empty_tags_nonce_works() {
         .to_string(),
     );
 
-    assert!(contract.data_requests.get(&alice()).is_some());
+    assert!(contract.get_data_request(format!("{}/a", alice())).is_some());
 }
 
 #[test]
@@ -175,7 +175,7 @@ fn ri_some_tags_nonce_works() {
         .to_string(),
     );
 
-    assert!(contract.data_requests.get(&alice()).is_some());
+    assert!(contract.get_data_request(format!("{}/on", alice())).is_some());
 }
 
 #[test]
@@ -216,5 +216,97 @@ fn ri_nonce_iterates_properly() {
         .to_string(),
     );
 
-    assert!(contract.data_requests.get(&bob()).is_some());
-}
\ No newline at end of file
+    assert!(contract.get_data_request(format!("{}/a", bob())).is_some());
+}
+
+fn price_request(asset: &str) -> String {
+    serde_json::json!({
+        "sources": Some(Vec::<String>::new()),
+        "challenge_period": U64(1500),
+        "settlement_time": U64(1),
+        "tags": vec!["pricing".to_string(), asset.to_string()],
+        "data_type": DataRequestDataType::String,
+        "creator": alice(),
+    })
+    .to_string()
+}
+
+#[test]
+fn ri_requests_are_kept_per_asset() {
+    let mut context = get_context(vec![], false);
+    context.prepaid_gas = 300_000_000_000_000;
+    testing_env!(context.clone());
+    let mut contract = Contract::new(oracle(), token(), None);
+
+    contract.ft_on_transfer(alice(), U128(100), price_request("wbtc.near"));
+    context.storage_usage = env::storage_usage();
+    testing_env!(context.clone());
+    contract.ft_on_transfer(alice(), U128(100), price_request("weth.near"));
+
+    let wbtc_request = contract
+        .get_data_request(format!("{}/wbtc.near", alice()))
+        .unwrap();
+    assert_eq!(
+        Some(&format!("{}/wbtc.near", alice())),
+        wbtc_request.tags.last()
+    );
+
+    context.storage_usage = env::storage_usage();
+    context.predecessor_account_id = oracle();
+    context.attached_deposit = 1;
+    testing_env!(context);
+    contract.set_outcome(
+        alice(),
+        Outcome::Answer(AnswerType::String("1".to_string())),
+        wbtc_request.tags,
+    );
+
+    assert!(matches!(
+        contract
+            .get_data_request(format!("{}/wbtc.near", alice()))
+            .unwrap()
+            .status,
+        RequestStatus::Finalized(_)
+    ));
+    assert!(matches!(
+        contract
+            .get_data_request(format!("{}/weth.near", alice()))
+            .unwrap()
+            .status,
+        RequestStatus::Pending
+    ));
+}
+#[test]
+fn ri_migrate_from_v0() {
+    let mut context = get_context(vec![], false);
+    testing_env!(context.clone());
+    let mut whitelist = UnorderedSet::new(b"w".to_vec());
+    whitelist.insert(&bob());
+    let old = crate::upgrade::ContractV0 {
+        oracle: oracle(),
+        payment_token: token(),
+        nonce: Nonce::new(),
+        data_requests: LookupMap::new(b"drq".to_vec()),
+        whitelist,
+    };
+    env::state_write(&old);
+
+    context.predecessor_account_id = alice();
+    testing_env!(context);
+    let contract = Contract::migrate();
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(oracle(), contract.oracle);
+    assert!(contract.whitelist.contains(&bob()));
+}
+
+#[test]
+#[should_panic(expected = "This method can only be called by alice.near")]
+fn ri_migrate_not_self() {
+    let context = get_context(vec![], false);
+    testing_env!(context);
+    let contract = Contract::new(oracle(), token(), None);
+    env::state_write(&contract);
+
+    Contract::migrate();
+}
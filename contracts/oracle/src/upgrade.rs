@@ -0,0 +1,95 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+/// Layouts the contract state has been persisted with. The version is kept
+/// under `STATE_VERSION_KEY`; deployments made before it existed are `V0`.
+pub enum VersionedContract {
+    V0(ContractV0),
+    V1(Contract),
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+    pub oracle: AccountId,
+    pub payment_token: AccountId,
+    pub nonce: Nonce,
+    pub data_requests: LookupMap<AccountId, DataRequestDetails>,
+    pub whitelist: UnorderedSet<AccountId>,
+}
+
+impl From<ContractV0> for Contract {
+    fn from(old: ContractV0) -> Self {
+        Self {
+            oracle: old.oracle,
+            payment_token: old.payment_token,
+            nonce: old.nonce,
+            data_requests: old.data_requests,
+            whitelist: old.whitelist,
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_read_state_version() -> u8 {
+        env::storage_read(STATE_VERSION_KEY)
+            .map(|value| value[0])
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+        match Self::internal_read_state_version() {
+            0 => VersionedContract::V0(env::state_read().expect("Failed to read the V0 state")),
+            CURRENT_STATE_VERSION => {
+                VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+            }
+            _ => env::panic(b"Unknown state version"),
+        }
+    }
+
+    pub(crate) fn internal_write_state_version() {
+        env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+    }
+}
+
+// The requester has no owner, so both entry points are restricted to the
+// contract account itself (a full access key on it).
+#[near_bindgen]
+impl Contract {
+    /// Converts the stored state into the current layout. Called by `upgrade`
+    /// right after the new code is deployed.
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let contract: Contract = match Self::internal_read_versioned_state() {
+            VersionedContract::V0(old) => old.into(),
+            VersionedContract::V1(contract) => contract,
+        };
+
+        contract.assert_caller(&env::current_account_id());
+
+        Self::internal_write_state_version();
+
+        contract
+    }
+
+    /// Deploys the wasm blob attached as the call input and migrates the state.
+    pub fn upgrade(&self) -> Promise {
+        self.assert_caller(&env::current_account_id());
+
+        let code = env::input().expect("Error: No code attached");
+        let migrate_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+            .expect("Not enough gas attached to migrate after the upgrade");
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), b"{}".to_vec(), 0, migrate_gas)
+    }
+
+    pub fn get_state_version(&self) -> u8 {
+        Self::internal_read_state_version()
+    }
+}
@@ -2,7 +2,8 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-    pub fn get_data_request(&self, request_id: ValidAccountId) -> Option<DataRequestDetails> {
-        self.data_requests.get(&request_id.into())
+    /// the request of a creator for an asset is stored under `creator/asset`
+    pub fn get_data_request(&self, request_id: String) -> Option<DataRequestDetails> {
+        self.data_requests.get(&request_id)
     }
 }
\ No newline at end of file
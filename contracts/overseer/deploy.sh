@@ -5,7 +5,6 @@ near deploy \
     --initFunction new \
     --initArgs '{
         "owner_id": "blockwave.testnet",
-        "stable_coin_contract": "stable.coin.testnet",
         "oracle_contrract": "oracle.synchro.testnet",
         "market_contract": "market.synchro.testnet",
         "liquidation_contract": "liquidation.synchro.testnet",
@@ -28,5 +27,13 @@ near deploy \
             "decimal": 100000000
         },
         "oracle_payment_token": "",
-        "requester_contract": ""
+        "requester_contract": "",
+        "price_timeframe": 3600
     }'
+
+near call overseer.synchro.testnet register_reserve '{
+        "stable_asset": "stable.coin.testnet",
+        "decimals": 6
+    }' \
+    --accountId blockwave.testnet \
+    --depositYocto 1
@@ -7,7 +7,6 @@ near dev-deploy \
     --initFunction new \
     --initArgs '{
         "owner_id": "blockwave.testnet",
-        "stable_coin_contract": "stable.coin.testnet",
         "oracle_contrract": "oracle.synchro.testnet",
         "market_contract": "market.synchro.testnet",
         "liquidation_contract": "liquidation.synchro.testnet",
@@ -30,5 +29,13 @@ near dev-deploy \
             "decimal": 100000000
         },
         "oracle_payment_token": "",
-        "requester_contract": ""
+        "requester_contract": "",
+        "price_timeframe": 3600
     }'
+
+near call $(cat neardev/dev-account) register_reserve '{
+        "stable_asset": "stable.coin.testnet",
+        "decimals": 6
+    }' \
+    --accountId blockwave.testnet \
+    --depositYocto 1
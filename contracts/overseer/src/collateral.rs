@@ -2,46 +2,124 @@ use crate::*;
 
 #[near_bindgen]
 impl Contract {
-    pub fn lock_collateral(&mut self, collaterals: Tokens) {
+    /// Locks the collaterals in their custodies. The collateral map is updated
+    /// right away and rolled back if any custody rejects its lock
+    pub fn lock_collateral(&mut self, collaterals: Tokens) -> Promise {
+        self.internal_lock_collateral(env::predecessor_account_id(), collaterals, None)
+    }
+
+    /// Locks a deposit the custody of the collateral just received from the borrower,
+    /// then borrows `borrow` from the market once the lock succeeded
+    pub fn lock_collateral_from_custody(
+        &mut self,
+        borrower: AccountId,
+        collateral_token: AccountId,
+        amount: U128,
+        borrow: Option<(AccountId, U128)>,
+    ) -> Promise {
+        let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
+        assert_eq!(
+            env::predecessor_account_id(),
+            white_list_elem.custody_contract,
+            "Can only be called by the custody of the collateral"
+        );
+
+        self.internal_lock_collateral(borrower, vec![(collateral_token, amount.0)], borrow)
+    }
+
+    /// Locks again the part of a liquidation the liquidation contract refused, the
+    /// custody of the collateral kept it locked for the borrower
+    pub fn restore_collateral_from_custody(
+        &mut self,
+        borrower: AccountId,
+        collateral_token: AccountId,
+        amount: U128,
+    ) {
+        let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
+        assert_eq!(
+            env::predecessor_account_id(),
+            white_list_elem.custody_contract,
+            "Can only be called by the custody of the collateral"
+        );
+
+        let mut collaterals: Tokens = self.get_collateral_map(&borrower);
+        collaterals.add(vec![(collateral_token, amount.0)]);
+        self.add_collateral_map(&borrower, &collaterals);
+    }
+
+    /// Attributes the loans of a borrower to its isolated collateral. The market
+    /// reports them after each borrow and repayment, so accrued interest counts
+    /// from the next report on
+    pub fn update_isolated_debt(&mut self, borrower: AccountId, loans: Tokens) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.config.market_contract,
+            "Can only be called by the market"
+        );
+
+        let collaterals: Tokens = self.get_collateral_map(&borrower);
+        let isolated_debt = self
+            .get_isolated_collateral(&collaterals)
+            .map(|(collateral_token, _)| (collateral_token, self.compute_loan_value(&loans)));
+        self.add_borrower_isolated_debt_map(&borrower, isolated_debt);
+    }
+
+    /// Opts the borrower into the category its collaterals all belong to, or out
+    /// of its category with `None`. Its loans must stay under the new borrow limit
+    pub fn update_borrower_category(&mut self, category: Option<String>) -> Promise {
         let borrower = env::predecessor_account_id();
-        let mut cur_collaterals: Tokens = self.get_collateral_map(&borrower);
+        self.assert_registered(&borrower);
+        let collaterals: Tokens = self.get_collateral_map(&borrower);
 
-        cur_collaterals.add(collaterals);
-        self.add_collateral_map(&borrower, &cur_collaterals);
+        let category = category.map(|category| {
+            let asset_category = self.get_category_map(&category);
+            for collateral in collaterals.iter() {
+                let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+                if white_list_elem.category.as_ref() != Some(&category) {
+                    env::panic(format!("{} is outside the category", collateral.0).as_bytes());
+                }
+            }
+            (category, asset_category)
+        });
+        let (borrow_limit, _) =
+            self.compute_borrow_limit(&collaterals, &category, Some(env::block_timestamp()));
 
-        for collateral in cur_collaterals {
-            let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
-            ext_custody_bnear::lock_collateral(
-                borrower.clone(),
-                collateral.1,
-                &white_list_elem.custody_contract,
-                NO_DEPOSIT,
-                SINGLE_CALL_GAS,
-            );
-        }
+        ext_market::get_borrower_loans(
+            borrower.clone(),
+            &self.config.market_contract,
+            NO_DEPOSIT,
+            SINGLE_CALL_GAS,
+        )
+        .then(ext_self::callback_update_borrower_category(
+            borrower,
+            category.map(|(category, _)| category),
+            borrow_limit,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            SINGLE_CALL_GAS,
+        ))
     }
 
     pub fn unlock_collateral(&mut self, collaterals: Tokens) {
         let borrower = env::predecessor_account_id();
+        assert!(!collaterals.is_empty(), "No collaterals to unlock");
         let mut cur_collaterals: Tokens = self.get_collateral_map(&borrower);
 
         cur_collaterals.sub(collaterals.clone());
 
-        let block_height = env::block_index();
-
+        let category = self.get_borrower_category(&borrower);
         let (borrow_limit, _) =
-            self.compute_borrow_limit(&collaterals, Some(env::block_timestamp()));
+            self.compute_borrow_limit(&cur_collaterals, &category, Some(env::block_timestamp()));
 
-        ext_market::get_borrower_info(
+        ext_market::get_borrower_loans(
             borrower.clone(),
-            Some(block_height),
             &self.config.market_contract,
             NO_DEPOSIT,
             SINGLE_CALL_GAS,
         )
         .then(ext_self::callback_unlock_collateral(
             borrower,
-            cur_collaterals,
+            collaterals,
             borrow_limit,
             &env::current_account_id(),
             NO_DEPOSIT,
@@ -49,57 +127,435 @@ impl Contract {
         ));
     }
 
-    // Don't use
+    /// Sells collaterals of a borrower whose loans exceed the liquidation limit.
+    /// The liquidation contract decides the amounts and repays the market
     pub fn liquidate_collateral(&self, borrower: AccountId) {
         let cur_collaterals: Tokens = self.get_collateral_map(&borrower);
 
-        let (borrow_limit, collateral_prices) =
-            self.compute_borrow_limit(&cur_collaterals, Some(env::block_timestamp()));
+        let collateral_prices =
+            self.read_collateral_prices(&cur_collaterals, Some(env::block_timestamp()));
+        let liquidation_limit = self.compute_liquidation_limit_with_prices(
+            &cur_collaterals,
+            &collateral_prices,
+            &self.get_borrower_category(&borrower),
+        );
 
-        let block_height = env::block_index();
+        let callback_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_LOANS_QUERY + GAS_FOR_LIQUIDATION_STEP)
+            .filter(|gas| {
+                *gas >= GAS_FOR_LIQUIDATION_STEP + Self::liquidation_gas(&cur_collaterals)
+            })
+            .expect("Not enough gas to liquidate the collaterals");
 
-        ext_market::get_borrower_info(
+        ext_market::get_borrower_loans(
             borrower.clone(),
-            Some(block_height),
             &self.config.market_contract,
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            GAS_FOR_LOANS_QUERY,
+        )
+        .then(ext_self::callback_liquidate_collateral(
+            env::predecessor_account_id(),
+            borrower,
+            cur_collaterals,
+            liquidation_limit,
+            collateral_prices,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            callback_gas,
+        ));
+    }
+
+    /// Starts the liquidation of every unhealthy borrower of the batch. The prices
+    /// are read once for the whole batch, and borrowers the remaining gas cannot
+    /// cover are reported as skipped, to be sent again in a later batch
+    pub fn liquidate_collaterals(&self, borrowers: Vec<AccountId>) -> Promise {
+        assert!(
+            !borrowers.is_empty() && borrowers.len() <= MAX_LIQUIDATION_BATCH,
+            "Batch size must be between 1 and {}",
+            MAX_LIQUIDATION_BATCH
+        );
+
+        let borrowers_collaterals: Vec<(AccountId, Tokens)> = borrowers
+            .iter()
+            .map(|borrower| (borrower.clone(), self.get_collateral_map(borrower)))
+            .collect();
+
+        let all_collaterals: Tokens = borrowers_collaterals
+            .iter()
+            .flat_map(|(_, collaterals)| collaterals.clone())
+            .collect();
+        let collateral_prices =
+            self.read_collateral_prices(&all_collaterals, Some(env::block_timestamp()));
+
+        let positions: Vec<(AccountId, Tokens, u128)> = borrowers_collaterals
+            .into_iter()
+            .map(|(borrower, collaterals)| {
+                let liquidation_limit = self.compute_liquidation_limit_with_prices(
+                    &collaterals,
+                    &collateral_prices,
+                    &self.get_borrower_category(&borrower),
+                );
+                (borrower, collaterals, liquidation_limit)
+            })
+            .collect();
+
+        let callback_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_LOANS_QUERY + GAS_FOR_LIQUIDATION_BATCH)
+            .expect("Not enough gas to liquidate a batch");
+
+        ext_market::get_borrowers_loans(
+            borrowers,
+            &self.config.market_contract,
+            NO_DEPOSIT,
+            GAS_FOR_LOANS_QUERY,
         )
-        .and(ext_market::get_balance(
+        .then(ext_self::callback_liquidate_collaterals(
+            env::predecessor_account_id(),
+            positions,
+            collateral_prices,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            callback_gas,
+        ))
+    }
+
+    /// Positions of the borrowers of a `get_all_collaterals` page whose loan to
+    /// collateral value is above `min_ltv`. Has to be a function call, a view
+    /// cannot reach the market for the loans
+    pub fn get_liquidation_candidates(
+        &self,
+        start_after: Option<AccountId>,
+        limit: Option<u32>,
+        min_ltv: D128,
+    ) -> Promise {
+        let borrowers: Vec<AccountId> = self
+            .get_all_collaterals(start_after, limit)
+            .into_iter()
+            .map(|(borrower, _)| borrower)
+            .collect();
+
+        ext_market::get_borrowers_loans(
+            borrowers,
             &self.config.market_contract,
             NO_DEPOSIT,
             SINGLE_CALL_GAS,
+        )
+        .then(ext_self::callback_get_liquidation_candidates(
+            min_ltv,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            SINGLE_CALL_GAS,
         ))
-        .then(ext_self::callback_liquidate_collateral(
+    }
+
+    /// Reads the loans of the borrower from the market and values them against
+    /// the locked collaterals. Has to be a function call, a view cannot reach the market
+    pub fn get_borrower_position(&self, borrower: AccountId) -> Promise {
+        ext_market::get_borrower_loans(
+            borrower.clone(),
+            &self.config.market_contract,
+            NO_DEPOSIT,
+            SINGLE_CALL_GAS,
+        )
+        .then(ext_self::callback_get_borrower_position(
             borrower,
-            cur_collaterals,
-            borrow_limit,
             &env::current_account_id(),
             NO_DEPOSIT,
             SINGLE_CALL_GAS,
-        ));
+        ))
     }
 
     pub(crate) fn compute_borrow_limit(
         &self,
         collaterals: &Tokens,
+        category: &Option<(String, AssetCategory)>,
         block_time: Option<Timestamp>,
-    ) -> (u128, Vec<D128>) {
-        let mut borrow_limit: u128 = 0;
-        let mut collateral_prices: Vec<D128> = vec![];
+    ) -> (u128, Vec<(AccountId, D128)>) {
+        let collateral_prices = self.read_collateral_prices(collaterals, block_time);
+        let borrow_limit =
+            self.compute_borrow_limit_with_prices(collaterals, &collateral_prices, category);
+
+        (borrow_limit, collateral_prices)
+    }
+
+    /// price of every collateral token, read once even if it appears several times
+    pub(crate) fn read_collateral_prices(
+        &self,
+        collaterals: &Tokens,
+        block_time: Option<Timestamp>,
+    ) -> Vec<(AccountId, D128)> {
+        let mut collateral_prices: Vec<(AccountId, D128)> = vec![];
 
         for collateral in collaterals.iter() {
             let collateral_token = collateral.0.clone();
-            let collateral_amount = collateral.1;
+            if collateral_prices
+                .iter()
+                .any(|collateral_price| collateral_price.0 == collateral_token)
+            {
+                continue;
+            }
+
+            let price_response = self.get_price_map(&collateral_token);
+            if let Some(block_time) = block_time {
+                self.assert_price_fresh(&collateral_token, &price_response, block_time);
+            }
+            collateral_prices.push((collateral_token, price_response.price));
+        }
 
-            let price = self.state.last_price_response.price;
+        collateral_prices
+    }
 
-            let elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
-            let collateral_value = collateral_amount * price;
-            borrow_limit += (collateral_value * elem.max_ltv).as_u128();
-            collateral_prices.push(price);
+    pub(crate) fn compute_borrow_limit_with_prices(
+        &self,
+        collaterals: &Tokens,
+        collateral_prices: &[(AccountId, D128)],
+        category: &Option<(String, AssetCategory)>,
+    ) -> u128 {
+        self.compute_limit_with_prices(collaterals, collateral_prices, category, |elem| {
+            elem.current_max_ltv()
+        })
+    }
+
+    /// value of the loans above which the collaterals can be liquidated
+    pub(crate) fn compute_liquidation_limit_with_prices(
+        &self,
+        collaterals: &Tokens,
+        collateral_prices: &[(AccountId, D128)],
+        category: &Option<(String, AssetCategory)>,
+    ) -> u128 {
+        self.compute_limit_with_prices(collaterals, collateral_prices, category, |elem| {
+            elem.liquidation_ltv
+        })
+    }
+
+    /// value of the loans of every reserve, in the price unit of the collaterals
+    pub(crate) fn compute_loan_value(&self, loans: &Tokens) -> u128 {
+        self.compute_loan_values(loans)
+            .iter()
+            .map(|(_, loan_value)| loan_value.0)
+            .sum()
+    }
+
+    /// value of the loan in each reserve, in the price unit of the collaterals
+    pub(crate) fn compute_loan_values(&self, loans: &Tokens) -> Vec<(AccountId, U128)> {
+        loans
+            .iter()
+            .map(|loan| {
+                let reserve_elem: ReserveElem = self.get_reserve_elem_map(&loan.0);
+
+                let price = self.get_price_map(&loan.0).price;
+                let loan_value = value_of(loan.1, reserve_elem.decimals, price, VALUE_DECIMALS);
+                (loan.0.clone(), U128::from(loan_value.as_u128()))
+            })
+            .collect()
+    }
+
+    pub(crate) fn compute_borrower_position(
+        &self,
+        borrower: AccountId,
+        loans: Tokens,
+    ) -> BorrowerPosition {
+        let collaterals: Tokens = self.get_collateral_map(&borrower);
+        let category = self.get_borrower_category(&borrower);
+        let (borrow_limit, collateral_prices) =
+            self.compute_borrow_limit(&collaterals, &category, None);
+        let liquidation_limit =
+            self.compute_liquidation_limit_with_prices(&collaterals, &collateral_prices, &category);
+        let loan_value = self.compute_loan_value(&loans);
+
+        let mut collateral_value: u128 = 0;
+        let mut collateral_positions: Vec<CollateralPosition> = vec![];
+        for (collateral, (_, price)) in collaterals.iter().zip(collateral_prices) {
+            let elem: WhitelistElem = self
+                .get_white_list_elem_map(&collateral.0)
+                .in_category(&category);
+            let value = value_of(collateral.1, elem.decimals, price, VALUE_DECIMALS);
+            let limit = (value * elem.liquidation_ltv).as_u128();
+            let value = value.as_u128();
+
+            // the price at which this collateral alone brings the liquidation limit down to the loans
+            let other_limit = liquidation_limit - limit;
+            let liquidation_price = if loan_value > other_limit && limit > 0 {
+                Some(
+                    (loan_value - other_limit)
+                        / (value_of(collateral.1, elem.decimals, D128::one(), VALUE_DECIMALS)
+                            * elem.liquidation_ltv),
+                )
+            } else {
+                None
+            };
+
+            collateral_value += value;
+            collateral_positions.push(CollateralPosition {
+                collateral_token: collateral.0.clone(),
+                amount: U128::from(collateral.1),
+                price,
+                value: U128::from(value),
+                max_ltv: elem.current_max_ltv(),
+                liquidation_ltv: elem.liquidation_ltv,
+                liquidation_price,
+            });
         }
 
-        (borrow_limit, collateral_prices)
+        BorrowerPosition {
+            borrower,
+            category: category.map(|(category, _)| category),
+            collaterals: collateral_positions,
+            collateral_value: U128::from(collateral_value),
+            borrow_limit: U128::from(borrow_limit),
+            liquidation_limit: U128::from(liquidation_limit),
+            loans,
+            loan_value: U128::from(loan_value),
+            ltv: if collateral_value > 0 {
+                D128::ratio(loan_value, collateral_value)
+            } else {
+                D128::zero()
+            },
+            health_factor: if loan_value > 0 {
+                Some(D128::ratio(liquidation_limit, loan_value))
+            } else {
+                None
+            },
+        }
+    }
+}
+
+impl Contract {
+    /// Locks the collaterals in their custodies. The collateral map is updated
+    /// right away and rolled back if any custody rejects its lock
+    pub(crate) fn internal_lock_collateral(
+        &mut self,
+        borrower: AccountId,
+        collaterals: Tokens,
+        borrow: Option<(AccountId, U128)>,
+    ) -> Promise {
+        assert!(!collaterals.is_empty(), "No collaterals to lock");
+        self.assert_registered(&borrower);
+        for collateral in collaterals.iter() {
+            let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+            if white_list_elem.status != WhitelistStatus::Active {
+                env::panic(format!("{} cannot be locked", collateral.0).as_bytes());
+            }
+        }
+
+        if let Some((category, _)) = self.get_borrower_category(&borrower) {
+            for collateral in collaterals.iter() {
+                let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+                if white_list_elem.category.as_ref() != Some(&category) {
+                    env::panic(
+                        format!("{} is outside the category of the borrower", collateral.0)
+                            .as_bytes(),
+                    );
+                }
+            }
+        }
+
+        let mut cur_collaterals: Tokens = self.get_collateral_map(&borrower);
+
+        cur_collaterals.add(collaterals.clone());
+        if cur_collaterals.len() > 1
+            && cur_collaterals.iter().any(|collateral| {
+                let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+                white_list_elem.debt_ceiling.is_some()
+            })
+        {
+            env::panic(b"An isolated collateral cannot be combined with other collaterals");
+        }
+        self.add_collateral_map(&borrower, &cur_collaterals);
+        self.assert_storage_covered(&borrower);
+
+        // the callback keeps a step of its own and borrows with the rest
+        let callback_gas = GAS_FOR_LOCK_STEP
+            + if borrow.is_some() {
+                GAS_FOR_MARKET_BORROW
+            } else {
+                0
+            };
+        assert!(
+            env::prepaid_gas()
+                >= GAS_FOR_LOCK_STEP
+                    + collaterals.len() as Gas * GAS_FOR_CUSTODY_LOCK
+                    + callback_gas,
+            "Not enough gas to lock the collaterals"
+        );
+
+        self.internal_custody_lock(&borrower, &collaterals).then(
+            ext_self::callback_lock_collateral(
+                borrower,
+                collaterals,
+                borrow,
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                callback_gas,
+            ),
+        )
+    }
+
+    /// The isolated collateral a borrower holds alone, with its debt ceiling
+    pub(crate) fn get_isolated_collateral(
+        &self,
+        collaterals: &Tokens,
+    ) -> Option<(AccountId, u128)> {
+        match collaterals.as_slice() {
+            [collateral] => {
+                let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+                white_list_elem
+                    .debt_ceiling
+                    .map(|debt_ceiling| (collateral.0.clone(), debt_ceiling.0))
+            }
+            _ => None,
+        }
+    }
+
+    /// Keeps the borrow limit of an isolated borrower within what the debt ceiling
+    /// of its collateral has left, on top of the loans already attributed to it
+    pub(crate) fn cap_isolated_borrow_limit(
+        &self,
+        borrower: &AccountId,
+        collaterals: &Tokens,
+        borrow_limit: u128,
+    ) -> u128 {
+        let (collateral_token, debt_ceiling) = match self.get_isolated_collateral(collaterals) {
+            Some(isolated_collateral) => isolated_collateral,
+            None => return borrow_limit,
+        };
+
+        let attributed_debt = match self.collection.borrower_isolated_debt_map.get(borrower) {
+            Some((isolated_token, debt)) if isolated_token == collateral_token => debt,
+            _ => 0,
+        };
+        let available_debt = debt_ceiling.saturating_sub(self.get_isolated_debt(&collateral_token));
+
+        std::cmp::min(borrow_limit, attributed_debt + available_debt)
+    }
+
+    /// value of the collaterals weighted by the LTV `ltv` reads from their whitelist elem,
+    /// in the category of the borrower
+    fn compute_limit_with_prices(
+        &self,
+        collaterals: &Tokens,
+        collateral_prices: &[(AccountId, D128)],
+        category: &Option<(String, AssetCategory)>,
+        ltv: fn(&WhitelistElem) -> D128,
+    ) -> u128 {
+        let mut limit: u128 = 0;
+
+        for collateral in collaterals.iter() {
+            let price = match collateral_prices
+                .iter()
+                .find(|collateral_price| collateral_price.0 == collateral.0)
+            {
+                Some(collateral_price) => collateral_price.1,
+                None => env::panic(format!("No price for {}", collateral.0).as_bytes()),
+            };
+
+            let elem: WhitelistElem = self
+                .get_white_list_elem_map(&collateral.0)
+                .in_category(category);
+            let collateral_value = value_of(collateral.1, elem.decimals, price, VALUE_DECIMALS);
+            limit += (collateral_value * ltv(&elem)).as_u128();
+        }
+
+        limit
     }
 }
@@ -1,5 +1,7 @@
 use crate::*;
 
+use flux_sdk::consts::DR_NEW_GAS;
+
 #[near_bindgen]
 impl Contract {
     pub(crate) fn assert_owner(&self) {
@@ -10,30 +12,317 @@ impl Contract {
         );
     }
 
-    /// updates price response at every function call
-    pub(crate) fn internal_update_price_response(&mut self) -> Promise {
+    /// A borrower at the borrow limit must not be liquidable right away
+    pub(crate) fn assert_valid_ltvs(&self, max_ltv: D128, liquidation_ltv: D128) {
+        assert!(
+            max_ltv < liquidation_ltv,
+            "The liquidation LTV must be above the max LTV"
+        );
+        assert!(
+            liquidation_ltv <= D128::one(),
+            "The liquidation LTV must not exceed 1"
+        );
+    }
+
+    pub(crate) fn assert_valid_decimals(&self, decimals: u8) {
+        assert!(decimals <= 24, "Decimals must not exceed 24");
+    }
+
+    pub(crate) fn storage_bytes_of(&self, account_id: &AccountId) -> StorageUsage {
+        STORAGE_BYTES_PER_ACCOUNT
+            + self.get_collateral_map(account_id).len() as StorageUsage
+                * STORAGE_BYTES_PER_COLLATERAL
+    }
+
+    pub(crate) fn assert_price_fresh(
+        &self,
+        asset: &AccountId,
+        price_response: &PriceResponse,
+        block_time: Timestamp,
+    ) {
+        if price_response.last_updated_at + self.config.price_timeframe * SECOND_TO_NANO
+            < block_time
+        {
+            env::panic(format!("Price of {} is too old", asset).as_bytes());
+        }
+    }
+
+    /// Gas a liquidation of the collaterals forwards once the loans are read: the
+    /// liquidation amount query, its callback and a sale by every custody
+    pub(crate) fn liquidation_gas(collaterals: &Tokens) -> Gas {
+        GAS_FOR_LIQUIDATION_AMOUNT
+            + GAS_FOR_LIQUIDATION_STEP
+            + collaterals.len() as Gas * GAS_FOR_CUSTODY_LIQUIDATION
+    }
+
+    /// Asks the liquidation contract how much of each collateral to sell, then
+    /// has the custodies sell it with `gas`, at least `liquidation_gas`. The sale
+    /// brings the loans back under the max LTVs, as far as the loan in the stable
+    /// coin of the liquidation contract covers
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn internal_start_liquidation(
+        &self,
+        liquidator: AccountId,
+        borrower: AccountId,
+        collaterals: Tokens,
+        liquidation_limit: u128,
+        loans: &Tokens,
+        collateral_prices: Vec<(AccountId, D128)>,
+        gas: Gas,
+    ) {
+        assert!(
+            gas >= Self::liquidation_gas(&collaterals),
+            "Not enough gas to liquidate the collaterals"
+        );
+        let category = self.get_borrower_category(&borrower);
+        let max_ltvs: Vec<(AccountId, D128)> = collaterals
+            .iter()
+            .map(|collateral| {
+                let white_list_elem: WhitelistElem = self
+                    .get_white_list_elem_map(&collateral.0)
+                    .in_category(&category);
+                (collateral.0.clone(), white_list_elem.current_max_ltv())
+            })
+            .collect();
+
+        let loan_values = self.compute_loan_values(loans);
+        let loan_value: u128 = loan_values.iter().map(|(_, value)| value.0).sum();
+
+        ext_liquidation::get_liquidation_amount(
+            U128::from(loan_value),
+            U128::from(liquidation_limit),
+            loan_values,
+            collaterals,
+            collateral_prices,
+            max_ltvs,
+            &self.config.liquidation_contract,
+            NO_DEPOSIT,
+            GAS_FOR_LIQUIDATION_AMOUNT,
+        )
+        .then(ext_self::callback_liquidate_collateral2(
+            liquidator,
+            borrower,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            gas - GAS_FOR_LIQUIDATION_AMOUNT,
+        ));
+    }
+
+    /// Has the custodies lock the collaterals of the borrower, joined so that a
+    /// callback sees the outcome of every lock in the order of `collaterals`
+    pub(crate) fn internal_custody_lock(
+        &self,
+        borrower: &AccountId,
+        collaterals: &Tokens,
+    ) -> Promise {
+        collaterals
+            .iter()
+            .map(|collateral| {
+                let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+                ext_custody::lock_collateral(
+                    borrower.clone(),
+                    collateral.1,
+                    &white_list_elem.custody_contract,
+                    NO_DEPOSIT,
+                    GAS_FOR_CUSTODY_LOCK,
+                )
+            })
+            .reduce(|locks, lock| locks.and(lock))
+            .unwrap()
+    }
+
+    pub(crate) fn internal_custody_unlock(
+        &self,
+        borrower: &AccountId,
+        collaterals: &Tokens,
+    ) -> Promise {
+        collaterals
+            .iter()
+            .map(|collateral| {
+                let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral.0);
+                ext_custody::unlock_collateral(
+                    borrower.clone(),
+                    collateral.1,
+                    &white_list_elem.custody_contract,
+                    NO_DEPOSIT,
+                    GAS_FOR_CUSTODY_LOCK,
+                )
+            })
+            .reduce(|unlocks, unlock| unlocks.and(unlock))
+            .unwrap()
+    }
+
+    pub(crate) fn internal_log_liquidation(&self, borrower: &AccountId, status: LiquidationStatus) {
+        let event = near_sdk::serde_json::json!({
+            "standard": "overseer",
+            "version": "1.0.0",
+            "event": "liquidate_collateral",
+            "data": [{
+                "borrower": borrower,
+                "status": status,
+            }],
+        });
+        env::log(format!("EVENT_JSON:{}", event).as_bytes());
+    }
+
+    /// Moves the target deposit rate of a reserve towards the rate its deposits
+    /// yielded over the epoch, within the bounds of the controller
+    pub(crate) fn internal_adjust_target_deposit_rate(
+        &mut self,
+        stable_asset: &AccountId,
+        mut reserve_elem: ReserveElem,
+        deposit_rate: D128,
+        accrued_buffer: u128,
+        interest_buffer: u128,
+        blocks: BlockHeight,
+    ) {
+        let controller = match self.config.deposit_rate_controller.clone() {
+            Some(controller) => controller,
+            None => return,
+        };
+
+        let prev_deposits: u128 = (reserve_elem.prev_stable_coin_total_supply
+            * reserve_elem.prev_exchange_rate)
+            .as_u128();
+        if prev_deposits == 0 {
+            return;
+        }
+
+        let yield_rate = deposit_rate + D128::ratio(accrued_buffer, prev_deposits) / blocks as u128;
+        let prev_target_deposit_rate = reserve_elem.target_deposit_rate;
+
+        let target_deposit_rate = if yield_rate > prev_target_deposit_rate {
+            let step = std::cmp::min(
+                (yield_rate - prev_target_deposit_rate) * controller.smoothing,
+                controller.max_step,
+            );
+
+            // a thin buffer could not cover the deposits once the yield falls back
+            if interest_buffer < (prev_deposits * controller.min_buffer_ratio).as_u128() {
+                prev_target_deposit_rate
+            } else {
+                prev_target_deposit_rate + step
+            }
+        } else {
+            let step = std::cmp::min(
+                (prev_target_deposit_rate - yield_rate) * controller.smoothing,
+                controller.max_step,
+            );
+            prev_target_deposit_rate - step
+        };
+        let target_deposit_rate = std::cmp::max(
+            std::cmp::min(target_deposit_rate, controller.max_target_deposit_rate),
+            controller.min_target_deposit_rate,
+        );
+
+        reserve_elem.target_deposit_rate = target_deposit_rate;
+        self.add_reserve_elem_map(stable_asset, &reserve_elem);
+
+        let index = self.state.target_deposit_rate_adjustments;
+        self.collection.target_deposit_rate_history.insert(
+            &(index % TARGET_DEPOSIT_RATE_HISTORY_SIZE),
+            &TargetDepositRateAdjustment {
+                stable_asset: stable_asset.clone(),
+                block_height: env::block_index(),
+                deposit_rate,
+                yield_rate,
+                interest_buffer: U128::from(interest_buffer),
+                prev_target_deposit_rate,
+                target_deposit_rate,
+            },
+        );
+        self.state.target_deposit_rate_adjustments += 1;
+    }
+
+    /// Keeps the record in the ring buffer of the reserve, over its oldest record
+    /// once the buffer is full
+    pub(crate) fn internal_push_epoch_record(
+        &mut self,
+        stable_asset: &AccountId,
+        record: &EpochRecord,
+    ) {
+        let mut reserve_elem: ReserveElem = self.get_reserve_elem_map(stable_asset);
+
+        self.collection.epoch_history.insert(
+            &(
+                stable_asset.clone(),
+                reserve_elem.epoch_count % EPOCH_HISTORY_SIZE,
+            ),
+            record,
+        );
+        reserve_elem.epoch_count += 1;
+        self.add_reserve_elem_map(stable_asset, &reserve_elem);
+    }
+
+    pub(crate) fn internal_update_epoch_state(
+        &mut self,
+        stable_asset: AccountId,
+        intereset_buffer: U128,
+        distributed_intereset: U128,
+        anc_purchase_amount: U128,
+    ) {
+        let block_height = env::block_index();
+        let reserve_elem: ReserveElem = self.get_reserve_elem_map(&stable_asset);
+        let blocks = block_height - reserve_elem.last_executed_height;
+
+        ext_market::get_epoch_state(
+            stable_asset.clone(),
+            Some(block_height),
+            Some(distributed_intereset),
+            &self.config.market_contract,
+            NO_DEPOSIT,
+            GAS_FOR_EPOCH_STATE,
+        )
+        .then(ext_self::callback_update_epoch_state(
+            stable_asset,
+            intereset_buffer,
+            distributed_intereset,
+            anc_purchase_amount,
+            block_height,
+            blocks,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_EPOCH_UPDATE,
+        ));
+    }
+
+    /// reads the request of a collateral, kept by the requester contract under
+    /// `overseer/collateral`, and stores its price once finalized. The callback gets
+    /// the gas left to pay the next request of the collateral
+    pub(crate) fn internal_update_price_response(
+        &mut self,
+        collateral_token: AccountId,
+    ) -> Promise {
+        let callback_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_PRICE_QUERY + GAS_FOR_PRICE_UPDATE)
+            .filter(|gas| *gas >= DR_NEW_GAS + GAS_FOR_PRICE_UPDATE)
+            .expect("Not enough gas to request the next price");
+
         requester::get_data_request(
-            env::current_account_id().try_into().unwrap(),
+            format!("{}/{}", env::current_account_id(), collateral_token),
             // Near params
             &self.config.requester_contract,
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            GAS_FOR_PRICE_QUERY,
         )
         .then(ext_self::callback_get_price_response(
+            collateral_token,
             // Near params
             &env::current_account_id(),
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            callback_gas,
         ))
     }
 
-    pub(crate) fn internal_create_new_price_request(&self) {
+    /// requests the next price of a collateral from the feed of its whitelist entry
+    pub(crate) fn internal_create_new_price_request(&self, collateral_token: &AccountId) {
+        let white_list_elem: WhitelistElem = self.get_white_list_elem_map(collateral_token);
+
         fungible_token_transfer_call(
         self.config.oracle_payment_token.clone(),
         self.config.requester_contract.clone(),
         1_000_000_000_000_000_000_000_000,
-        // query NEAR price
-        format!("{{\"sources\": [{{ \"end_point\": \"https://api.coingecko.com/api/v3/simple/price?ids=tether%2Cnear&vs_currencies=usd\", \"source_path\":\"near.usd\"}}], \"tags\":[\"pricing\",\"near\"],  \"challenge_period\":\"120000000000\", \"settlement_time\":\"1\", \"data_type\":{{\"Number\":\"{}\"}}, \"creator\":\"{}\"}}", DECIMAL, env::current_account_id())
+        format!("{{\"sources\": [{{ \"end_point\": \"{}\", \"source_path\":\"{}\"}}], \"tags\":[\"pricing\",\"{}\"],  \"challenge_period\":\"120000000000\", \"settlement_time\":\"1\", \"data_type\":{{\"Number\":\"{}\"}}, \"creator\":\"{}\"}}", white_list_elem.price_feed.end_point, white_list_elem.price_feed.source_path, collateral_token, DECIMAL, env::current_account_id())
     );
     }
 }
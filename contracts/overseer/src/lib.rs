@@ -1,20 +1,24 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, UnorderedMap};
-use near_sdk::json_types::{ValidAccountId, U128};
+use near_sdk::collections::{LookupMap, TreeMap, UnorderedMap};
+use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     assert_one_yocto, env, ext_contract, near_bindgen, AccountId, Balance, BlockHeight,
-    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue, PromiseResult, Timestamp,
+    BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseResult, StorageUsage, Timestamp,
 };
 
 use uint::construct_uint;
 
-use crate::math::{D128, DECIMAL};
-use crate::state::{Collection, Config, State, WhitelistElem};
-use crate::tokens::{Token, Tokens, TokensMath};
+use crate::math::{value_of, D128, DECIMAL};
+use crate::state::{
+    AssetCategory, BorrowerPosition, CollateralPosition, Collection, Config, DepositRateController,
+    EpochAverages, EpochRecord, LiquidationStatus, PriceFeed, PriceStatus, ReserveElem, State,
+    TargetDepositRateAdjustment, WhitelistElem, WhitelistResponseElem, WhitelistStatus,
+};
+use crate::tokens::{Tokens, TokensMath};
 use crate::utils::{
-    ext_custody_bnear, ext_market, ext_self, fungible_token, fungible_token_transfer_call,
-    requester,
+    ext_custody, ext_liquidation, ext_market, ext_self, fungible_token,
+    fungible_token_transfer_call, requester,
 };
 
 mod collateral;
@@ -22,9 +26,12 @@ mod internal;
 mod math;
 mod owner;
 mod state;
+#[path = "../../common/storage.rs"]
+mod storage;
 #[cfg(test)]
 mod testing;
 mod tokens;
+mod upgrade;
 mod utils;
 mod view;
 
@@ -32,6 +39,95 @@ const NO_DEPOSIT: Balance = 0;
 
 const SINGLE_CALL_GAS: Gas = 100_000_000_000_000;
 
+// a registration and the category and isolated debt of a borrower
+const STORAGE_BYTES_PER_ACCOUNT: StorageUsage = 200;
+
+// every collateral of a borrower is paid for when it is first locked
+const STORAGE_BYTES_PER_COLLATERAL: StorageUsage = 100;
+
+const MAX_STORAGE_BYTES_PER_ACCOUNT: Option<StorageUsage> = None;
+
+const SECOND_TO_NANO: u64 = 1_000_000_000;
+
+const MAX_LIQUIDATION_BATCH: usize = 10;
+
+const TARGET_DEPOSIT_RATE_HISTORY_SIZE: u64 = 100;
+
+const EPOCH_HISTORY_SIZE: u64 = 100;
+
+// the loans of the borrowers read from the market
+const GAS_FOR_LOANS_QUERY: Gas = 20_000_000_000_000;
+
+// the collateral amounts to sell computed by the liquidation contract
+const GAS_FOR_LIQUIDATION_AMOUNT: Gas = 10_000_000_000_000;
+
+// kept by each step of a liquidation for its receipts, the rest is forwarded down the chain
+const GAS_FOR_LIQUIDATION_STEP: Gas = 25_000_000_000_000;
+
+// least gas a custody gets to sell a collateral to the liquidation contract, whose
+// proceeds repay the market, and to give back what the contract refused
+const GAS_FOR_CUSTODY_LIQUIDATION: Gas = 70_000_000_000_000;
+
+// left to the batch call itself once its callback is scheduled
+const GAS_FOR_LIQUIDATION_BATCH: Gas = 20_000_000_000_000;
+
+// a lock or unlock of a collateral by its custody
+const GAS_FOR_CUSTODY_LOCK: Gas = 10_000_000_000_000;
+
+// kept by a lock of collaterals and by its callback for their receipts
+const GAS_FOR_LOCK_STEP: Gas = 25_000_000_000_000;
+
+// least gas of the market to borrow against collaterals just locked, the overseer
+// computes the borrow limit which spares the market one of its views
+const GAS_FOR_MARKET_BORROW: Gas = 120_000_000_000_000;
+
+// loans, collaterals and limits are valued in the price unit with these decimals
+const VALUE_DECIMALS: u8 = 6;
+
+// least gas of a custody to claim its rewards, swap them and send them to the overseer
+const GAS_FOR_CUSTODY_REWARDS: Gas = 240_000_000_000_000;
+
+// kept by the call distributing the rewards of a collateral
+const GAS_FOR_REWARDS_CALL: Gas = 10_000_000_000_000;
+
+// kept by each step of an epoch for its receipts
+const GAS_FOR_EPOCH_STEP: Gas = 35_000_000_000_000;
+
+// the market accrues the interest of a reserve and reports its exchange rate
+const GAS_FOR_EPOCH_STATE: Gas = 40_000_000_000_000;
+
+// the interest buffer held by the overseer in a stable asset
+const GAS_FOR_BALANCE_QUERY: Gas = 10_000_000_000_000;
+
+// the ANC purchase sent to the collector
+const GAS_FOR_FT_TRANSFER: Gas = 10_000_000_000_000;
+
+// the interest distributed to the market, credited to its reserve once received
+const GAS_FOR_INTEREST_DISTRIBUTION: Gas = 40_000_000_000_000;
+
+// the market executes the epoch of a reserve and sends its reserves to the collector
+const GAS_FOR_MARKET_EPOCH: Gas = 30_000_000_000_000;
+
+// the epoch recorded with the emission rate set by the market
+const GAS_FOR_RECORD_EPOCH: Gas = 10_000_000_000_000;
+
+// the epoch state stored once the market reported it, then executed by the market
+const GAS_FOR_EPOCH_UPDATE: Gas = GAS_FOR_EPOCH_STEP + GAS_FOR_MARKET_EPOCH + GAS_FOR_RECORD_EPOCH;
+
+// least gas of the callback of an epoch: the buffer sent out and the epoch state updated
+const GAS_FOR_EPOCH_CALLBACK: Gas = GAS_FOR_EPOCH_STEP
+    + GAS_FOR_FT_TRANSFER
+    + GAS_FOR_INTEREST_DISTRIBUTION
+    + GAS_FOR_EPOCH_STATE
+    + GAS_FOR_EPOCH_UPDATE;
+
+// the pending request of a collateral read from the requester contract
+const GAS_FOR_PRICE_QUERY: Gas = 10_000_000_000_000;
+
+// kept by the price update and its callback for their receipts, the callback also
+// pays the next request
+const GAS_FOR_PRICE_UPDATE: Gas = 25_000_000_000_000;
+
 construct_uint! {
     /// 256-bit unsigned integer.
     pub struct U256(4);
@@ -41,6 +137,17 @@ construct_uint! {
 pub(crate) enum StorageKey {
     WhitelistElem,
     Collateral,
+    ReserveElem,
+    Price,
+    CollateralIndex,
+    TargetDepositRateHistory,
+    EpochHistory,
+    TotalLocked,
+    IsolatedDebt,
+    BorrowerIsolatedDebt,
+    Category,
+    BorrowerCategory,
+    StorageBalance,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -63,7 +170,6 @@ impl Contract {
     #[init]
     pub fn new(
         owner_id: AccountId,
-        stable_coin_contract: AccountId,
         oracle_contrract: AccountId,
         market_contract: AccountId,
         liquidation_contract: AccountId,
@@ -75,6 +181,7 @@ impl Contract {
         anc_purchase_factor: D128,
         oracle_payment_token: AccountId,
         requester_contract: AccountId,
+        price_timeframe: u64,
     ) -> Self {
         assert!(!env::state_exists(), "The contract is already initialized");
         assert!(
@@ -84,7 +191,6 @@ impl Contract {
 
         let config = Config {
             owner_id,
-            stable_coin_contract,
             oracle_contrract,
             market_contract,
             liquidation_contract,
@@ -96,34 +202,39 @@ impl Contract {
             anc_purchase_factor,
             oracle_payment_token,
             requester_contract,
+            price_timeframe,
+            deposit_rate_controller: None,
         };
 
         let state = State {
-            deposit_rate: D128::zero(),
-            prev_stable_coin_total_supply: 0,
             last_executed_height: 0u64,
-            prev_exchange_rate: D128::one(),
-            prev_interest_buffer: 0,
-            last_price_response: PriceResponse {
-                price: D128::one(),
-                last_updated_at: env::block_timestamp(),
-            },
+            target_deposit_rate_adjustments: 0,
+            legacy_borrowers: 0,
         };
 
         let collection = Collection {
             white_list_elem_map: UnorderedMap::new(StorageKey::WhitelistElem),
-            collateral_map: LookupMap::new(StorageKey::Collateral),
+            collateral_map: TreeMap::new(StorageKey::CollateralIndex),
+            legacy_collateral_map: LookupMap::new(StorageKey::Collateral),
+            reserve_elem_map: UnorderedMap::new(StorageKey::ReserveElem),
+            price_map: LookupMap::new(StorageKey::Price),
+            target_deposit_rate_history: LookupMap::new(StorageKey::TargetDepositRateHistory),
+            epoch_history: LookupMap::new(StorageKey::EpochHistory),
+            total_locked_map: LookupMap::new(StorageKey::TotalLocked),
+            isolated_debt_map: LookupMap::new(StorageKey::IsolatedDebt),
+            borrower_isolated_debt_map: LookupMap::new(StorageKey::BorrowerIsolatedDebt),
+            category_map: UnorderedMap::new(StorageKey::Category),
+            borrower_category_map: LookupMap::new(StorageKey::BorrowerCategory),
+            storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
         };
 
-        let mut instance = Self {
+        Self::internal_write_state_version();
+
+        Self {
             config,
             state,
             collection,
-        };
-
-        instance.internal_update_price_response();
-
-        instance
+        }
     }
 
     #[payable]
@@ -131,23 +242,36 @@ impl Contract {
         &mut self,
         name: String,
         symbol: String,
+        decimals: u8,
         collateral_token: AccountId,
         custody_contract: AccountId,
         max_ltv: D128,
+        liquidation_ltv: D128,
+        price_feed: PriceFeed,
     ) {
         assert_one_yocto();
         self.assert_owner();
-        self.internal_update_price_response();
+        self.assert_valid_ltvs(max_ltv, liquidation_ltv);
+        self.assert_valid_decimals(decimals);
 
         self.add_white_list_elem_map(
             &collateral_token,
             &WhitelistElem {
                 name: name.to_string(),
                 symbol: symbol.to_string(),
+                decimals,
                 custody_contract,
                 max_ltv,
+                liquidation_ltv,
+                price_feed,
+                status: WhitelistStatus::Active,
+                debt_ceiling: None,
+                category: None,
             },
         );
+
+        // the collateral is only valued once its feed reports a price
+        self.internal_create_new_price_request(&collateral_token);
     }
 
     #[payable]
@@ -156,77 +280,284 @@ impl Contract {
         collateral_token: AccountId,
         custody_contract: Option<AccountId>,
         max_ltv: Option<D128>,
+        liquidation_ltv: Option<D128>,
+        price_feed: Option<PriceFeed>,
     ) {
         assert_one_yocto();
         self.assert_owner();
-        self.internal_update_price_response();
         let mut white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
 
         if let Some(custody_contract) = custody_contract {
             white_list_elem.custody_contract = custody_contract;
         }
 
-        if let Some(max_ltv) = max_ltv {
-            white_list_elem.max_ltv = max_ltv;
+        if max_ltv.is_some() || liquidation_ltv.is_some() {
+            white_list_elem.max_ltv = max_ltv.unwrap_or(white_list_elem.max_ltv);
+            white_list_elem.liquidation_ltv =
+                liquidation_ltv.unwrap_or(white_list_elem.liquidation_ltv);
+            self.assert_valid_ltvs(white_list_elem.max_ltv, white_list_elem.liquidation_ltv);
+        }
+
+        // the next price request of the collateral goes to the new feed
+        if let Some(price_feed) = price_feed {
+            white_list_elem.price_feed = price_feed;
+        }
+
+        self.add_white_list_elem_map(&collateral_token, &white_list_elem);
+    }
+
+    /// Pauses new locks of a collateral, or deprecates it with a reduced LTV
+    /// for the amounts still locked
+    #[payable]
+    pub fn update_whitelist_status(
+        &mut self,
+        collateral_token: AccountId,
+        status: WhitelistStatus,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
+
+        if let WhitelistStatus::Deprecated { max_ltv } = status {
+            assert!(
+                max_ltv <= white_list_elem.max_ltv,
+                "A deprecated collateral cannot have a higher LTV"
+            );
+        }
+
+        white_list_elem.status = status;
+        self.add_white_list_elem_map(&collateral_token, &white_list_elem);
+    }
+
+    /// Isolates a collateral under a ceiling on the loans it backs, or lifts its
+    /// isolation. Borrowers already combining it with other collaterals keep
+    /// their position but cannot lock more collaterals
+    #[payable]
+    pub fn update_debt_ceiling(&mut self, collateral_token: AccountId, debt_ceiling: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
+
+        white_list_elem.debt_ceiling = debt_ceiling;
+        self.add_white_list_elem_map(&collateral_token, &white_list_elem);
+    }
+
+    /// Registers a category of correlated collaterals with the LTVs its borrowers get
+    #[payable]
+    pub fn register_category(&mut self, category: String, max_ltv: D128, liquidation_ltv: D128) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(
+            self.collection.category_map.get(&category).is_none(),
+            "Category already registered"
+        );
+        self.assert_valid_ltvs(max_ltv, liquidation_ltv);
+
+        self.collection.category_map.insert(
+            &category,
+            &AssetCategory {
+                max_ltv,
+                liquidation_ltv,
+            },
+        );
+    }
+
+    #[payable]
+    pub fn update_category(
+        &mut self,
+        category: String,
+        max_ltv: Option<D128>,
+        liquidation_ltv: Option<D128>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut asset_category: AssetCategory = self.get_category_map(&category);
+
+        asset_category.max_ltv = max_ltv.unwrap_or(asset_category.max_ltv);
+        asset_category.liquidation_ltv = liquidation_ltv.unwrap_or(asset_category.liquidation_ltv);
+        self.assert_valid_ltvs(asset_category.max_ltv, asset_category.liquidation_ltv);
+
+        self.collection
+            .category_map
+            .insert(&category, &asset_category);
+    }
+
+    /// Moves a collateral into a category, or out of any with `None`. Borrowers of
+    /// its former category value it with its own LTVs from then on
+    #[payable]
+    pub fn update_whitelist_category(
+        &mut self,
+        collateral_token: AccountId,
+        category: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
+
+        if let Some(category) = category.as_ref() {
+            self.get_category_map(category);
         }
 
+        white_list_elem.category = category;
         self.add_white_list_elem_map(&collateral_token, &white_list_elem);
     }
 
+    /// Removes a deprecated collateral nobody holds anymore. Borrowers that locked
+    /// collaterals before the collateral map became iterable must be indexed first
+    #[payable]
+    pub fn remove_whitelist(&mut self, collateral_token: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
+
+        assert!(
+            matches!(white_list_elem.status, WhitelistStatus::Deprecated { .. }),
+            "Only a deprecated collateral can be removed"
+        );
+        assert_eq!(
+            self.get_total_locked(&collateral_token),
+            0,
+            "The collateral is still locked by borrowers"
+        );
+        assert_eq!(
+            self.state.legacy_borrowers, 0,
+            "Borrowers of the V0 layout must be indexed first"
+        );
+
+        self.collection
+            .white_list_elem_map
+            .remove(&collateral_token);
+        self.collection.total_locked_map.remove(&collateral_token);
+        self.collection.price_map.remove(&collateral_token);
+    }
+
+    /// Registers a stable asset reserve of the market
     #[payable]
-    pub fn execute_epoch_operations(&mut self) {
+    pub fn register_reserve(&mut self, stable_asset: AccountId, decimals: u8) {
         assert_one_yocto();
-        self.internal_update_price_response();
+        self.assert_owner();
+        self.assert_valid_decimals(decimals);
+        assert!(
+            self.collection.reserve_elem_map.get(&stable_asset).is_none(),
+            "Reserve already registered"
+        );
 
-        if env::block_index() < self.state.last_executed_height + self.config.epoch_period {
+        self.add_reserve_elem_map(
+            &stable_asset,
+            &ReserveElem {
+                deposit_rate: D128::zero(),
+                prev_stable_coin_total_supply: 0,
+                prev_exchange_rate: D128::one(),
+                prev_interest_buffer: 0,
+                target_deposit_rate: self.config.target_deposit_rate,
+                epoch_count: 0,
+                decimals,
+                // its first epoch runs once an epoch period passed
+                last_executed_height: env::block_index(),
+            },
+        );
+
+        // stable assets are pegged until their own feed reports a price
+        self.collection.price_map.insert(
+            &stable_asset,
+            &PriceResponse {
+                price: D128::one(),
+                last_updated_at: env::block_timestamp(),
+            },
+        );
+    }
+
+    /// Stores the price of a collateral once its request is finalized and requests
+    /// the next one. Anyone can refresh a price, each collateral keeps one request
+    #[payable]
+    pub fn update_price(&mut self, collateral_token: AccountId) -> Promise {
+        assert_one_yocto();
+        self.get_white_list_elem_map(&collateral_token);
+
+        self.internal_update_price_response(collateral_token)
+    }
+
+    #[payable]
+    /// Executes the epoch of a reserve once its epoch period passed. An epoch takes
+    /// most of the gas of a call, so every reserve runs its epochs by calls of its own
+    #[payable]
+    pub fn execute_epoch_operations(&mut self, stable_asset: AccountId) -> Promise {
+        assert_one_yocto();
+        let reserve_elem: ReserveElem = self.get_reserve_elem_map(&stable_asset);
+
+        if env::block_index() < reserve_elem.last_executed_height + self.config.epoch_period {
             env::panic("Epoch Not Passed".as_bytes());
         }
 
         let block_height = env::block_index();
-        let blocks = block_height - self.state.last_executed_height;
+        let blocks = block_height - reserve_elem.last_executed_height;
 
-        let interest_buffer = env::account_balance();
+        let callback_gas = env::prepaid_gas()
+            .checked_sub(
+                env::used_gas() + GAS_FOR_EPOCH_STATE + GAS_FOR_BALANCE_QUERY + GAS_FOR_EPOCH_STEP,
+            )
+            .filter(|gas| *gas >= GAS_FOR_EPOCH_CALLBACK)
+            .expect("Not enough gas to execute the epoch");
 
         ext_market::get_epoch_state(
+            stable_asset.clone(),
             Some(block_height),
             None,
             &self.config.market_contract,
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            GAS_FOR_EPOCH_STATE,
         )
+        .and(fungible_token::ft_balance_of(
+            env::current_account_id(),
+            &stable_asset,
+            NO_DEPOSIT,
+            GAS_FOR_BALANCE_QUERY,
+        ))
         .then(ext_self::callback_execute_epoch_operations(
+            stable_asset,
             blocks,
-            interest_buffer,
             &env::current_account_id(),
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
-        ));
+            callback_gas,
+        ))
     }
 
+    /// Has the custody of a collateral claim its rewards, swap them to the stable
+    /// coin and send them to the overseer. A distribution takes most of the gas of a
+    /// call, so every collateral is distributed by a call of its own
     #[payable]
-    pub fn update_epoch_state(&mut self, intereset_buffer: U128, distributed_intereset: U128) {
+    pub fn distribute_rewards(&mut self, collateral_token: AccountId) -> Promise {
         assert_one_yocto();
-        self.internal_update_price_response();
-        self.assert_owner();
+        let white_list_elem: WhitelistElem = self.get_white_list_elem_map(&collateral_token);
 
-        let block_height = env::block_index();
-        let blocks = block_height - self.state.last_executed_height;
+        let distribution_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_REWARDS_CALL)
+            .filter(|gas| *gas >= GAS_FOR_CUSTODY_REWARDS)
+            .expect("Not enough gas to distribute the rewards");
 
-        ext_market::get_epoch_state(
-            Some(block_height),
-            Some(distributed_intereset),
-            &self.config.market_contract,
+        ext_custody::distribute_rewards(
+            &white_list_elem.custody_contract,
             NO_DEPOSIT,
-            SINGLE_CALL_GAS,
+            distribution_gas,
         )
-        .then(ext_self::callback_update_epoch_state(
+    }
+
+    #[payable]
+    pub fn update_epoch_state(
+        &mut self,
+        stable_asset: AccountId,
+        intereset_buffer: U128,
+        distributed_intereset: U128,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+
+        // the owner distributes the buffer without purchasing ANC
+        self.internal_update_epoch_state(
+            stable_asset,
             intereset_buffer,
             distributed_intereset,
-            block_height,
-            blocks,
-            &env::current_account_id(),
-            NO_DEPOSIT,
-            SINGLE_CALL_GAS,
-        ));
+            U128::from(0),
+        );
     }
 }
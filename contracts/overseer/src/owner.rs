@@ -13,6 +13,7 @@ impl Contract {
         target_deposit_rate: Option<D128>,
         oracle_payment_token: Option<AccountId>,
         requester_contract: Option<AccountId>,
+        price_timeframe: Option<u64>,
     ) {
         self.assert_owner();
         assert_one_yocto();
@@ -33,6 +34,12 @@ impl Contract {
         }
         if let Some(target_deposit_rate) = target_deposit_rate {
             self.config.target_deposit_rate = target_deposit_rate;
+
+            // an explicit target overrides what the controller reached so far
+            for (stable_asset, mut reserve_elem) in self.collection.reserve_elem_map.to_vec() {
+                reserve_elem.target_deposit_rate = target_deposit_rate;
+                self.add_reserve_elem_map(&stable_asset, &reserve_elem);
+            }
         }
         if let Some(oracle_payment_token) = oracle_payment_token {
             self.config.oracle_payment_token = oracle_payment_token;
@@ -40,5 +47,41 @@ impl Contract {
         if let Some(requester_contract) = requester_contract {
             self.config.requester_contract = requester_contract;
         }
+        if let Some(price_timeframe) = price_timeframe {
+            self.config.price_timeframe = price_timeframe;
+        }
+    }
+
+    /// Enables the target deposit rate controller with the given bounds, or
+    /// disables it with `None`, leaving every target where it is
+    #[payable]
+    pub fn update_deposit_rate_controller(&mut self, controller: Option<DepositRateController>) {
+        self.assert_owner();
+        assert_one_yocto();
+        if let Some(controller) = controller.as_ref() {
+            assert!(
+                controller.min_target_deposit_rate <= controller.max_target_deposit_rate,
+                "The minimum target deposit rate exceeds the maximum"
+            );
+            assert!(
+                controller.smoothing <= D128::one(),
+                "Smoothing must not exceed 1"
+            );
+        }
+
+        self.config.deposit_rate_controller = controller;
+    }
+
+    /// Moves collaterals locked before the upgrade into the iterable map, so that
+    /// `get_all_collaterals` lists their borrowers
+    #[payable]
+    pub fn index_collaterals(&mut self, borrowers: Vec<AccountId>) {
+        self.assert_owner();
+        assert_one_yocto();
+        for borrower in borrowers {
+            if let Some(collaterals) = self.collection.legacy_collateral_map.get(&borrower) {
+                self.add_collateral_map(&borrower, &collaterals);
+            }
+        }
     }
 }
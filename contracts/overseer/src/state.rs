@@ -4,7 +4,6 @@ use crate::*;
 #[serde(crate = "near_sdk::serde")]
 pub struct Config {
     pub owner_id: AccountId,
-    pub stable_coin_contract: AccountId,
     pub oracle_contrract: AccountId,
     pub market_contract: AccountId,
     pub liquidation_contract: AccountId,
@@ -16,23 +15,172 @@ pub struct Config {
     pub anc_purchase_factor: D128,
     pub oracle_payment_token: AccountId,
     pub requester_contract: AccountId,
+    // unit: seconds
+    pub price_timeframe: u64,
+    // moves the target deposit rate of every reserve at each epoch when set
+    pub deposit_rate_controller: Option<DepositRateController>,
+}
+
+/// Bounds and pace of the target deposit rate adjustments
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DepositRateController {
+    pub min_target_deposit_rate: D128,
+    pub max_target_deposit_rate: D128,
+    // largest change of a target in one epoch
+    pub max_step: D128,
+    // share of the gap to the yielded rate closed at each epoch
+    pub smoothing: D128,
+    // the target only rises while the interest buffer holds this share of the deposits
+    pub min_buffer_ratio: D128,
+}
+
+/// Target deposit rate change made by the controller at the end of an epoch
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TargetDepositRateAdjustment {
+    pub stable_asset: AccountId,
+    pub block_height: BlockHeight,
+    pub deposit_rate: D128,
+    // deposit rate plus the interest buffer inflow, per deposit and block
+    pub yield_rate: D128,
+    pub interest_buffer: U128,
+    pub prev_target_deposit_rate: D128,
+    pub target_deposit_rate: D128,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct State {
-    pub deposit_rate: D128,
-    pub prev_stable_coin_total_supply: Balance,
-    pub prev_exchange_rate: D128,
-    pub prev_interest_buffer: u128,
+    // block of the latest epoch of any reserve
     pub last_executed_height: BlockHeight,
-    pub last_price_response: PriceResponse,
+    pub target_deposit_rate_adjustments: u64,
+    // borrowers of the V0 layout whose collaterals are not indexed yet
+    pub legacy_borrowers: u64,
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Collection {
     pub white_list_elem_map: UnorderedMap<AccountId, WhitelistElem>,
-    pub collateral_map: LookupMap<AccountId, Tokens>,
+    // ordered by borrower so that pages stay stable while borrowers come and go
+    pub collateral_map: TreeMap<AccountId, Tokens>,
+    // collaterals locked before the map became iterable, moved on their next update
+    pub legacy_collateral_map: LookupMap<AccountId, Tokens>,
+    pub reserve_elem_map: UnorderedMap<AccountId, ReserveElem>,
+    pub price_map: LookupMap<AccountId, PriceResponse>,
+    // the latest adjustments, keyed by their number modulo the history size
+    pub target_deposit_rate_history: LookupMap<u64, TargetDepositRateAdjustment>,
+    // the latest epochs of every reserve, keyed by their number modulo the history size
+    pub epoch_history: LookupMap<(AccountId, u64), EpochRecord>,
+    // amount of each collateral held across the borrowers of `collateral_map`
+    pub total_locked_map: LookupMap<AccountId, Balance>,
+    // loan value attributed to each isolated collateral, as last reported by the market
+    pub isolated_debt_map: LookupMap<AccountId, Balance>,
+    // isolated collateral of a borrower and the loan value attributed to it
+    pub borrower_isolated_debt_map: LookupMap<AccountId, (AccountId, Balance)>,
+    pub category_map: UnorderedMap<String, AssetCategory>,
+    // category the borrower opted into
+    pub borrower_category_map: LookupMap<AccountId, String>,
+    // NEP-145 storage balance of the registered accounts
+    pub storage_balance_map: LookupMap<AccountId, Balance>,
+}
+
+/// Age of the price of an asset and whether it can still be acted on
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceStatus {
+    pub price: D128,
+    pub last_updated_at: u64,
+    // unit: seconds
+    pub age: u64,
+    pub is_fresh: bool,
+}
+
+/// Collateral of a borrower valued at its latest price
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollateralPosition {
+    pub collateral_token: AccountId,
+    pub amount: U128,
+    pub price: D128,
+    pub value: U128,
+    pub max_ltv: D128,
+    pub liquidation_ltv: D128,
+    // price under which the borrower can be liquidated, the other prices unchanged
+    pub liquidation_price: Option<D128>,
+}
+
+/// Collaterals and loans of a borrower, with how close they are to liquidation
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BorrowerPosition {
+    pub borrower: AccountId,
+    pub category: Option<String>,
+    pub collaterals: Vec<CollateralPosition>,
+    pub collateral_value: U128,
+    pub borrow_limit: U128,
+    // value of the loans above which the borrower can be liquidated
+    pub liquidation_limit: U128,
+    pub loans: Tokens,
+    pub loan_value: U128,
+    // loan value over collateral value
+    pub ltv: D128,
+    // liquidation limit over loan value, none without a loan
+    pub health_factor: Option<D128>,
+}
+
+/// Outcome of a borrower of a liquidation batch
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationStatus {
+    Started,
+    Safe,
+    // not enough gas left in the batch
+    Skipped,
+}
+
+/// Outcome of an epoch of a reserve
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EpochRecord {
+    pub block_height: BlockHeight,
+    pub exchange_rate: D128,
+    pub deposit_rate: D128,
+    pub interest_buffer: U128,
+    pub anc_purchase_amount: U128,
+    pub distributed_interest: U128,
+    pub anc_emission_rate: D128,
+}
+
+/// Averages of the records of the latest epochs of a reserve
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EpochAverages {
+    // number of epochs averaged, fewer than asked when the history is shorter
+    pub epochs: u64,
+    pub deposit_rate: D128,
+    pub interest_buffer: U128,
+    pub anc_purchase_amount: U128,
+    pub distributed_interest: U128,
+    pub anc_emission_rate: D128,
+}
+
+/// Epoch state of a market reserve, keyed by its stable asset
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveElem {
+    pub deposit_rate: D128,
+    pub prev_stable_coin_total_supply: Balance,
+    pub prev_exchange_rate: D128,
+    pub prev_interest_buffer: u128,
+    pub target_deposit_rate: D128,
+    // epochs executed since the reserve was registered
+    pub epoch_count: u64,
+    // of the stable asset
+    pub decimals: u8,
+    // block of the latest epoch of the reserve
+    pub last_executed_height: BlockHeight,
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -40,17 +188,94 @@ pub struct Collection {
 pub struct WhitelistElem {
     pub name: String,
     pub symbol: String,
+    // of the collateral token, its amounts are valued in whole tokens
+    pub decimals: u8,
+    pub max_ltv: D128,
+    // loan to value above which the borrower can be liquidated, above max_ltv
+    pub liquidation_ltv: D128,
+    pub custody_contract: AccountId,
+    pub price_feed: PriceFeed,
+    pub status: WhitelistStatus,
+    // isolates the collateral: it backs loans alone and up to this value in total
+    pub debt_ceiling: Option<U128>,
+    // assets the collateral is correlated with
+    pub category: Option<String>,
+}
+
+impl WhitelistElem {
+    /// LTV the collateral is valued with, reduced once it is deprecated
+    pub fn current_max_ltv(&self) -> D128 {
+        match self.status {
+            WhitelistStatus::Deprecated { max_ltv } => std::cmp::min(max_ltv, self.max_ltv),
+            _ => self.max_ltv,
+        }
+    }
+
+    /// The collateral with the LTVs of the category of a borrower, when it belongs to it
+    pub fn in_category(mut self, category: &Option<(String, AssetCategory)>) -> Self {
+        if let Some((category, asset_category)) = category {
+            if self.category.as_ref() == Some(category) {
+                self.max_ltv = asset_category.max_ltv;
+                self.liquidation_ltv = asset_category.liquidation_ltv;
+            }
+        }
+        self
+    }
+}
+
+/// Correlated collaterals, borrowed against with their own LTVs by the borrowers
+/// whose collaterals all belong to the category
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetCategory {
+    pub max_ltv: D128,
+    pub liquidation_ltv: D128,
+}
+
+/// Stage of a collateral in its lifecycle. Only active collaterals can be locked
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum WhitelistStatus {
+    Active,
+    LockPaused,
+    // wound down: locked amounts are borrowed against with the reduced max_ltv until
+    // unlocked, the liquidation LTV is unchanged
+    Deprecated { max_ltv: D128 },
+}
+
+/// Whitelisted collateral with the amount every borrower holds of it
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WhitelistResponseElem {
+    pub collateral_token: AccountId,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
     pub max_ltv: D128,
+    pub liquidation_ltv: D128,
     pub custody_contract: AccountId,
+    pub status: WhitelistStatus,
+    pub total_locked: U128,
+    pub debt_ceiling: Option<U128>,
+    pub isolated_debt: U128,
+    pub category: Option<String>,
+}
+
+/// Oracle source the price of a collateral is requested from
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PriceFeed {
+    pub end_point: String,
+    pub source_path: String,
 }
 
-#[near_bindgen]
 impl Contract {
-    pub fn add_white_list_elem_map(&mut self, key: &String, value: &WhitelistElem) {
+    pub(crate) fn add_white_list_elem_map(&mut self, key: &String, value: &WhitelistElem) {
         self.collection.white_list_elem_map.insert(&key, value);
     }
 
-    pub fn get_white_list_elem_map(&self, key: &String) -> WhitelistElem {
+    pub(crate) fn get_white_list_elem_map(&self, key: &String) -> WhitelistElem {
         match self.collection.white_list_elem_map.get(&key) {
             Some(value) => {
                 let log_message = format!("Value from LookupMap is {:?}", value.clone());
@@ -61,18 +286,141 @@ impl Contract {
         }
     }
 
-    pub fn add_collateral_map(&mut self, key: &String, value: &Tokens) {
-        self.collection.collateral_map.insert(&key, value);
+    pub(crate) fn add_collateral_map(&mut self, key: &String, value: &Tokens) {
+        // legacy collaterals are counted once they join the iterable map
+        let prev_value: Tokens = self.collection.collateral_map.get(&key).unwrap_or_default();
+        for collateral in prev_value.iter() {
+            let total_locked = self.get_total_locked(&collateral.0);
+            self.collection
+                .total_locked_map
+                .insert(&collateral.0, &(total_locked - collateral.1));
+        }
+        for collateral in value.iter() {
+            let total_locked = self.get_total_locked(&collateral.0);
+            self.collection
+                .total_locked_map
+                .insert(&collateral.0, &(total_locked + collateral.1));
+        }
+
+        if self.collection.legacy_collateral_map.remove(&key).is_some() {
+            self.state.legacy_borrowers = self.state.legacy_borrowers.saturating_sub(1);
+        }
+
+        // borrowers without collaterals are left out of the iteration
+        if value.is_empty() {
+            self.collection.collateral_map.remove(&key);
+        } else {
+            self.collection.collateral_map.insert(&key, value);
+        }
     }
 
-    pub fn get_collateral_map(&self, key: &String) -> Tokens {
-        match self.collection.collateral_map.get(&key) {
+    pub(crate) fn get_collateral_map(&self, key: &String) -> Tokens {
+        match self
+            .collection
+            .collateral_map
+            .get(&key)
+            .or_else(|| self.collection.legacy_collateral_map.get(&key))
+        {
             Some(value) => {
                 let log_message = format!("Value from LookupMap is {:?}", value.clone());
                 env::log(log_message.as_bytes());
                 value
             }
-            None => env::panic("".as_bytes()),
+            None => vec![],
+        }
+    }
+
+    pub(crate) fn add_reserve_elem_map(&mut self, key: &String, value: &ReserveElem) {
+        self.collection.reserve_elem_map.insert(&key, value);
+    }
+
+    pub(crate) fn get_reserve_elem_map(&self, key: &String) -> ReserveElem {
+        match self.collection.reserve_elem_map.get(&key) {
+            Some(value) => value,
+            None => env::panic("Reserve not found".as_bytes()),
+        }
+    }
+
+    pub(crate) fn get_total_locked(&self, collateral_token: &AccountId) -> Balance {
+        self.collection
+            .total_locked_map
+            .get(collateral_token)
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn get_isolated_debt(&self, collateral_token: &AccountId) -> Balance {
+        self.collection
+            .isolated_debt_map
+            .get(collateral_token)
+            .unwrap_or(0)
+    }
+
+    /// Attributes the loan value of a borrower to its isolated collateral, in place
+    /// of the value previously attributed
+    pub(crate) fn add_borrower_isolated_debt_map(
+        &mut self,
+        key: &String,
+        value: Option<(AccountId, Balance)>,
+    ) {
+        if let Some(prev_value) = self.collection.borrower_isolated_debt_map.get(key) {
+            let isolated_debt = self.get_isolated_debt(&prev_value.0);
+            self.collection
+                .isolated_debt_map
+                .insert(&prev_value.0, &(isolated_debt - prev_value.1));
+        }
+
+        match value {
+            Some(value) => {
+                let isolated_debt = self.get_isolated_debt(&value.0);
+                self.collection
+                    .isolated_debt_map
+                    .insert(&value.0, &(isolated_debt + value.1));
+                self.collection
+                    .borrower_isolated_debt_map
+                    .insert(key, &value);
+            }
+            None => {
+                self.collection.borrower_isolated_debt_map.remove(key);
+            }
+        }
+    }
+
+    pub(crate) fn get_category_map(&self, key: &String) -> AssetCategory {
+        match self.collection.category_map.get(key) {
+            Some(value) => value,
+            None => env::panic(format!("Category {} not found", key).as_bytes()),
+        }
+    }
+
+    /// The category the borrower opted into, with its LTVs
+    pub(crate) fn get_borrower_category(
+        &self,
+        borrower: &AccountId,
+    ) -> Option<(String, AssetCategory)> {
+        self.collection
+            .borrower_category_map
+            .get(borrower)
+            .map(|category| {
+                let asset_category = self.get_category_map(&category);
+                (category, asset_category)
+            })
+    }
+
+    pub(crate) fn get_epoch_record(&self, stable_asset: &AccountId, index: u64) -> EpochRecord {
+        match self
+            .collection
+            .epoch_history
+            .get(&(stable_asset.clone(), index % EPOCH_HISTORY_SIZE))
+        {
+            Some(value) => value,
+            None => env::panic("Epoch record not found".as_bytes()),
+        }
+    }
+
+    pub(crate) fn get_price_map(&self, key: &String) -> PriceResponse {
+        match self.collection.price_map.get(&key) {
+            Some(value) => value,
+            None => env::panic(format!("No price for {}", key).as_bytes()),
         }
     }
 }
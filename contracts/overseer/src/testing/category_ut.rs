@@ -0,0 +1,188 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{
+    callback_context, loans_result, register_collateral, register_storage, set_price,
+    setup_contract,
+};
+use crate::*;
+
+/// bNEAR and stNEAR share the "near" category, the borrower holds both
+fn setup_category() -> (VMContextBuilder, Contract) {
+    let (mut context, mut contract) = setup_contract();
+    register_collateral(
+        &mut context,
+        &mut contract,
+        "stNEAR",
+        D128::new_exp(4, -1),
+        D128::new_exp(5, -1),
+    );
+    set_price(&mut contract, "stnear", D128::new_exp(5, 0));
+    testing_env!(context.build());
+
+    contract.register_category(
+        String::from("near"),
+        D128::new_exp(9, -1),
+        D128::new_exp(93, -2),
+    );
+    for collateral_token in ["bnear", "stnear"] {
+        contract.update_whitelist_category(
+            AccountId::from(collateral_token),
+            Some(String::from("near")),
+        );
+    }
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![
+            (AccountId::from("bnear"), 100),
+            (AccountId::from("stnear"), 100),
+        ],
+    );
+    register_storage(&mut contract, &["borrower"]);
+
+    (context, contract)
+}
+
+#[test]
+fn category_borrow_limit() {
+    let (_, mut contract) = setup_category();
+    // 100 * 5 * 0.5 + 100 * 5 * 0.4
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(450)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+
+    contract
+        .collection
+        .borrower_category_map
+        .insert(&AccountId::from("borrower"), &String::from("near"));
+    // 200 * 5 * 0.9
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(900)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+
+    let position = contract.get_borrower_position_with_loans(AccountId::from("borrower"), vec![]);
+    assert_eq!(Some(String::from("near")), position.category);
+    assert_eq!(U128::from(930), position.liquidation_limit);
+    assert_eq!(D128::new_exp(9, -1), position.collaterals[1].max_ltv);
+    assert_eq!(
+        D128::new_exp(93, -2),
+        position.collaterals[1].liquidation_ltv
+    );
+}
+
+#[test]
+fn deprecated_collateral_in_category() {
+    let (_, mut contract) = setup_category();
+    contract
+        .collection
+        .borrower_category_map
+        .insert(&AccountId::from("borrower"), &String::from("near"));
+
+    contract.update_whitelist_status(
+        AccountId::from("stnear"),
+        WhitelistStatus::Deprecated {
+            max_ltv: D128::new_exp(2, -1),
+        },
+    );
+    // 100 * 5 * 0.9 + 100 * 5 * 0.2
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(550)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+}
+
+#[test]
+fn opt_into_category() {
+    let (mut context, mut contract) = setup_category();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.update_borrower_category(Some(String::from("near")));
+
+    callback_context(&mut context, vec![loans_result(800)]);
+    contract.callback_update_borrower_category(
+        AccountId::from("borrower"),
+        Some(String::from("near")),
+        900,
+    );
+    assert_eq!(
+        Some(String::from("near")),
+        contract
+            .collection
+            .borrower_category_map
+            .get(&AccountId::from("borrower"))
+    );
+
+    callback_context(&mut context, vec![loans_result(400)]);
+    contract.callback_update_borrower_category(AccountId::from("borrower"), None, 450);
+    assert!(contract
+        .collection
+        .borrower_category_map
+        .get(&AccountId::from("borrower"))
+        .is_none());
+}
+
+#[test]
+#[should_panic(expected = "The loans exceed the borrow limit of the category")]
+fn opt_out_of_category_above_borrow_limit() {
+    let (mut context, mut contract) = setup_category();
+    contract
+        .collection
+        .borrower_category_map
+        .insert(&AccountId::from("borrower"), &String::from("near"));
+
+    callback_context(&mut context, vec![loans_result(800)]);
+    contract.callback_update_borrower_category(AccountId::from("borrower"), None, 450);
+}
+
+#[test]
+#[should_panic(expected = "weth is outside the category")]
+fn opt_in_with_collateral_outside_category() {
+    let (mut context, mut contract) = setup_category();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![
+            (AccountId::from("bnear"), 100),
+            (AccountId::from("weth"), 1),
+        ],
+    );
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.update_borrower_category(Some(String::from("near")));
+}
+
+#[test]
+#[should_panic(expected = "weth is outside the category of the borrower")]
+fn lock_collateral_outside_category() {
+    let (mut context, mut contract) = setup_category();
+    contract
+        .collection
+        .borrower_category_map
+        .insert(&AccountId::from("borrower"), &String::from("near"));
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.lock_collateral(vec![(AccountId::from("weth"), 1)]);
+}
+
+#[test]
+#[should_panic(expected = "Category btc not found")]
+fn update_whitelist_category_unknown() {
+    let (_, mut contract) = setup_category();
+
+    contract.update_whitelist_category(AccountId::from("weth"), Some(String::from("btc")));
+}
+
+#[test]
+#[should_panic(expected = "The liquidation LTV must be above the max LTV")]
+fn update_category_with_invalid_ltvs() {
+    let (_, mut contract) = setup_category();
+
+    contract.update_category(String::from("near"), Some(D128::new_exp(95, -2)), None);
+}
@@ -0,0 +1,485 @@
+use near_sdk::serde_json::{self, Value};
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{callback_context, created_function_calls, new_contract, setup_contract};
+use crate::*;
+
+/// The stable coin reserve with a deposit rate controller
+fn setup_controller() -> (VMContextBuilder, Contract) {
+    let (context, mut contract) = new_contract();
+    contract.register_reserve(AccountId::from("stable_coin"), 6);
+    contract.add_reserve_elem_map(
+        &AccountId::from("stable_coin"),
+        &ReserveElem {
+            deposit_rate: D128::zero(),
+            prev_stable_coin_total_supply: 1_000_000,
+            prev_exchange_rate: D128::one(),
+            prev_interest_buffer: 0,
+            target_deposit_rate: D128::new_exp(5, -3),
+            epoch_count: 0,
+            decimals: 6,
+            last_executed_height: 0,
+        },
+    );
+    contract.update_deposit_rate_controller(Some(DepositRateController {
+        min_target_deposit_rate: D128::new_exp(1, -3),
+        max_target_deposit_rate: D128::new_exp(1, -2),
+        max_step: D128::new_exp(2, -3),
+        smoothing: D128::new_exp(5, -1),
+        min_buffer_ratio: D128::new_exp(5, -2),
+    }));
+
+    (context, contract)
+}
+
+fn target_deposit_rate(contract: &Contract) -> D128 {
+    contract.get_target_deposit_rate(AccountId::from("stable_coin"))
+}
+
+#[test]
+fn epoch_operations_adjust_target() {
+    let (mut context, mut contract) = setup_controller();
+    context.prepaid_gas(10u64.pow(15));
+    callback_context(
+        &mut context,
+        vec![
+            PromiseResult::Successful(
+                b"[{\"num\": \"101000000\", \"decimal\": 8}, \"1000000\"]".to_vec(),
+            ),
+            PromiseResult::Successful(b"\"100000\"".to_vec()),
+        ],
+    );
+
+    contract.callback_execute_epoch_operations(AccountId::from("stable_coin"), 10);
+
+    // yield rate 0.001 + 100000 / 1000000 / 10, half the gap capped by the step
+    assert_eq!(D128::new_exp(7, -3), target_deposit_rate(&contract));
+
+    let history = contract.get_target_deposit_rate_history(None, None);
+    assert_eq!(1, history.len());
+    assert_eq!(
+        TargetDepositRateAdjustment {
+            stable_asset: AccountId::from("stable_coin"),
+            block_height: 0,
+            deposit_rate: D128::new_exp(1, -3),
+            yield_rate: D128::new_exp(11, -3),
+            interest_buffer: U128::from(80_000),
+            prev_target_deposit_rate: D128::new_exp(5, -3),
+            target_deposit_rate: D128::new_exp(7, -3),
+        },
+        history[0].1
+    );
+}
+
+#[test]
+fn thin_buffer_holds_target() {
+    let (_, mut contract) = setup_controller();
+    let reserve_elem = contract.get_reserve(AccountId::from("stable_coin"));
+
+    // 10000 is below 5% of the 1000000 deposits
+    contract.internal_adjust_target_deposit_rate(
+        &AccountId::from("stable_coin"),
+        reserve_elem,
+        D128::new_exp(1, -3),
+        100_000,
+        10_000,
+        10,
+    );
+
+    assert_eq!(D128::new_exp(5, -3), target_deposit_rate(&contract));
+}
+
+#[test]
+fn target_stays_within_bounds() {
+    let (_, mut contract) = setup_controller();
+
+    for _ in 0..3 {
+        let reserve_elem = contract.get_reserve(AccountId::from("stable_coin"));
+        contract.internal_adjust_target_deposit_rate(
+            &AccountId::from("stable_coin"),
+            reserve_elem,
+            D128::zero(),
+            0,
+            100_000,
+            10,
+        );
+    }
+
+    // 0.005 - 0.0025 - 0.00125, then held at the minimum
+    assert_eq!(D128::new_exp(1, -3), target_deposit_rate(&contract));
+}
+
+#[test]
+fn target_without_controller() {
+    let (mut context, mut contract) = setup_controller();
+    testing_env!(context.attached_deposit(1).build());
+    contract.update_deposit_rate_controller(None);
+
+    let reserve_elem = contract.get_reserve(AccountId::from("stable_coin"));
+    contract.internal_adjust_target_deposit_rate(
+        &AccountId::from("stable_coin"),
+        reserve_elem,
+        D128::new_exp(1, -3),
+        100_000,
+        100_000,
+        10,
+    );
+
+    assert_eq!(D128::new_exp(5, -3), target_deposit_rate(&contract));
+    assert!(contract
+        .get_target_deposit_rate_history(None, None)
+        .is_empty());
+}
+
+#[test]
+fn target_deposit_rate_history_keeps_the_latest() {
+    let (_, mut contract) = setup_controller();
+
+    for _ in 0..105 {
+        let reserve_elem = contract.get_reserve(AccountId::from("stable_coin"));
+        contract.internal_adjust_target_deposit_rate(
+            &AccountId::from("stable_coin"),
+            reserve_elem,
+            D128::new_exp(5, -3),
+            0,
+            100_000,
+            10,
+        );
+    }
+
+    let history = contract.get_target_deposit_rate_history(None, Some(3));
+    assert_eq!(
+        vec![5, 6, 7],
+        history.iter().map(|entry| entry.0).collect::<Vec<u64>>()
+    );
+
+    let history = contract.get_target_deposit_rate_history(Some(102), None);
+    assert_eq!(
+        vec![103, 104],
+        history.iter().map(|entry| entry.0).collect::<Vec<u64>>()
+    );
+}
+
+#[test]
+fn update_config_overrides_target() {
+    let (mut context, mut contract) = setup_controller();
+    testing_env!(context.attached_deposit(1).build());
+
+    contract.update_config(
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(D128::new_exp(4, -3)),
+        None,
+        None,
+        None,
+    );
+
+    assert_eq!(D128::new_exp(4, -3), target_deposit_rate(&contract));
+}
+
+#[test]
+#[should_panic(expected = "The minimum target deposit rate exceeds the maximum")]
+fn controller_with_inverted_bounds() {
+    let (mut context, mut contract) = setup_controller();
+    testing_env!(context.attached_deposit(1).build());
+
+    contract.update_deposit_rate_controller(Some(DepositRateController {
+        min_target_deposit_rate: D128::new_exp(1, -2),
+        max_target_deposit_rate: D128::new_exp(1, -3),
+        max_step: D128::new_exp(2, -3),
+        smoothing: D128::new_exp(5, -1),
+        min_buffer_ratio: D128::new_exp(5, -2),
+    }));
+}
+
+fn epoch_record(index: u128) -> EpochRecord {
+    EpochRecord {
+        block_height: index as u64,
+        exchange_rate: D128::one(),
+        deposit_rate: D128::new_exp(index, -3),
+        interest_buffer: U128::from(1000 * index),
+        anc_purchase_amount: U128::from(10 * index),
+        distributed_interest: U128::from(index),
+        anc_emission_rate: D128::new_exp(index, 0),
+    }
+}
+
+#[test]
+fn update_epoch_state_records_epoch() {
+    let (mut context, mut contract) = setup_controller();
+    context.block_index(100);
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(
+            b"[{\"num\": \"101000000\", \"decimal\": 8}, \"1000000\"]".to_vec(),
+        )],
+    );
+
+    contract.callback_update_epoch_state(
+        AccountId::from("stable_coin"),
+        U128::from(80_000),
+        U128::from(20_000),
+        U128::from(20_000),
+        100,
+        10,
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!("callback_record_epoch", function_calls[1].1);
+
+    let args = &function_calls[1].2;
+    assert_eq!(100, args["record"]["block_height"]);
+    assert_eq!("101000000", args["record"]["exchange_rate"]["num"]);
+    assert_eq!("100000", args["record"]["deposit_rate"]["num"]);
+    assert_eq!("80000", args["record"]["interest_buffer"]);
+    assert_eq!("20000", args["record"]["anc_purchase_amount"]);
+    assert_eq!("20000", args["record"]["distributed_interest"]);
+}
+
+#[test]
+fn record_epoch_with_emission_rate() {
+    let (mut context, mut contract) = setup_controller();
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(
+            b"{\"num\": \"300000000\", \"decimal\": 8}".to_vec(),
+        )],
+    );
+
+    contract.callback_record_epoch(AccountId::from("stable_coin"), epoch_record(1));
+
+    assert_eq!(
+        vec![(
+            0,
+            EpochRecord {
+                anc_emission_rate: D128::new_exp(3, 0),
+                ..epoch_record(1)
+            }
+        )],
+        contract.get_epoch_history(AccountId::from("stable_coin"), None, None)
+    );
+}
+
+#[test]
+fn epoch_history_keeps_the_latest() {
+    let (_, mut contract) = setup_controller();
+
+    for index in 0..105 {
+        contract.internal_push_epoch_record(&AccountId::from("stable_coin"), &epoch_record(index));
+    }
+
+    let history = contract.get_epoch_history(AccountId::from("stable_coin"), None, Some(2));
+    assert_eq!(vec![(5, epoch_record(5)), (6, epoch_record(6))], history);
+
+    let history = contract.get_epoch_history(AccountId::from("stable_coin"), Some(103), None);
+    assert_eq!(vec![(104, epoch_record(104))], history);
+}
+
+#[test]
+fn proper_epoch_averages() {
+    let (_, mut contract) = setup_controller();
+
+    assert_eq!(
+        0,
+        contract
+            .get_epoch_averages(AccountId::from("stable_coin"), 3)
+            .epochs
+    );
+
+    for index in 1..=4 {
+        contract.internal_push_epoch_record(&AccountId::from("stable_coin"), &epoch_record(index));
+    }
+
+    // epochs 2, 3 and 4
+    assert_eq!(
+        EpochAverages {
+            epochs: 3,
+            deposit_rate: D128::new_exp(3, -3),
+            interest_buffer: U128::from(3000),
+            anc_purchase_amount: U128::from(30),
+            distributed_interest: U128::from(3),
+            anc_emission_rate: D128::new_exp(3, 0),
+        },
+        contract.get_epoch_averages(AccountId::from("stable_coin"), 3)
+    );
+    assert_eq!(
+        4,
+        contract
+            .get_epoch_averages(AccountId::from("stable_coin"), 10)
+            .epochs
+    );
+}
+
+#[test]
+fn distribute_rewards_of_collateral() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .attached_deposit(1)
+        .prepaid_gas(300_000_000_000_000)
+        .build());
+
+    contract.distribute_rewards(AccountId::from("bnear"));
+
+    let function_calls = created_function_calls();
+    assert_eq!(1, function_calls.len());
+    assert_eq!("custody_bnear", function_calls[0].0);
+    assert_eq!("distribute_rewards", function_calls[0].1);
+    assert!(function_calls[0].3 >= GAS_FOR_CUSTODY_REWARDS);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to distribute the rewards")]
+fn distribute_rewards_without_gas() {
+    let (mut context, mut contract) = setup_contract();
+    testing_env!(context
+        .attached_deposit(1)
+        .prepaid_gas(GAS_FOR_CUSTODY_REWARDS)
+        .build());
+
+    contract.distribute_rewards(AccountId::from("bnear"));
+}
+
+/// Epoch state of the market: an exchange rate of 1.01 over 1000000 aTokens, and
+/// the interest buffer of the overseer
+fn epoch_results(interest_buffer: u128) -> Vec<PromiseResult> {
+    vec![
+        PromiseResult::Successful(
+            b"[{\"num\": \"101000000\", \"decimal\": 8}, \"1000000\"]".to_vec(),
+        ),
+        PromiseResult::Successful(serde_json::to_vec(&U128::from(interest_buffer)).unwrap()),
+    ]
+}
+
+#[test]
+fn execute_epoch_of_reserve() {
+    let (mut context, mut contract) = setup_controller();
+    testing_env!(context
+        .attached_deposit(1)
+        .block_index(86400)
+        .prepaid_gas(300_000_000_000_000)
+        .build());
+
+    contract.execute_epoch_operations(AccountId::from("stable_coin"));
+
+    let function_calls = created_function_calls();
+    assert_eq!(
+        (String::from("market"), String::from("get_epoch_state")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(GAS_FOR_EPOCH_STATE, function_calls[0].3);
+    assert_eq!(
+        (String::from("stable_coin"), String::from("ft_balance_of")),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+    assert_eq!("callback_execute_epoch_operations", function_calls[2].1);
+    assert_eq!(86400, function_calls[2].2["blocks"]);
+    assert!(function_calls[2].3 >= GAS_FOR_EPOCH_CALLBACK);
+
+    // the callback buys ANC and distributes interest within its gas
+    context.prepaid_gas(GAS_FOR_EPOCH_CALLBACK);
+    callback_context(&mut context, epoch_results(100_000));
+    contract.callback_execute_epoch_operations(AccountId::from("stable_coin"), 10);
+
+    let function_calls = created_function_calls();
+    assert_eq!(
+        (String::from("stable_coin"), String::from("ft_transfer")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("collector", function_calls[0].2["receiver_id"]);
+    assert_eq!("20000", function_calls[0].2["amount"]);
+    assert_eq!(
+        (
+            String::from("stable_coin"),
+            String::from("ft_transfer_call")
+        ),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+    assert_eq!("market", function_calls[1].2["receiver_id"]);
+    assert_eq!("\"distribute_interest\"", function_calls[1].2["msg"]);
+    assert_eq!(GAS_FOR_INTEREST_DISTRIBUTION, function_calls[1].3);
+    assert_eq!("get_epoch_state", function_calls[2].1);
+    assert_eq!("callback_update_epoch_state", function_calls[3].1);
+    assert_eq!(GAS_FOR_EPOCH_UPDATE, function_calls[3].3);
+
+    // both transfers attach the yocto required by the stable asset
+    let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+    assert_eq!(1, receipts[0]["actions"][0]["FunctionCall"]["deposit"]);
+    assert_eq!(1, receipts[1]["actions"][0]["FunctionCall"]["deposit"]);
+}
+
+#[test]
+fn update_epoch_state_of_reserve() {
+    let (mut context, mut contract) = setup_controller();
+    context.block_index(86400);
+    context.prepaid_gas(GAS_FOR_EPOCH_UPDATE);
+    callback_context(&mut context, epoch_results(0).into_iter().take(1).collect());
+
+    contract.callback_update_epoch_state(
+        AccountId::from("stable_coin"),
+        U128::from(80_000),
+        U128::from(0),
+        U128::from(20_000),
+        86400,
+        86400,
+    );
+
+    // the next epoch of the reserve runs once another period passed
+    assert_eq!(
+        86400,
+        contract
+            .get_reserve(AccountId::from("stable_coin"))
+            .last_executed_height
+    );
+    let function_calls = created_function_calls();
+    assert_eq!("execute_epoch_operations", function_calls[0].1);
+    assert_eq!(GAS_FOR_MARKET_EPOCH, function_calls[0].3);
+    assert_eq!(GAS_FOR_RECORD_EPOCH, function_calls[1].3);
+}
+
+#[test]
+#[should_panic(expected = "Epoch Not Passed")]
+fn execute_epoch_before_its_period() {
+    let (mut context, mut contract) = setup_controller();
+    testing_env!(context
+        .attached_deposit(1)
+        .block_index(86399)
+        .prepaid_gas(300_000_000_000_000)
+        .build());
+
+    contract.execute_epoch_operations(AccountId::from("stable_coin"));
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to execute the epoch")]
+fn execute_epoch_without_gas() {
+    let (mut context, mut contract) = setup_controller();
+    testing_env!(context
+        .attached_deposit(1)
+        .block_index(86400)
+        .prepaid_gas(GAS_FOR_EPOCH_CALLBACK)
+        .build());
+
+    contract.execute_epoch_operations(AccountId::from("stable_coin"));
+}
+
+#[test]
+fn epoch_with_buffer_below_previous() {
+    let (mut context, mut contract) = setup_controller();
+    let mut reserve_elem = contract.get_reserve(AccountId::from("stable_coin"));
+    reserve_elem.prev_interest_buffer = 200_000;
+    contract.add_reserve_elem_map(&AccountId::from("stable_coin"), &reserve_elem);
+    context.prepaid_gas(GAS_FOR_EPOCH_CALLBACK);
+    callback_context(&mut context, epoch_results(100_000));
+
+    contract.callback_execute_epoch_operations(AccountId::from("stable_coin"), 10);
+
+    // nothing accrued, so no ANC is purchased
+    let function_calls = created_function_calls();
+    assert!(function_calls
+        .iter()
+        .all(|function_call| function_call.2["receiver_id"] != "collector"));
+}
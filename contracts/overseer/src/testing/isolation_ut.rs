@@ -0,0 +1,165 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{register_storage, setup_contract};
+use crate::*;
+
+/// bNEAR is isolated under a debt ceiling of 300
+fn setup_isolation() -> (VMContextBuilder, Contract) {
+    let (context, mut contract) = setup_contract();
+    contract.update_debt_ceiling(AccountId::from("bnear"), Some(U128::from(300)));
+    register_storage(&mut contract, &["borrower"]);
+
+    (context, contract)
+}
+
+fn report_loan(
+    context: &mut VMContextBuilder,
+    contract: &mut Contract,
+    borrower: &str,
+    loan: u128,
+) {
+    // within the gas the market attaches to its report
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("market").unwrap())
+        .prepaid_gas(10_000_000_000_000)
+        .build());
+    let loans = if loan == 0 {
+        vec![]
+    } else {
+        vec![(AccountId::from("stable_coin"), loan)]
+    };
+    contract.update_isolated_debt(AccountId::from(borrower), loans);
+}
+
+#[test]
+#[should_panic(expected = "An isolated collateral cannot be combined with other collaterals")]
+fn lock_isolated_with_other_collateral() {
+    let (mut context, mut contract) = setup_isolation();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("weth"), 1)],
+    );
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 100)]);
+}
+
+#[test]
+fn lock_more_isolated_collateral() {
+    let (mut context, mut contract) = setup_isolation();
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 100)]);
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 20)]);
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 120)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+}
+
+#[test]
+fn borrow_limit_capped_by_debt_ceiling() {
+    let (mut context, mut contract) = setup_isolation();
+    for borrower in ["borrower", "borrower2"] {
+        contract.add_collateral_map(
+            &AccountId::from(borrower),
+            &vec![(AccountId::from("bnear"), 100)],
+        );
+    }
+    report_loan(&mut context, &mut contract, "borrower2", 200);
+
+    // the other borrowers left 100 of the ceiling
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(100)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+    // its own loans are not counted against it
+    assert_eq!(
+        (AccountId::from("borrower2"), U128::from(250)),
+        contract.get_borrow_limit(AccountId::from("borrower2"), None)
+    );
+
+    let whitelist = contract.get_whitelist(None, Some(1));
+    assert_eq!(Some(U128::from(300)), whitelist[0].debt_ceiling);
+    assert_eq!(U128::from(200), whitelist[0].isolated_debt);
+}
+
+#[test]
+fn repaid_loan_frees_debt_ceiling() {
+    let (mut context, mut contract) = setup_isolation();
+    for borrower in ["borrower", "borrower2"] {
+        contract.add_collateral_map(
+            &AccountId::from(borrower),
+            &vec![(AccountId::from("bnear"), 100)],
+        );
+    }
+    report_loan(&mut context, &mut contract, "borrower2", 300);
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(0)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+
+    report_loan(&mut context, &mut contract, "borrower2", 50);
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(250)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+
+    report_loan(&mut context, &mut contract, "borrower2", 0);
+    assert_eq!(0, contract.get_isolated_debt(&AccountId::from("bnear")));
+}
+
+#[test]
+fn lifted_debt_ceiling() {
+    let (mut context, mut contract) = setup_isolation();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+    report_loan(&mut context, &mut contract, "borrower2", 300);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    contract.update_debt_ceiling(AccountId::from("bnear"), None);
+
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(250)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+}
+
+#[test]
+fn loans_of_combined_collaterals_not_attributed() {
+    let (mut context, mut contract) = setup_isolation();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("weth"), 1)],
+    );
+    report_loan(&mut context, &mut contract, "borrower", 1000);
+
+    assert_eq!(0, contract.get_isolated_debt(&AccountId::from("bnear")));
+    assert!(contract
+        .collection
+        .borrower_isolated_debt_map
+        .get(&AccountId::from("borrower"))
+        .is_none());
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the market")]
+fn update_isolated_debt_from_someone() {
+    let (mut context, mut contract) = setup_isolation();
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.update_isolated_debt(AccountId::from("borrower"), vec![]);
+}
@@ -0,0 +1,440 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::{self, Value};
+use near_sdk::test_utils::{get_logs, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{callback_context, created_function_calls, loans_result, setup_contract};
+use crate::*;
+
+fn setup_borrower() -> (VMContextBuilder, Contract) {
+    let (context, mut contract) = setup_contract();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+
+    (context, contract)
+}
+
+#[test]
+fn liquidate_collateral_reads_loans() {
+    let (mut context, contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("liquidator").unwrap())
+        .build());
+
+    contract.liquidate_collateral(AccountId::from("borrower"));
+
+    let function_calls = created_function_calls();
+    assert_eq!(2, function_calls.len());
+    assert_eq!(
+        (String::from("market"), String::from("get_borrower_loans")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("liquidator", function_calls[1].2["liquidator"]);
+    assert_eq!("300", function_calls[1].2["liquidation_limit"].to_string());
+}
+
+#[test]
+#[should_panic(expected = "CannotLiquidationSafeLoan")]
+fn liquidate_safe_loan() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(&mut context, vec![loans_result(250)]);
+
+    contract.callback_liquidate_collateral(
+        AccountId::from("liquidator"),
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 100)],
+        250,
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+    );
+}
+
+#[test]
+fn liquidate_unsafe_loan() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(&mut context, vec![loans_result(300)]);
+
+    contract.callback_liquidate_collateral(
+        AccountId::from("liquidator"),
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 100)],
+        250,
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(
+        (
+            String::from("liquidation"),
+            String::from("get_liquidation_amount")
+        ),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("300", function_calls[0].2["borrow_amount"]);
+    assert_eq!("250", function_calls[0].2["borrow_limit"]);
+    assert_eq!(
+        serde_json::json!([["stable_coin", "300"]]),
+        function_calls[0].2["loan_values"]
+    );
+    assert_eq!("bnear", function_calls[0].2["max_ltvs"][0][0]);
+    assert_eq!("50000000", function_calls[0].2["max_ltvs"][0][1]["num"]);
+    assert_eq!(
+        (
+            String::from("overseer"),
+            String::from("callback_liquidate_collateral2")
+        ),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+}
+
+#[test]
+fn liquidate_loans_of_several_reserves() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.register_reserve(AccountId::from("dai"), 18);
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(
+            serde_json::to_vec(&vec![
+                (AccountId::from("stable_coin"), 100),
+                (AccountId::from("dai"), 200 * 10u128.pow(12)),
+            ])
+            .unwrap(),
+        )],
+    );
+
+    contract.callback_liquidate_collateral(
+        AccountId::from("liquidator"),
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 100)],
+        250,
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+    );
+
+    // the liquidation contract caps the sale at the loan in its own stable coin
+    let function_calls = created_function_calls();
+    assert_eq!("300", function_calls[0].2["borrow_amount"]);
+    assert_eq!(
+        serde_json::json!([["stable_coin", "100"], ["dai", "200"]]),
+        function_calls[0].2["loan_values"]
+    );
+}
+
+#[test]
+fn liquidate_collateral_through_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(b"[[\"bnear\", 40]]".to_vec())],
+    );
+
+    contract
+        .callback_liquidate_collateral2(AccountId::from("liquidator"), AccountId::from("borrower"));
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 60)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(1, function_calls.len());
+    assert_eq!(
+        (
+            String::from("custody_bnear"),
+            String::from("liquidate_collateral")
+        ),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("liquidator", function_calls[0].2["liquidator"]);
+    assert_eq!("borrower", function_calls[0].2["borrower"]);
+    assert_eq!(40, function_calls[0].2["amount"]);
+}
+
+#[test]
+fn restore_collateral_refused_by_liquidation() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("custody_bnear").unwrap())
+        .build());
+
+    contract.restore_collateral_from_custody(
+        AccountId::from("borrower"),
+        AccountId::from("bnear"),
+        U128::from(15),
+    );
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 115)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+    assert_eq!(115, contract.get_total_locked(&AccountId::from("bnear")));
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the custody of the collateral")]
+fn restore_collateral_from_other_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("custody_weth").unwrap())
+        .build());
+
+    contract.restore_collateral_from_custody(
+        AccountId::from("borrower"),
+        AccountId::from("bnear"),
+        U128::from(15),
+    );
+}
+
+#[test]
+fn liquidation_forwards_gas() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("liquidator").unwrap())
+        .build());
+
+    contract.liquidate_collateral(AccountId::from("borrower"));
+
+    let function_calls = created_function_calls();
+    assert_eq!(GAS_FOR_LOANS_QUERY, function_calls[0].3);
+    let callback_gas = function_calls[1].3;
+    assert!(callback_gas <= 300_000_000_000_000 - GAS_FOR_LOANS_QUERY - GAS_FOR_LIQUIDATION_STEP);
+    assert!(callback_gas > 250_000_000_000_000);
+
+    // every step runs with the gas the previous one attached
+    context.prepaid_gas(callback_gas);
+    callback_context(&mut context, vec![loans_result(300)]);
+    contract.callback_liquidate_collateral(
+        AccountId::from("liquidator"),
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 100)],
+        250,
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(GAS_FOR_LIQUIDATION_AMOUNT, function_calls[0].3);
+    let liquidation_gas = function_calls[1].3;
+    assert!(
+        liquidation_gas <= callback_gas - GAS_FOR_LIQUIDATION_STEP - GAS_FOR_LIQUIDATION_AMOUNT
+    );
+    assert!(liquidation_gas > 200_000_000_000_000);
+
+    context.prepaid_gas(liquidation_gas);
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(b"[[\"bnear\", 40]]".to_vec())],
+    );
+    contract
+        .callback_liquidate_collateral2(AccountId::from("liquidator"), AccountId::from("borrower"));
+
+    let function_calls = created_function_calls();
+    assert_eq!("liquidate_collateral", function_calls[0].1);
+    assert!(function_calls[0].3 <= liquidation_gas - GAS_FOR_LIQUIDATION_STEP);
+    assert!(function_calls[0].3 > 150_000_000_000_000);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to liquidate the collaterals")]
+fn liquidate_collateral_without_gas() {
+    let (mut context, contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("liquidator").unwrap())
+        .prepaid_gas(150_000_000_000_000)
+        .build());
+
+    contract.liquidate_collateral(AccountId::from("borrower"));
+}
+
+#[test]
+#[should_panic(expected = "Subtraction underflow")]
+fn liquidate_more_than_locked() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(b"[[\"bnear\", 101]]".to_vec())],
+    );
+
+    contract
+        .callback_liquidate_collateral2(AccountId::from("liquidator"), AccountId::from("borrower"));
+}
+
+#[test]
+#[should_panic(expected = "Price of bnear is too old")]
+fn liquidate_with_stale_price() {
+    let (mut context, contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("liquidator").unwrap())
+        .block_timestamp(61 * 1_000_000_000)
+        .build());
+
+    contract.liquidate_collateral(AccountId::from("borrower"));
+}
+
+#[test]
+fn liquidate_collaterals_reads_loans_once() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("keeper").unwrap())
+        .build());
+    contract.add_collateral_map(
+        &AccountId::from("borrower2"),
+        &vec![(AccountId::from("bnear"), 40)],
+    );
+
+    contract.liquidate_collaterals(vec![
+        AccountId::from("borrower"),
+        AccountId::from("borrower2"),
+    ]);
+
+    let function_calls = created_function_calls();
+    assert_eq!(2, function_calls.len());
+    assert_eq!(
+        (String::from("market"), String::from("get_borrowers_loans")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(
+        (
+            String::from("overseer"),
+            String::from("callback_liquidate_collaterals")
+        ),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+    assert_eq!("keeper", function_calls[1].2["liquidator"]);
+    // liquidation limits of 100 and 40 bnear at the liquidation LTV
+    assert_eq!("300", function_calls[1].2["positions"][0][2].to_string());
+    assert_eq!("120", function_calls[1].2["positions"][1][2].to_string());
+    assert_eq!(
+        1,
+        function_calls[1].2["collateral_prices"]
+            .as_array()
+            .unwrap()
+            .len()
+    );
+}
+
+#[test]
+#[should_panic(expected = "Batch size must be between 1 and 10")]
+fn liquidate_empty_batch() {
+    let (_, contract) = setup_borrower();
+
+    contract.liquidate_collaterals(vec![]);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to liquidate a batch")]
+fn liquidate_batch_without_gas() {
+    let (mut context, contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("keeper").unwrap())
+        .prepaid_gas(30_000_000_000_000)
+        .build());
+
+    contract.liquidate_collaterals(vec![AccountId::from("borrower")]);
+}
+
+#[test]
+fn liquidate_collaterals_within_gas() {
+    let (mut context, mut contract) = setup_borrower();
+    for borrower in ["safe", "borrower3", "borrower4"].iter() {
+        contract.add_collateral_map(
+            &AccountId::from(*borrower),
+            &vec![(AccountId::from("bnear"), 100)],
+        );
+    }
+    let borrowers = vec![
+        AccountId::from("borrower"),
+        AccountId::from("safe"),
+        AccountId::from("borrower3"),
+        AccountId::from("borrower4"),
+    ];
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("keeper").unwrap())
+        .build());
+
+    contract.liquidate_collaterals(borrowers.clone());
+
+    let function_calls = created_function_calls();
+    assert_eq!(GAS_FOR_LOANS_QUERY, function_calls[0].3);
+    let callback_gas = function_calls[1].3;
+    assert!(callback_gas <= 300_000_000_000_000 - GAS_FOR_LOANS_QUERY - GAS_FOR_LIQUIDATION_BATCH);
+
+    // the callback runs with the gas the batch attached to it
+    context.prepaid_gas(callback_gas);
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(b"[[\"borrower\", [[\"stable_coin\", 300]]], [\"safe\", [[\"stable_coin\", 50]]], [\"borrower3\", [[\"stable_coin\", 300]]], [\"borrower4\", [[\"stable_coin\", 300]]]]".to_vec())],
+    );
+    contract.callback_liquidate_collaterals(
+        AccountId::from("keeper"),
+        borrowers
+            .into_iter()
+            .map(|borrower| (borrower, vec![(AccountId::from("bnear"), 100)], 250))
+            .collect(),
+        vec![(AccountId::from("bnear"), D128::new_exp(5, 0))],
+    );
+
+    // every liquidation gets the gas of a sale by a single custody
+    let liquidation_gas = Contract::liquidation_gas(&vec![(AccountId::from("bnear"), 100)]);
+    let function_calls = created_function_calls();
+    assert_eq!(4, function_calls.len());
+    assert_eq!("get_liquidation_amount", function_calls[0].1);
+    assert_eq!("borrower", function_calls[1].2["borrower"]);
+    assert_eq!(
+        liquidation_gas - GAS_FOR_LIQUIDATION_AMOUNT,
+        function_calls[1].3
+    );
+    assert_eq!("borrower3", function_calls[3].2["borrower"]);
+    assert_eq!(
+        liquidation_gas - GAS_FOR_LIQUIDATION_AMOUNT,
+        function_calls[3].3
+    );
+
+    let statuses: Vec<(String, String)> = get_logs()
+        .iter()
+        .filter(|log| log.starts_with("EVENT_JSON:"))
+        .map(|log| {
+            let event: Value = serde_json::from_str(log.trim_start_matches("EVENT_JSON:")).unwrap();
+            (
+                event["data"][0]["borrower"].as_str().unwrap().to_string(),
+                event["data"][0]["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            (String::from("borrower"), String::from("started")),
+            (String::from("safe"), String::from("safe")),
+            (String::from("borrower3"), String::from("started")),
+            (String::from("borrower4"), String::from("skipped")),
+        ],
+        statuses
+    );
+}
+
+#[test]
+fn proper_liquidation_candidates() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(
+            b"[[\"borrower\", [[\"stable_coin\", 300]]], [\"borrower2\", [[\"stable_coin\", 50]]]]"
+                .to_vec(),
+        )],
+    );
+
+    contract.add_collateral_map(
+        &AccountId::from("borrower2"),
+        &vec![(AccountId::from("bnear"), 40)],
+    );
+
+    let candidates = contract.callback_get_liquidation_candidates(D128::new_exp(5, -1));
+
+    // 300 / 500 is above the threshold, 50 / 200 is not
+    assert_eq!(1, candidates.len());
+    assert_eq!(AccountId::from("borrower"), candidates[0].borrower);
+    assert_eq!(D128::new_exp(6, -1), candidates[0].ltv);
+}
@@ -0,0 +1,470 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{callback_context, created_function_calls, register_storage, setup_contract};
+use crate::*;
+
+fn setup_borrower() -> (VMContextBuilder, Contract) {
+    let (context, mut contract) = setup_contract();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+    register_storage(&mut contract, &["borrower"]);
+
+    (context, contract)
+}
+
+fn total_locked(contract: &Contract) -> Vec<(AccountId, U128)> {
+    contract
+        .get_whitelist(None, None)
+        .into_iter()
+        .map(|elem| (elem.collateral_token, elem.total_locked))
+        .collect()
+}
+
+#[test]
+fn lock_collateral_locks_the_deltas() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+
+    contract.lock_collateral(vec![
+        (AccountId::from("weth"), 1),
+        (AccountId::from("bnear"), 50),
+    ]);
+
+    assert_eq!(
+        vec![
+            (AccountId::from("bnear"), 150),
+            (AccountId::from("weth"), 1)
+        ],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(3, function_calls.len());
+    assert_eq!(
+        (
+            String::from("custody_weth"),
+            String::from("lock_collateral")
+        ),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(1, function_calls[0].2["amount"]);
+    assert_eq!(
+        (
+            String::from("custody_bnear"),
+            String::from("lock_collateral")
+        ),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+    assert_eq!(50, function_calls[1].2["amount"]);
+    assert_eq!(
+        (
+            String::from("overseer"),
+            String::from("callback_lock_collateral")
+        ),
+        (function_calls[2].0.clone(), function_calls[2].1.clone())
+    );
+}
+
+#[test]
+#[should_panic(expected = "No collaterals to lock")]
+fn lock_no_collateral() {
+    let (_, mut contract) = setup_borrower();
+
+    contract.lock_collateral(vec![]);
+}
+
+#[test]
+fn locks_accepted_by_every_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(
+        &mut context,
+        vec![
+            PromiseResult::Successful(vec![]),
+            PromiseResult::Successful(vec![]),
+        ],
+    );
+
+    contract.callback_lock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("weth"), 1), (AccountId::from("bnear"), 50)],
+        None,
+    );
+
+    assert!(created_function_calls().is_empty());
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 100)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+}
+
+#[test]
+fn lock_rejected_by_a_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    // state left by a lock of 1 weth and 50 bnear
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![
+            (AccountId::from("bnear"), 150),
+            (AccountId::from("weth"), 1),
+        ],
+    );
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(vec![]), PromiseResult::Failed],
+    );
+
+    contract.callback_lock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("weth"), 1), (AccountId::from("bnear"), 50)],
+        None,
+    );
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 100)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+    assert_eq!(
+        vec![
+            (AccountId::from("bnear"), U128::from(100)),
+            (AccountId::from("weth"), U128::from(0)),
+        ],
+        total_locked(&contract)
+    );
+
+    // the weth custody did lock, it releases the weth again
+    let function_calls = created_function_calls();
+    assert_eq!(1, function_calls.len());
+    assert_eq!(
+        (
+            String::from("custody_weth"),
+            String::from("unlock_collateral")
+        ),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(1, function_calls[0].2["amount"]);
+}
+
+#[test]
+fn lock_deposit_from_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("custody_bnear").unwrap())
+        .build());
+
+    contract.lock_collateral_from_custody(
+        AccountId::from("borrower"),
+        AccountId::from("bnear"),
+        U128::from(50),
+        Some((AccountId::from("stable_coin"), U128::from(30))),
+    );
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 150)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(2, function_calls.len());
+    assert_eq!(
+        (
+            String::from("custody_bnear"),
+            String::from("lock_collateral")
+        ),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(50, function_calls[0].2["amount"]);
+    assert_eq!("callback_lock_collateral", function_calls[1].1);
+    assert_eq!(
+        near_sdk::serde_json::json!(["stable_coin", "30"]),
+        function_calls[1].2["borrow"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the custody of the collateral")]
+fn lock_deposit_from_another_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("custody_weth").unwrap())
+        .build());
+
+    contract.lock_collateral_from_custody(
+        AccountId::from("borrower"),
+        AccountId::from("bnear"),
+        U128::from(50),
+        None,
+    );
+}
+
+#[test]
+fn borrow_once_locked() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(&mut context, vec![PromiseResult::Successful(vec![])]);
+
+    contract.callback_lock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 50)],
+        Some((AccountId::from("stable_coin"), U128::from(30))),
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(1, function_calls.len());
+    assert_eq!(
+        (String::from("market"), String::from("borrow_stable_for")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("borrower", function_calls[0].2["borrower"]);
+    assert_eq!("stable_coin", function_calls[0].2["stable_asset"]);
+    assert_eq!(30, function_calls[0].2["borrow_amount"]);
+    // the limit of the collaterals just locked spares the market a view of the overseer
+    let (_, borrow_limit) = contract.get_borrow_limit(AccountId::from("borrower"), None);
+    assert_eq!(
+        borrow_limit.0.to_string(),
+        function_calls[0].2["borrow_limit"]
+    );
+    assert_eq!(GAS_FOR_MARKET_BORROW, function_calls[0].3);
+}
+
+#[test]
+fn lock_deposit_and_borrow_within_forwarded_gas() {
+    let (mut context, mut contract) = setup_borrower();
+    // a deposit locks one collateral, then borrows
+    let lock_gas = 2 * GAS_FOR_LOCK_STEP + GAS_FOR_CUSTODY_LOCK + GAS_FOR_MARKET_BORROW;
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("custody_bnear").unwrap())
+        .prepaid_gas(lock_gas)
+        .build());
+
+    contract.lock_collateral_from_custody(
+        AccountId::from("borrower"),
+        AccountId::from("bnear"),
+        U128::from(50),
+        Some((AccountId::from("stable_coin"), U128::from(30))),
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(GAS_FOR_CUSTODY_LOCK, function_calls[0].3);
+    assert_eq!("callback_lock_collateral", function_calls[1].1);
+    assert_eq!(
+        GAS_FOR_LOCK_STEP + GAS_FOR_MARKET_BORROW,
+        function_calls[1].3
+    );
+
+    context.prepaid_gas(function_calls[1].3);
+    callback_context(&mut context, vec![PromiseResult::Successful(vec![])]);
+    contract.callback_lock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 50)],
+        Some((AccountId::from("stable_coin"), U128::from(30))),
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!("borrow_stable_for", function_calls[0].1);
+    assert_eq!(GAS_FOR_MARKET_BORROW, function_calls[0].3);
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to lock the collaterals")]
+fn lock_deposit_and_borrow_without_gas() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("custody_bnear").unwrap())
+        .prepaid_gas(GAS_FOR_LOCK_STEP + GAS_FOR_CUSTODY_LOCK + GAS_FOR_MARKET_BORROW)
+        .build());
+
+    contract.lock_collateral_from_custody(
+        AccountId::from("borrower"),
+        AccountId::from("bnear"),
+        U128::from(50),
+        Some((AccountId::from("stable_coin"), U128::from(30))),
+    );
+}
+
+#[test]
+fn no_borrow_when_the_lock_is_rejected() {
+    let (mut context, mut contract) = setup_borrower();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 150)],
+    );
+    callback_context(&mut context, vec![PromiseResult::Failed]);
+
+    contract.callback_lock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 50)],
+        Some((AccountId::from("stable_coin"), U128::from(30))),
+    );
+
+    assert!(created_function_calls().is_empty());
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 100)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+}
+
+#[test]
+fn unlock_collateral_unlocks_the_deltas() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(
+            b"[[\"stable_coin\", 100]]".to_vec(),
+        )],
+    );
+
+    contract.callback_unlock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 40)],
+        150,
+    );
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 60)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+
+    let function_calls = created_function_calls();
+    assert_eq!(2, function_calls.len());
+    assert_eq!(
+        (
+            String::from("custody_bnear"),
+            String::from("unlock_collateral")
+        ),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(40, function_calls[0].2["amount"]);
+    assert_eq!(
+        (
+            String::from("overseer"),
+            String::from("callback_unlock_collateral2")
+        ),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+}
+
+#[test]
+#[should_panic(expected = "UnlockTooLarge")]
+fn unlock_too_large() {
+    let (mut context, mut contract) = setup_borrower();
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Successful(
+            b"[[\"stable_coin\", 200]]".to_vec(),
+        )],
+    );
+
+    contract.callback_unlock_collateral(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 40)],
+        150,
+    );
+}
+
+#[test]
+fn unlock_rejected_by_a_custody() {
+    let (mut context, mut contract) = setup_borrower();
+    // state left by an unlock of 1 weth and 50 bnear
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 50)],
+    );
+    callback_context(
+        &mut context,
+        vec![PromiseResult::Failed, PromiseResult::Successful(vec![])],
+    );
+
+    contract.callback_unlock_collateral2(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("bnear"), 50), (AccountId::from("weth"), 1)],
+    );
+
+    // the bnear is still locked in its custody
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 100)],
+        contract.get_collateral_map(&AccountId::from("borrower"))
+    );
+}
+
+#[test]
+fn first_lock_of_registered_account() {
+    let (mut context, mut contract) = setup_borrower();
+    let min_balance = contract.storage_balance_bounds().min.0;
+    let collateral_balance = STORAGE_BYTES_PER_COLLATERAL as Balance * env::storage_byte_cost();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower2").unwrap())
+        .attached_deposit(min_balance)
+        .build());
+    contract.storage_deposit(None, None);
+
+    // the storage of the collateral is topped up before locking it
+    testing_env!(context.attached_deposit(collateral_balance).build());
+    let storage_balance = contract.storage_deposit(None, None);
+    assert_eq!(
+        U128::from(min_balance + collateral_balance),
+        storage_balance.total
+    );
+    assert_eq!(U128::from(collateral_balance), storage_balance.available);
+
+    testing_env!(context.attached_deposit(0).build());
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 50)]);
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 50)],
+        contract.get_collateral_map(&AccountId::from("borrower2"))
+    );
+    assert_eq!(
+        U128::from(0),
+        contract
+            .storage_balance_of(AccountId::from("borrower2"))
+            .unwrap()
+            .available
+    );
+}
+
+#[test]
+fn withdraw_storage_of_unlocked_collaterals() {
+    let (mut context, mut contract) = setup_borrower();
+    let used_balance = (STORAGE_BYTES_PER_ACCOUNT + STORAGE_BYTES_PER_COLLATERAL) as Balance
+        * env::storage_byte_cost();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .attached_deposit(1)
+        .build());
+
+    // only the storage of the bnear is kept
+    let storage_balance = contract.storage_withdraw(None);
+    assert_eq!(U128::from(used_balance), storage_balance.total);
+    assert_eq!(U128::from(0), storage_balance.available);
+}
+
+#[test]
+#[should_panic(expected = "The storage balance of borrower2 does not cover its entries")]
+fn lock_collateral_beyond_storage_balance() {
+    let (mut context, mut contract) = setup_borrower();
+    let min_balance = contract.storage_balance_bounds().min.0;
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower2").unwrap())
+        .attached_deposit(min_balance)
+        .build());
+    contract.storage_deposit(None, None);
+
+    testing_env!(context.attached_deposit(0).build());
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 50)]);
+}
+
+#[test]
+#[should_panic(expected = "The account borrower2 is not registered")]
+fn lock_collateral_of_unregistered_account() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower2").unwrap())
+        .build());
+
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 50)]);
+}
@@ -1 +1,183 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::{self, Value};
+use near_sdk::test_utils::{get_created_receipts, VMContextBuilder};
+use near_sdk::{testing_env, MockedBlockchain, RuntimeFeesConfig, VMConfig};
+
+use crate::*;
+
+pub mod category_ut;
+pub mod epoch_ut;
+pub mod isolation_ut;
+pub mod liquidation_ut;
+pub mod lock_ut;
+pub mod position_ut;
+pub mod price_ut;
 pub mod tests;
+pub mod upgrade_ut;
+pub mod whitelist_ut;
+
+/// The overseer as its owner creates it, without reserves or collaterals
+pub(crate) fn new_contract() -> (VMContextBuilder, Contract) {
+    let mut context = VMContextBuilder::new();
+    testing_env!(context
+        .current_account_id(ValidAccountId::try_from("overseer").unwrap())
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    let contract = Contract::new(
+        AccountId::from("owner"),
+        AccountId::from("oracle"),
+        AccountId::from("market"),
+        AccountId::from("liquidation"),
+        AccountId::from("collector"),
+        86400u64,
+        D128::new_exp(3, -3),
+        D128::new_exp(5, -3),
+        D128::new_exp(20, -2),
+        D128::new_exp(20, -2),
+        AccountId::from("oralce_payment_token"),
+        AccountId::from("requester"),
+        60,
+    );
+
+    (context, contract)
+}
+
+/// The overseer with the stable coin reserve, and bNEAR priced at 5 and wETH
+/// priced at 2000 whitelisted
+pub(crate) fn setup_contract() -> (VMContextBuilder, Contract) {
+    let (mut context, mut contract) = new_contract();
+    contract.register_reserve(AccountId::from("stable_coin"), 6);
+
+    register_collateral(
+        &mut context,
+        &mut contract,
+        "bNEAR",
+        D128::new_exp(5, -1),
+        D128::new_exp(6, -1),
+    );
+    register_collateral(
+        &mut context,
+        &mut contract,
+        "wETH",
+        D128::new_exp(8, -1),
+        D128::new_exp(85, -2),
+    );
+    set_price(&mut contract, "bnear", D128::new_exp(5, 0));
+    set_price(&mut contract, "weth", D128::new_exp(2000, 0));
+    testing_env!(context.build());
+
+    (context, contract)
+}
+
+/// Whitelists the collateral named `symbol`, its token is the lowercase symbol
+/// and is held by `custody_<token>`
+pub(crate) fn register_collateral(
+    context: &mut VMContextBuilder,
+    contract: &mut Contract,
+    symbol: &str,
+    max_ltv: D128,
+    liquidation_ltv: D128,
+) {
+    // every registration requests a price with its own prepaid gas
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    let collateral_token = symbol.to_lowercase();
+    contract.register_whitelist(
+        String::from(symbol),
+        String::from(symbol),
+        6,
+        collateral_token.clone(),
+        format!("custody_{}", collateral_token),
+        max_ltv,
+        liquidation_ltv,
+        PriceFeed {
+            end_point: String::from(
+                "https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd",
+            ),
+            source_path: String::from("near.usd"),
+        },
+    );
+}
+
+/// Stores a price of the asset reported at the genesis block
+pub(crate) fn set_price(contract: &mut Contract, asset: &str, price: D128) {
+    contract.collection.price_map.insert(
+        &AccountId::from(asset),
+        &PriceResponse {
+            price,
+            last_updated_at: 0,
+        },
+    );
+}
+
+/// Registers the borrowers with the storage of every collateral, they pay for
+/// it before locking
+pub(crate) fn register_storage(contract: &mut Contract, borrowers: &[&str]) {
+    let storage_balance = (STORAGE_BYTES_PER_ACCOUNT + 10 * STORAGE_BYTES_PER_COLLATERAL)
+        as Balance
+        * env::storage_byte_cost();
+    for borrower in borrowers {
+        contract
+            .collection
+            .storage_balance_map
+            .insert(&AccountId::from(*borrower), &storage_balance);
+    }
+}
+
+/// Collaterals locked before the collateral map became iterable
+pub(crate) fn insert_legacy_collaterals(
+    contract: &mut Contract,
+    borrower: &str,
+    collaterals: Tokens,
+) {
+    contract
+        .collection
+        .legacy_collateral_map
+        .insert(&AccountId::from(borrower), &collaterals);
+    contract.state.legacy_borrowers += 1;
+}
+
+/// Result of the market reporting a loan in the stable coin
+pub(crate) fn loans_result(loan: Balance) -> PromiseResult {
+    PromiseResult::Successful(
+        serde_json::to_vec(&vec![(AccountId::from("stable_coin"), loan)]).unwrap(),
+    )
+}
+
+/// Receiver, method, arguments and gas of every function call the contract made,
+/// the arguments are null for a call without any
+pub(crate) fn created_function_calls() -> Vec<(String, String, Value, Gas)> {
+    let receipts: Value = serde_json::to_value(get_created_receipts()).unwrap();
+
+    let mut function_calls = vec![];
+    for receipt in receipts.as_array().unwrap() {
+        for action in receipt["actions"].as_array().unwrap() {
+            let function_call = &action["FunctionCall"];
+            function_calls.push((
+                receipt["receiver_id"].as_str().unwrap().to_string(),
+                function_call["method_name"].as_str().unwrap().to_string(),
+                serde_json::from_str(function_call["args"].as_str().unwrap())
+                    .unwrap_or(Value::Null),
+                function_call["gas"].as_u64().unwrap(),
+            ));
+        }
+    }
+
+    function_calls
+}
+
+/// Runs the next call as a callback of the overseer receiving `results`
+pub(crate) fn callback_context(context: &mut VMContextBuilder, results: Vec<PromiseResult>) {
+    testing_env!(
+        context
+            .predecessor_account_id(ValidAccountId::try_from("overseer").unwrap())
+            .build(),
+        VMConfig::default(),
+        RuntimeFeesConfig::default(),
+        Default::default(),
+        results
+    );
+}
@@ -0,0 +1,271 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{
+    callback_context, created_function_calls, insert_legacy_collaterals, loans_result, set_price,
+    setup_contract,
+};
+use crate::*;
+
+fn setup_borrower() -> (VMContextBuilder, Contract) {
+    let (context, mut contract) = setup_contract();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![
+            (AccountId::from("bnear"), 100),
+            (AccountId::from("weth"), 2),
+        ],
+    );
+
+    (context, contract)
+}
+
+#[test]
+fn borrower_position_reads_loans() {
+    let (_, contract) = setup_borrower();
+
+    contract.get_borrower_position(AccountId::from("borrower"));
+
+    let function_calls = created_function_calls();
+    assert_eq!(2, function_calls.len());
+    assert_eq!(
+        (String::from("market"), String::from("get_borrower_loans")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!(
+        (
+            String::from("overseer"),
+            String::from("callback_get_borrower_position")
+        ),
+        (function_calls[1].0.clone(), function_calls[1].1.clone())
+    );
+}
+
+#[test]
+fn proper_borrower_position() {
+    let (mut context, contract) = setup_borrower();
+    callback_context(&mut context, vec![loans_result(3500)]);
+
+    let position = contract.callback_get_borrower_position(AccountId::from("borrower"));
+
+    // 100 * 5 * 0.5 + 2 * 2000 * 0.8
+    assert_eq!(U128::from(3450), position.borrow_limit);
+    assert_eq!(U128::from(4500), position.collateral_value);
+    assert_eq!(U128::from(3500), position.loan_value);
+    assert_eq!(D128::new(77_777_777), position.ltv);
+    // 100 * 5 * 0.6 + 2 * 2000 * 0.85
+    assert_eq!(U128::from(3700), position.liquidation_limit);
+    assert_eq!(Some(D128::new(105_714_285)), position.health_factor);
+    assert_eq!(
+        vec![
+            CollateralPosition {
+                collateral_token: AccountId::from("bnear"),
+                amount: U128::from(100),
+                price: D128::new_exp(5, 0),
+                value: U128::from(500),
+                max_ltv: D128::new_exp(5, -1),
+                liquidation_ltv: D128::new_exp(6, -1),
+                // (3500 - 3400) / (100 * 0.6)
+                liquidation_price: Some(D128::new(166_666_666)),
+            },
+            CollateralPosition {
+                collateral_token: AccountId::from("weth"),
+                amount: U128::from(2),
+                price: D128::new_exp(2000, 0),
+                value: U128::from(4000),
+                max_ltv: D128::new_exp(8, -1),
+                liquidation_ltv: D128::new_exp(85, -2),
+                // (3500 - 300) / (2 * 0.85)
+                liquidation_price: Some(D128::new(188_235_294_117)),
+            },
+        ],
+        position.collaterals
+    );
+}
+
+#[test]
+fn borrower_position_without_liquidation_price() {
+    let (_, contract) = setup_borrower();
+
+    // the weth alone covers the loan whatever the price of bnear
+    let position = contract.get_borrower_position_with_loans(
+        AccountId::from("borrower"),
+        vec![(AccountId::from("stable_coin"), 3000)],
+    );
+    assert_eq!(None, position.collaterals[0].liquidation_price);
+    // (3000 - 300) / (2 * 0.85)
+    assert_eq!(
+        Some(D128::new(158_823_529_411)),
+        position.collaterals[1].liquidation_price
+    );
+
+    let position = contract.get_borrower_position_with_loans(AccountId::from("borrower"), vec![]);
+    assert_eq!(None, position.health_factor);
+    assert_eq!(D128::zero(), position.ltv);
+}
+
+#[test]
+fn borrower_position_across_decimals() {
+    let (mut context, mut contract) = setup_borrower();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    contract.register_reserve(AccountId::from("dai"), 18);
+    testing_env!(context.build());
+    contract.register_whitelist(
+        String::from("wNEAR"),
+        String::from("wNEAR"),
+        24,
+        AccountId::from("wnear"),
+        AccountId::from("custody_wnear"),
+        D128::new_exp(5, -1),
+        D128::new_exp(6, -1),
+        PriceFeed {
+            end_point: String::from(
+                "https://api.coingecko.com/api/v3/simple/price?ids=near&vs_currencies=usd",
+            ),
+            source_path: String::from("near.usd"),
+        },
+    );
+    set_price(&mut contract, "wnear", D128::new_exp(5, 0));
+    contract.add_collateral_map(
+        &AccountId::from("borrower2"),
+        &vec![(AccountId::from("wnear"), 100 * 10u128.pow(24))],
+    );
+
+    let position = contract.get_borrower_position_with_loans(
+        AccountId::from("borrower2"),
+        vec![(AccountId::from("dai"), 150 * 10u128.pow(18))],
+    );
+
+    // values are in the price unit with 6 decimals, 100 wNEAR at 5 are worth 500
+    assert_eq!(U128::from(500_000_000), position.collateral_value);
+    assert_eq!(U128::from(250_000_000), position.borrow_limit);
+    assert_eq!(U128::from(300_000_000), position.liquidation_limit);
+    assert_eq!(U128::from(150_000_000), position.loan_value);
+    // 150 / (100 * 0.6)
+    assert_eq!(
+        Some(D128::new(250_000_000)),
+        position.collaterals[0].liquidation_price
+    );
+}
+
+#[test]
+fn borrower_position_without_collaterals() {
+    let (_, contract) = setup_borrower();
+
+    assert_eq!(
+        Vec::<(AccountId, Balance)>::new(),
+        contract.get_collateral_map(&AccountId::from("someone"))
+    );
+
+    let position = contract.get_borrower_position_with_loans(AccountId::from("someone"), vec![]);
+    assert_eq!(U128::from(0), position.borrow_limit);
+    assert!(position.collaterals.is_empty());
+}
+
+#[test]
+fn paginate_all_collaterals() {
+    let (_, mut contract) = setup_borrower();
+    contract.add_collateral_map(
+        &AccountId::from("borrower2"),
+        &vec![(AccountId::from("bnear"), 200)],
+    );
+    contract.add_collateral_map(
+        &AccountId::from("borrower3"),
+        &vec![(AccountId::from("weth"), 3)],
+    );
+
+    let page = contract.get_all_collaterals(None, Some(2));
+    assert_eq!(
+        vec![AccountId::from("borrower"), AccountId::from("borrower2")],
+        page.iter()
+            .map(|collaterals| collaterals.0.clone())
+            .collect::<Vec<AccountId>>()
+    );
+
+    assert_eq!(
+        vec![(
+            AccountId::from("borrower3"),
+            vec![(AccountId::from("weth"), 3)]
+        )],
+        contract.get_all_collaterals(Some(AccountId::from("borrower2")), Some(2))
+    );
+    assert!(contract
+        .get_all_collaterals(Some(AccountId::from("borrower3")), None)
+        .is_empty());
+
+    // borrowers who unlocked everything are no longer listed
+    contract.add_collateral_map(&AccountId::from("borrower2"), &vec![]);
+    assert_eq!(2, contract.get_all_collaterals(None, None).len());
+}
+
+#[test]
+fn page_collaterals_across_removals() {
+    let (_, mut contract) = setup_borrower();
+    for borrower in ["borrower_a", "borrower_b", "borrower_c", "borrower_d"].iter() {
+        contract.add_collateral_map(
+            &AccountId::from(*borrower),
+            &vec![(AccountId::from("bnear"), 100)],
+        );
+    }
+
+    let page = contract.get_all_collaterals(Some(AccountId::from("borrower")), Some(2));
+    assert_eq!(AccountId::from("borrower_b"), page[1].0);
+
+    // a borrower of the first page unlocks everything before the next page is read
+    contract.add_collateral_map(&AccountId::from("borrower_a"), &vec![]);
+    assert_eq!(
+        vec![AccountId::from("borrower_c"), AccountId::from("borrower_d")],
+        contract
+            .get_all_collaterals(Some(AccountId::from("borrower_b")), Some(2))
+            .iter()
+            .map(|collaterals| collaterals.0.clone())
+            .collect::<Vec<AccountId>>()
+    );
+
+    // the page resumes after a borrower who left the map
+    contract.add_collateral_map(&AccountId::from("borrower_b"), &vec![]);
+    assert_eq!(
+        2,
+        contract
+            .get_all_collaterals(Some(AccountId::from("borrower_b")), None)
+            .len()
+    );
+}
+
+#[test]
+fn index_legacy_collaterals() {
+    let (mut context, mut contract) = setup_borrower();
+    insert_legacy_collaterals(
+        &mut contract,
+        "legacy",
+        vec![(AccountId::from("bnear"), 50)],
+    );
+
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 50)],
+        contract.get_collateral_map(&AccountId::from("legacy"))
+    );
+    assert_eq!(1, contract.get_all_collaterals(None, None).len());
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+    contract.index_collaterals(vec![AccountId::from("legacy"), AccountId::from("unknown")]);
+
+    assert_eq!(
+        vec![(
+            AccountId::from("legacy"),
+            vec![(AccountId::from("bnear"), 50)]
+        )],
+        contract.get_all_collaterals(Some(AccountId::from("borrower")), None)
+    );
+    assert!(contract
+        .collection
+        .legacy_collateral_map
+        .get(&AccountId::from("legacy"))
+        .is_none());
+}
@@ -0,0 +1,126 @@
+use flux_sdk::consts::DR_NEW_GAS;
+use flux_sdk::DataRequestDetails;
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::serde_json::{self, json, Value};
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{callback_context, created_function_calls, setup_contract};
+use crate::*;
+
+/// The request of bNEAR as the requester contract keeps it
+fn bnear_request(status: Value) -> DataRequestDetails {
+    serde_json::from_value(json!({
+        "amount": "1000000000000000000000000",
+        "payload": {
+            "sources": [],
+            "tags": ["pricing", "bnear", "overseer/bnear"],
+            "description": null,
+            "outcomes": null,
+            "challenge_period": "120000000000",
+            "data_type": {"Number": "100000000"},
+            "creator": "overseer",
+        },
+        "tags": ["pricing", "bnear", "overseer/bnear"],
+        "status": status,
+        "creator": "overseer",
+        "has_withdrawn_validity_bond": false,
+    }))
+    .unwrap()
+}
+
+fn update_bnear_price(context: &mut VMContextBuilder, contract: &mut Contract) {
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("keeper").unwrap())
+        .attached_deposit(1)
+        .prepaid_gas(300_000_000_000_000)
+        .build());
+    contract.update_price(AccountId::from("bnear"));
+}
+
+#[test]
+fn update_price_stores_finalized_price() {
+    let (mut context, mut contract) = setup_contract();
+    update_bnear_price(&mut context, &mut contract);
+
+    let function_calls = created_function_calls();
+    assert_eq!(
+        (String::from("requester"), String::from("get_data_request")),
+        (function_calls[0].0.clone(), function_calls[0].1.clone())
+    );
+    assert_eq!("overseer/bnear", function_calls[0].2["request_id"]);
+    assert_eq!(GAS_FOR_PRICE_QUERY, function_calls[0].3);
+    assert_eq!("callback_get_price_response", function_calls[1].1);
+    assert!(function_calls[1].3 >= DR_NEW_GAS + GAS_FOR_PRICE_UPDATE);
+
+    context.block_timestamp(1_000);
+    context.prepaid_gas(function_calls[1].3);
+    callback_context(&mut context, vec![]);
+    contract.callback_get_price_response(
+        AccountId::from("bnear"),
+        Some(bnear_request(json!({
+            "Finalized": {"Answer": {"Number": {
+                "value": "700000000",
+                "multiplier": "100000000",
+                "negative": false,
+            }}}
+        }))),
+    );
+
+    let price = contract
+        .collection
+        .price_map
+        .get(&AccountId::from("bnear"))
+        .unwrap();
+    assert_eq!(D128::new_exp(7, 0), price.price);
+    assert_eq!(1_000, price.last_updated_at);
+
+    // the next request of bNEAR is paid to the requester contract
+    let function_calls = created_function_calls();
+    assert_eq!("oralce_payment_token", function_calls[0].0);
+    assert_eq!("ft_transfer_call", function_calls[0].1);
+    assert_eq!("requester", function_calls[0].2["receiver_id"]);
+    let msg: Value = serde_json::from_str(function_calls[0].2["msg"].as_str().unwrap()).unwrap();
+    assert_eq!(json!(["pricing", "bnear"]), msg["tags"]);
+}
+
+#[test]
+fn update_price_keeps_price_of_pending_request() {
+    let (mut context, mut contract) = setup_contract();
+
+    callback_context(&mut context, vec![]);
+    contract.callback_get_price_response(
+        AccountId::from("bnear"),
+        Some(bnear_request(json!("Pending"))),
+    );
+
+    let price = contract
+        .collection
+        .price_map
+        .get(&AccountId::from("bnear"))
+        .unwrap();
+    assert_eq!(D128::new_exp(5, 0), price.price);
+    assert!(created_function_calls().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Not enough gas to request the next price")]
+fn update_price_without_gas_for_next_request() {
+    let (mut context, mut contract) = setup_contract();
+
+    testing_env!(context.attached_deposit(1).prepaid_gas(DR_NEW_GAS).build());
+    contract.update_price(AccountId::from("bnear"));
+}
+
+#[test]
+fn update_price_requests_missing_price() {
+    let (mut context, mut contract) = setup_contract();
+
+    context.prepaid_gas(300_000_000_000_000);
+    callback_context(&mut context, vec![]);
+    contract.callback_get_price_response(AccountId::from("bnear"), None);
+
+    let function_calls = created_function_calls();
+    assert_eq!("oralce_payment_token", function_calls[0].0);
+    assert_eq!("ft_transfer_call", function_calls[0].1);
+}
@@ -1,28 +1,220 @@
-use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
 use near_sdk::{testing_env, MockedBlockchain};
 
+use super::{new_contract, register_collateral, set_price};
 use crate::*;
 
-fn setup_contract() -> (VMContextBuilder, Contract) {
-    let mut context = VMContextBuilder::new();
+#[test]
+fn proper_register_reserve() {
+    let (mut context, mut contract) = new_contract();
     testing_env!(context
-        .predecessor_account_id(accounts(0))
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
         .attached_deposit(1)
         .build());
-    let contract = Contract::new(
-        AccountId::from("owner"),
-        AccountId::from("stable_coin"),
-        AccountId::from("oracle"),
-        AccountId::from("market"),
-        AccountId::from("liquidation"),
-        AccountId::from("collector"),
-        86400u64,
-        D128::new_exp(3, -3),
-        D128::new_exp(5, -3),
-        D128::new_exp(20, -2),
-        D128::new_exp(20, -2),
-        AccountId::from("oralce_payment_token"),
-        AccountId::from("requester"),
-    );
-    (context, contract)
+
+    contract.register_reserve(AccountId::from("usdt"), 6);
+    contract.register_reserve(AccountId::from("usdc"), 6);
+
+    assert_eq!(2, contract.get_reserves().len());
+    assert_eq!(
+        D128::one(),
+        contract
+            .get_reserve(AccountId::from("usdc"))
+            .prev_exchange_rate
+    );
+
+    // loans of every reserve count towards the same borrow limit
+    assert_eq!(
+        U128::from(3000),
+        contract.get_loan_value(vec![
+            (AccountId::from("usdt"), 1000),
+            (AccountId::from("usdc"), 2000),
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Reserve already registered")]
+fn register_reserve_twice() {
+    let (mut context, mut contract) = new_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+
+    contract.register_reserve(AccountId::from("usdt"), 6);
+    contract.register_reserve(AccountId::from("usdt"), 6);
+}
+
+#[test]
+#[should_panic(expected = "Decimals must not exceed 24")]
+fn register_reserve_with_invalid_decimals() {
+    let (mut context, mut contract) = new_contract();
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+
+    contract.register_reserve(AccountId::from("usdt"), 25);
+}
+
+#[test]
+#[should_panic(expected = "Reserve not found")]
+fn loan_value_of_unknown_reserve() {
+    let (_, contract) = new_contract();
+
+    contract.get_loan_value(vec![(AccountId::from("usdt"), 1000)]);
+}
+
+/// bNEAR and wETH without any price reported yet
+fn register_collaterals(context: &mut VMContextBuilder, contract: &mut Contract) {
+    register_collateral(
+        context,
+        contract,
+        "bNEAR",
+        D128::new_exp(5, -1),
+        D128::new_exp(6, -1),
+    );
+    register_collateral(
+        context,
+        contract,
+        "wETH",
+        D128::new_exp(8, -1),
+        D128::new_exp(85, -2),
+    );
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .attached_deposit(1)
+        .build());
+}
+
+#[test]
+fn borrow_limit_with_collateral_prices() {
+    let (mut context, mut contract) = new_contract();
+    register_collaterals(&mut context, &mut contract);
+
+    set_price(&mut contract, "bnear", D128::new_exp(5, 0));
+    set_price(&mut contract, "weth", D128::new_exp(2000, 0));
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![
+            (AccountId::from("bnear"), 100),
+            (AccountId::from("weth"), 2),
+        ],
+    );
+
+    let (borrow_limit, collateral_prices) = contract.compute_borrow_limit(
+        &contract.get_collateral_map(&AccountId::from("borrower")),
+        &None,
+        None,
+    );
+
+    // 100 * 5 * 0.5 + 2 * 2000 * 0.8
+    assert_eq!(3450, borrow_limit);
+    assert_eq!(
+        vec![
+            (AccountId::from("bnear"), D128::new_exp(5, 0)),
+            (AccountId::from("weth"), D128::new_exp(2000, 0)),
+        ],
+        collateral_prices
+    );
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(3450)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+}
+
+#[test]
+fn collateral_prices_before_first_report() {
+    let (mut context, mut contract) = new_contract();
+    register_collaterals(&mut context, &mut contract);
+
+    set_price(&mut contract, "weth", D128::new_exp(2000, 0));
+
+    assert_eq!(
+        vec![
+            (AccountId::from("bnear"), None),
+            (
+                AccountId::from("weth"),
+                Some(PriceResponse {
+                    price: D128::new_exp(2000, 0),
+                    last_updated_at: 0,
+                })
+            ),
+        ],
+        contract.get_collateral_prices()
+    );
+}
+
+#[test]
+#[should_panic(expected = "No price for bnear")]
+fn borrow_limit_without_collateral_price() {
+    let (mut context, mut contract) = new_contract();
+    register_collaterals(&mut context, &mut contract);
+
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+
+    contract.get_borrow_limit(AccountId::from("borrower"), None);
+}
+
+#[test]
+fn proper_price_status() {
+    let (mut context, mut contract) = new_contract();
+    register_collaterals(&mut context, &mut contract);
+
+    contract.collection.price_map.insert(
+        &AccountId::from("bnear"),
+        &PriceResponse {
+            price: D128::new_exp(5, 0),
+            last_updated_at: 100 * 1_000_000_000,
+        },
+    );
+
+    testing_env!(context.block_timestamp(130 * 1_000_000_000).build());
+    assert_eq!(
+        PriceStatus {
+            price: D128::new_exp(5, 0),
+            last_updated_at: 100 * 1_000_000_000,
+            age: 30,
+            is_fresh: true,
+        },
+        contract.get_price_status(AccountId::from("bnear"))
+    );
+
+    testing_env!(context.block_timestamp(161 * 1_000_000_000).build());
+    assert_eq!(
+        false,
+        contract.get_price_status(AccountId::from("bnear")).is_fresh
+    );
+}
+
+#[test]
+#[should_panic(expected = "Price of bnear is too old")]
+fn borrow_limit_with_stale_price() {
+    let (mut context, mut contract) = new_contract();
+    register_collaterals(&mut context, &mut contract);
+
+    contract.collection.price_map.insert(
+        &AccountId::from("bnear"),
+        &PriceResponse {
+            price: D128::new_exp(5, 0),
+            last_updated_at: 100 * 1_000_000_000,
+        },
+    );
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+
+    // views without a block time still value the collateral
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(250)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+
+    contract.get_borrow_limit(AccountId::from("borrower"), Some(161 * 1_000_000_000));
 }
@@ -0,0 +1,170 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{insert_legacy_collaterals, new_contract};
+use crate::upgrade::{CollectionV0, ConfigV0, ContractV0, StateV0, WhitelistElemV0};
+use crate::*;
+
+/// The state of the deployed overseer, with bNEAR whitelisted and `legacy`
+/// holding 50 bNEAR
+fn write_v0_state(mut contract: Contract) {
+    let config = contract.config.clone();
+    insert_legacy_collaterals(
+        &mut contract,
+        "legacy",
+        vec![(AccountId::from("bnear"), 50)],
+    );
+
+    let mut white_list_elem_map: UnorderedMap<AccountId, WhitelistElemV0> =
+        UnorderedMap::new(StorageKey::WhitelistElem);
+    white_list_elem_map.insert(
+        &AccountId::from("bnear"),
+        &WhitelistElemV0 {
+            name: String::from("bNEAR"),
+            symbol: String::from("bNEAR"),
+            max_ltv: D128::new_exp(5, -1),
+            custody_contract: AccountId::from("custody_bnear"),
+        },
+    );
+
+    env::state_write(&ContractV0 {
+        config: ConfigV0 {
+            owner_id: config.owner_id.clone(),
+            stable_coin_contract: AccountId::from("stable_coin"),
+            oracle_contrract: config.oracle_contrract.clone(),
+            market_contract: config.market_contract.clone(),
+            liquidation_contract: config.liquidation_contract.clone(),
+            collector_contract: config.collector_contract.clone(),
+            epoch_period: config.epoch_period,
+            threshold_deposit_rate: config.threshold_deposit_rate,
+            target_deposit_rate: config.target_deposit_rate,
+            buffer_distribution_factor: config.buffer_distribution_factor,
+            anc_purchase_factor: config.anc_purchase_factor,
+            oracle_payment_token: config.oracle_payment_token.clone(),
+            requester_contract: config.requester_contract.clone(),
+        },
+        state: StateV0 {
+            deposit_rate: D128::new_exp(1, -6),
+            prev_stable_coin_total_supply: 5000,
+            prev_exchange_rate: D128::one(),
+            prev_interest_buffer: 300,
+            last_executed_height: 100,
+            last_price_response: PriceResponse {
+                price: D128::new_exp(5, 0),
+                last_updated_at: 0,
+            },
+        },
+        collection: CollectionV0 {
+            white_list_elem_map,
+            collateral_map: contract.collection.legacy_collateral_map,
+        },
+    });
+    env::storage_remove(b"STATE_VERSION");
+}
+
+#[test]
+fn proper_migrate_from_v0() {
+    let (mut context, contract) = new_contract();
+    let config = contract.config.clone();
+    write_v0_state(contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    let contract = Contract::migrate(Some(1));
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(1, contract.state.legacy_borrowers);
+    assert_eq!(
+        vec![(AccountId::from("bnear"), 50)],
+        contract.get_collateral_map(&AccountId::from("legacy"))
+    );
+    assert_eq!(config.owner_id, contract.config.owner_id);
+    assert_eq!(config.market_contract, contract.config.market_contract);
+    assert_eq!(config.epoch_period, contract.config.epoch_period);
+    assert_eq!(3600, contract.config.price_timeframe);
+    assert_eq!(100, contract.state.last_executed_height);
+    assert_eq!(
+        ReserveElem {
+            deposit_rate: D128::new_exp(1, -6),
+            prev_stable_coin_total_supply: 5000,
+            prev_exchange_rate: D128::one(),
+            prev_interest_buffer: 300,
+            target_deposit_rate: config.target_deposit_rate,
+            epoch_count: 0,
+            decimals: 6,
+            last_executed_height: 100,
+        },
+        contract.get_reserve(AccountId::from("stable_coin"))
+    );
+    assert_eq!(
+        U128::from(1000),
+        contract.get_loan_value(vec![(AccountId::from("stable_coin"), 1000)])
+    );
+
+    let white_list_elem = contract.get_white_list_elem_map(&AccountId::from("bnear"));
+    assert_eq!(D128::new_exp(5, -1), white_list_elem.max_ltv);
+    assert_eq!(D128::new_exp(5, -1), white_list_elem.liquidation_ltv);
+    assert_eq!(24, white_list_elem.decimals);
+    assert_eq!(WhitelistStatus::Active, white_list_elem.status);
+    assert_eq!("near.usd", white_list_elem.price_feed.source_path);
+    assert_eq!(
+        D128::new_exp(5, 0),
+        contract.get_price(AccountId::from("bnear")).price
+    );
+}
+
+#[test]
+#[should_panic(expected = "Can only be called by the owner")]
+fn migrate_only_owner() {
+    let (mut context, contract) = new_contract();
+    env::state_write(&contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("someone").unwrap())
+        .build());
+    Contract::migrate(None);
+}
+
+#[test]
+#[should_panic(expected = "The number of legacy borrowers is required")]
+fn migrate_from_v0_requires_legacy_borrowers() {
+    let (mut context, contract) = new_contract();
+    write_v0_state(contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    Contract::migrate(None);
+}
+
+#[test]
+fn legacy_borrowers_counted_by_owner_are_indexed() {
+    let (mut context, contract) = new_contract();
+    write_v0_state(contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    let mut contract = Contract::migrate(Some(1));
+
+    // indexing the only borrower of the V0 layout lets collaterals be removed again
+    contract.index_collaterals(vec![AccountId::from("legacy")]);
+    assert_eq!(0, contract.state.legacy_borrowers);
+    assert_eq!(50, contract.get_total_locked(&AccountId::from("bnear")));
+}
+
+#[test]
+fn migrate_current_state() {
+    let (mut context, mut contract) = new_contract();
+    contract.state.last_executed_height = 100;
+    env::state_write(&contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("owner").unwrap())
+        .build());
+    let contract = Contract::migrate(None);
+
+    assert_eq!(1, contract.get_state_version());
+    assert_eq!(100, contract.state.last_executed_height);
+}
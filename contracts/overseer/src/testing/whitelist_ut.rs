@@ -0,0 +1,283 @@
+use near_sdk::json_types::ValidAccountId;
+use near_sdk::test_utils::VMContextBuilder;
+use near_sdk::{testing_env, MockedBlockchain};
+
+use super::{insert_legacy_collaterals, register_storage, setup_contract};
+use crate::*;
+
+fn setup_borrowers() -> (VMContextBuilder, Contract) {
+    let (context, mut contract) = setup_contract();
+    register_storage(&mut contract, &["borrower", "borrower3"]);
+
+    (context, contract)
+}
+
+fn deprecate_bnear(contract: &mut Contract) {
+    contract.update_whitelist_status(
+        AccountId::from("bnear"),
+        WhitelistStatus::Deprecated {
+            max_ltv: D128::new_exp(2, -1),
+        },
+    );
+}
+
+#[test]
+#[should_panic(expected = "bnear cannot be locked")]
+fn lock_paused_collateral() {
+    let (mut context, mut contract) = setup_borrowers();
+    contract.update_whitelist_status(AccountId::from("bnear"), WhitelistStatus::LockPaused);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 100)]);
+}
+
+#[test]
+#[should_panic(expected = "bnear cannot be locked")]
+fn lock_deprecated_collateral() {
+    let (mut context, mut contract) = setup_borrowers();
+    deprecate_bnear(&mut contract);
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower").unwrap())
+        .build());
+    contract.lock_collateral(vec![(AccountId::from("bnear"), 100)]);
+}
+
+#[test]
+fn deprecated_collateral_reduces_borrow_limit() {
+    let (_, mut contract) = setup_borrowers();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+
+    contract.update_whitelist_status(AccountId::from("bnear"), WhitelistStatus::LockPaused);
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(250)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+
+    deprecate_bnear(&mut contract);
+    assert_eq!(
+        (AccountId::from("borrower"), U128::from(100)),
+        contract.get_borrow_limit(AccountId::from("borrower"), None)
+    );
+}
+
+#[test]
+#[should_panic(expected = "A deprecated collateral cannot have a higher LTV")]
+fn deprecate_with_higher_ltv() {
+    let (_, mut contract) = setup_borrowers();
+
+    contract.update_whitelist_status(
+        AccountId::from("bnear"),
+        WhitelistStatus::Deprecated {
+            max_ltv: D128::new_exp(6, -1),
+        },
+    );
+}
+
+#[test]
+fn whitelist_with_total_locked() {
+    let (mut context, mut contract) = setup_borrowers();
+    deprecate_bnear(&mut contract);
+
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+    contract.add_collateral_map(
+        &AccountId::from("borrower2"),
+        &vec![(AccountId::from("bnear"), 50), (AccountId::from("weth"), 1)],
+    );
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 30)],
+    );
+
+    testing_env!(context
+        .predecessor_account_id(ValidAccountId::try_from("borrower3").unwrap())
+        .build());
+    contract.lock_collateral(vec![(AccountId::from("weth"), 2)]);
+
+    assert_eq!(
+        vec![
+            WhitelistResponseElem {
+                collateral_token: AccountId::from("bnear"),
+                name: String::from("bNEAR"),
+                symbol: String::from("bNEAR"),
+                decimals: 6,
+                max_ltv: D128::new_exp(5, -1),
+                liquidation_ltv: D128::new_exp(6, -1),
+                custody_contract: AccountId::from("custody_bnear"),
+                status: WhitelistStatus::Deprecated {
+                    max_ltv: D128::new_exp(2, -1),
+                },
+                total_locked: U128::from(80),
+                debt_ceiling: None,
+                isolated_debt: U128::from(0),
+                category: None,
+            },
+            WhitelistResponseElem {
+                collateral_token: AccountId::from("weth"),
+                name: String::from("wETH"),
+                symbol: String::from("wETH"),
+                decimals: 6,
+                max_ltv: D128::new_exp(8, -1),
+                liquidation_ltv: D128::new_exp(85, -2),
+                custody_contract: AccountId::from("custody_weth"),
+                status: WhitelistStatus::Active,
+                total_locked: U128::from(3),
+                debt_ceiling: None,
+                isolated_debt: U128::from(0),
+                category: None,
+            },
+        ],
+        contract.get_whitelist(None, None)
+    );
+    assert_eq!(
+        1,
+        contract
+            .get_whitelist(Some(AccountId::from("bnear")), None)
+            .len()
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only a deprecated collateral can be removed")]
+fn remove_active_collateral() {
+    let (_, mut contract) = setup_borrowers();
+
+    contract.remove_whitelist(AccountId::from("bnear"));
+}
+
+#[test]
+#[should_panic(expected = "The collateral is still locked by borrowers")]
+fn remove_locked_collateral() {
+    let (_, mut contract) = setup_borrowers();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+    deprecate_bnear(&mut contract);
+
+    contract.remove_whitelist(AccountId::from("bnear"));
+}
+
+#[test]
+#[should_panic(expected = "Borrowers of the V0 layout must be indexed first")]
+fn remove_collateral_with_legacy_holder() {
+    let (_, mut contract) = setup_borrowers();
+    insert_legacy_collaterals(
+        &mut contract,
+        "legacy",
+        vec![(AccountId::from("bnear"), 50)],
+    );
+    deprecate_bnear(&mut contract);
+
+    contract.remove_whitelist(AccountId::from("bnear"));
+}
+
+#[test]
+fn remove_collateral_of_indexed_legacy_holder() {
+    let (_, mut contract) = setup_borrowers();
+    insert_legacy_collaterals(
+        &mut contract,
+        "legacy",
+        vec![(AccountId::from("bnear"), 50)],
+    );
+    deprecate_bnear(&mut contract);
+
+    contract.index_collaterals(vec![AccountId::from("legacy")]);
+    assert_eq!(0, contract.state.legacy_borrowers);
+    assert_eq!(50, contract.get_total_locked(&AccountId::from("bnear")));
+
+    contract.add_collateral_map(&AccountId::from("legacy"), &vec![]);
+    contract.remove_whitelist(AccountId::from("bnear"));
+    assert_eq!(1, contract.get_whitelist(None, None).len());
+}
+
+#[test]
+fn remove_unlocked_collateral() {
+    let (_, mut contract) = setup_borrowers();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+    deprecate_bnear(&mut contract);
+    contract.add_collateral_map(&AccountId::from("borrower"), &vec![]);
+
+    contract.remove_whitelist(AccountId::from("bnear"));
+
+    let whitelist = contract.get_whitelist(None, None);
+    assert_eq!(1, whitelist.len());
+    assert_eq!(AccountId::from("weth"), whitelist[0].collateral_token);
+    assert!(contract
+        .collection
+        .price_map
+        .get(&AccountId::from("bnear"))
+        .is_none());
+}
+
+#[test]
+#[should_panic(expected = "The liquidation LTV must be above the max LTV")]
+fn register_with_liquidation_ltv_below_max_ltv() {
+    let (mut context, mut contract) = setup_borrowers();
+    testing_env!(context.attached_deposit(1).build());
+
+    contract.register_whitelist(
+        String::from("USDT"),
+        String::from("USDT"),
+        6,
+        AccountId::from("usdt"),
+        AccountId::from("custody_usdt"),
+        D128::new_exp(9, -1),
+        D128::new_exp(9, -1),
+        PriceFeed {
+            end_point: String::from(
+                "https://api.coingecko.com/api/v3/simple/price?ids=tether&vs_currencies=usd",
+            ),
+            source_path: String::from("tether.usd"),
+        },
+    );
+}
+
+#[test]
+fn update_liquidation_ltv() {
+    let (mut context, mut contract) = setup_borrowers();
+    contract.add_collateral_map(
+        &AccountId::from("borrower"),
+        &vec![(AccountId::from("bnear"), 100)],
+    );
+
+    // the update requests the price responses of every collateral first
+    testing_env!(context.prepaid_gas(10u64.pow(15)).build());
+    contract.update_whitelist(
+        AccountId::from("bnear"),
+        None,
+        None,
+        Some(D128::new_exp(7, -1)),
+        None,
+    );
+
+    let position = contract.get_borrower_position_with_loans(AccountId::from("borrower"), vec![]);
+    assert_eq!(U128::from(250), position.borrow_limit);
+    assert_eq!(U128::from(350), position.liquidation_limit);
+}
+
+#[test]
+#[should_panic(expected = "The liquidation LTV must be above the max LTV")]
+fn update_max_ltv_above_liquidation_ltv() {
+    let (mut context, mut contract) = setup_borrowers();
+    testing_env!(context.prepaid_gas(10u64.pow(15)).build());
+
+    contract.update_whitelist(
+        AccountId::from("bnear"),
+        None,
+        Some(D128::new_exp(7, -1)),
+        None,
+        None,
+    );
+}
@@ -0,0 +1,262 @@
+use crate::*;
+
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+const CURRENT_STATE_VERSION: u8 = 1;
+
+const GAS_FOR_UPGRADE_CALL: Gas = 10_000_000_000_000;
+
+// unit: seconds
+const DEFAULT_PRICE_TIMEFRAME: u64 = 3600;
+
+/// Layouts the contract state has been persisted with. The version is kept
+/// under `STATE_VERSION_KEY`; the deployment made before it existed is `V0`,
+/// converted into the current layout by `migrate`.
+pub enum VersionedContract {
+    V0(ContractV0),
+    V1(Contract),
+}
+
+/// Stores the elements of a map in their next layout. There are a few whitelisted
+/// collaterals and reserves, they are keyed again under the same prefix
+fn convert_map<V, W>(
+    mut old_map: UnorderedMap<AccountId, V>,
+    storage_key: StorageKey,
+    convert: impl Fn(V) -> W,
+) -> UnorderedMap<AccountId, W>
+where
+    V: BorshSerialize + BorshDeserialize,
+    W: BorshSerialize + BorshDeserialize,
+{
+    let elems: Vec<(AccountId, V)> = old_map.to_vec();
+    old_map.clear();
+
+    let mut map = UnorderedMap::new(storage_key);
+    for (key, elem) in elems {
+        map.insert(&key, &convert(elem));
+    }
+    map
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ConfigV0 {
+    pub owner_id: AccountId,
+    pub stable_coin_contract: AccountId,
+    pub oracle_contrract: AccountId,
+    pub market_contract: AccountId,
+    pub liquidation_contract: AccountId,
+    pub collector_contract: AccountId,
+    pub epoch_period: BlockHeight,
+    pub threshold_deposit_rate: D128,
+    pub target_deposit_rate: D128,
+    pub buffer_distribution_factor: D128,
+    pub anc_purchase_factor: D128,
+    pub oracle_payment_token: AccountId,
+    pub requester_contract: AccountId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StateV0 {
+    pub deposit_rate: D128,
+    pub prev_stable_coin_total_supply: Balance,
+    pub prev_exchange_rate: D128,
+    pub prev_interest_buffer: u128,
+    pub last_executed_height: BlockHeight,
+    pub last_price_response: PriceResponse,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WhitelistElemV0 {
+    pub name: String,
+    pub symbol: String,
+    pub max_ltv: D128,
+    pub custody_contract: AccountId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CollectionV0 {
+    pub white_list_elem_map: UnorderedMap<AccountId, WhitelistElemV0>,
+    pub collateral_map: LookupMap<AccountId, Tokens>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV0 {
+    pub config: ConfigV0,
+    pub state: StateV0,
+    pub collection: CollectionV0,
+}
+
+impl From<ContractV0> for Contract {
+    fn from(old: ContractV0) -> Self {
+        let config = Config {
+            owner_id: old.config.owner_id,
+            oracle_contrract: old.config.oracle_contrract,
+            market_contract: old.config.market_contract,
+            liquidation_contract: old.config.liquidation_contract,
+            collector_contract: old.config.collector_contract,
+            epoch_period: old.config.epoch_period,
+            threshold_deposit_rate: old.config.threshold_deposit_rate,
+            target_deposit_rate: old.config.target_deposit_rate,
+            buffer_distribution_factor: old.config.buffer_distribution_factor,
+            anc_purchase_factor: old.config.anc_purchase_factor,
+            oracle_payment_token: old.config.oracle_payment_token,
+            requester_contract: old.config.requester_contract,
+            // the overseer never checked the age of its prices before
+            price_timeframe: DEFAULT_PRICE_TIMEFRAME,
+            deposit_rate_controller: None,
+        };
+
+        // bNEAR was the only collateral, valued with the NEAR/USD feed. Liquidations
+        // keep their previous trigger until the owner sets one
+        let price_feed = PriceFeed {
+            end_point: String::from(
+                "https://api.coingecko.com/api/v3/simple/price?ids=tether%2Cnear&vs_currencies=usd",
+            ),
+            source_path: String::from("near.usd"),
+        };
+        let white_list_elem_map = convert_map(
+            old.collection.white_list_elem_map,
+            StorageKey::WhitelistElem,
+            |elem| WhitelistElem {
+                name: elem.name,
+                symbol: elem.symbol,
+                decimals: 24,
+                max_ltv: elem.max_ltv,
+                liquidation_ltv: elem.max_ltv,
+                custody_contract: elem.custody_contract,
+                price_feed: price_feed.clone(),
+                status: WhitelistStatus::Active,
+                debt_ceiling: None,
+                category: None,
+            },
+        );
+
+        // the single stable coin of the old market, USDT, becomes its first reserve
+        let mut reserve_elem_map = UnorderedMap::new(StorageKey::ReserveElem);
+        reserve_elem_map.insert(
+            &old.config.stable_coin_contract,
+            &ReserveElem {
+                deposit_rate: old.state.deposit_rate,
+                prev_stable_coin_total_supply: old.state.prev_stable_coin_total_supply,
+                prev_exchange_rate: old.state.prev_exchange_rate,
+                prev_interest_buffer: old.state.prev_interest_buffer,
+                target_deposit_rate: config.target_deposit_rate,
+                epoch_count: 0,
+                decimals: 6,
+                last_executed_height: old.state.last_executed_height,
+            },
+        );
+
+        let mut price_map = LookupMap::new(StorageKey::Price);
+        price_map.insert(
+            &old.config.stable_coin_contract,
+            &PriceResponse {
+                price: D128::one(),
+                last_updated_at: env::block_timestamp(),
+            },
+        );
+        for collateral_token in white_list_elem_map.keys() {
+            price_map.insert(&collateral_token, &old.state.last_price_response);
+        }
+
+        Self {
+            config,
+            state: State {
+                last_executed_height: old.state.last_executed_height,
+                target_deposit_rate_adjustments: 0,
+                // counted by the owner when migrating, see `migrate`
+                legacy_borrowers: 0,
+            },
+            collection: Collection {
+                white_list_elem_map,
+                // a lookup map cannot be enumerated, borrowers are indexed as their
+                // collaterals change
+                collateral_map: TreeMap::new(StorageKey::CollateralIndex),
+                legacy_collateral_map: old.collection.collateral_map,
+                reserve_elem_map,
+                price_map,
+                target_deposit_rate_history: LookupMap::new(StorageKey::TargetDepositRateHistory),
+                epoch_history: LookupMap::new(StorageKey::EpochHistory),
+                // the totals are counted as the collaterals are indexed
+                total_locked_map: LookupMap::new(StorageKey::TotalLocked),
+                isolated_debt_map: LookupMap::new(StorageKey::IsolatedDebt),
+                borrower_isolated_debt_map: LookupMap::new(StorageKey::BorrowerIsolatedDebt),
+                category_map: UnorderedMap::new(StorageKey::Category),
+                borrower_category_map: LookupMap::new(StorageKey::BorrowerCategory),
+                // the borrowers of the old layout keep their collaterals without a registration
+                storage_balance_map: LookupMap::new(StorageKey::StorageBalance),
+            },
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_read_state_version() -> u8 {
+        env::storage_read(STATE_VERSION_KEY)
+            .map(|value| value[0])
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn internal_read_versioned_state() -> VersionedContract {
+        match Self::internal_read_state_version() {
+            0 => VersionedContract::V0(env::state_read().expect("Failed to read the V0 state")),
+            CURRENT_STATE_VERSION => {
+                VersionedContract::V1(env::state_read().expect("Failed to read the V1 state"))
+            }
+            _ => env::panic("Unknown state version".as_bytes()),
+        }
+    }
+
+    pub(crate) fn internal_write_state_version() {
+        env::storage_write(STATE_VERSION_KEY, &[CURRENT_STATE_VERSION]);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the stored state into the current layout. Called by `upgrade`
+    /// right after the new code is deployed, or by the owner directly.
+    /// The `V0` state keeps the collaterals in a lookup map, its borrowers cannot
+    /// be counted on chain. The owner counts the borrowers holding collaterals off
+    /// chain and migrates with that `legacy_borrowers`, deploying the code and
+    /// calling `migrate` in one transaction since `upgrade` passes no arguments.
+    /// No collateral can be removed until they are all indexed, an undercount
+    /// would let one go while some still hold it.
+    #[init(ignore_state)]
+    pub fn migrate(legacy_borrowers: Option<u64>) -> Self {
+        let contract: Contract = match Self::internal_read_versioned_state() {
+            VersionedContract::V0(old) => {
+                let mut contract: Contract = old.into();
+                contract.state.legacy_borrowers =
+                    legacy_borrowers.expect("The number of legacy borrowers is required");
+                contract
+            }
+            VersionedContract::V1(contract) => contract,
+        };
+
+        if env::predecessor_account_id() != env::current_account_id() {
+            contract.assert_owner();
+        }
+
+        Self::internal_write_state_version();
+
+        contract
+    }
+
+    /// Deploys the wasm blob attached as the call input and migrates the state.
+    pub fn upgrade(&self) -> Promise {
+        self.assert_owner();
+
+        let code = env::input().expect("Error: No code attached");
+        let migrate_gas = env::prepaid_gas()
+            .checked_sub(env::used_gas() + GAS_FOR_UPGRADE_CALL)
+            .expect("Not enough gas attached to migrate after the upgrade");
+
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(b"migrate".to_vec(), b"{}".to_vec(), NO_DEPOSIT, migrate_gas)
+    }
+
+    pub fn get_state_version(&self) -> u8 {
+        Self::internal_read_state_version()
+    }
+}
@@ -1,6 +1,6 @@
 use crate::*;
 
-use flux_sdk::consts::{DR_NEW_GAS, GAS_BASE_TRANSFER};
+use flux_sdk::consts::DR_NEW_GAS;
 use flux_sdk::{AnswerType, DataRequestDetails, Outcome, RequestStatus};
 
 #[ext_contract(fungible_token)]
@@ -27,75 +27,109 @@ pub trait FungibleToken {
 
 #[ext_contract(requester)]
 pub trait RequesterContract {
-    fn get_data_request(&self, request_id: ValidAccountId) -> Option<DataRequestDetails>;
+    fn get_data_request(&self, request_id: String) -> Option<DataRequestDetails>;
 }
 
 #[ext_contract(ext_self)]
 pub trait Contract {
-    fn callback_get_price_response(&mut self, #[callback] result: Option<DataRequestDetails>);
+    fn callback_get_price_response(
+        &mut self,
+        collateral_token: AccountId,
+        #[callback] result: Option<DataRequestDetails>,
+    );
+
+    fn callback_lock_collateral(
+        &mut self,
+        borrower: AccountId,
+        collaterals: Tokens,
+        borrow: Option<(AccountId, U128)>,
+    );
 
     fn callback_unlock_collateral(
-        &self,
+        &mut self,
         borrower: AccountId,
-        cur_collaterals: Tokens,
+        collaterals: Tokens,
         borrow_limit: u128,
     );
 
-    fn callback_liquidate_collateral(
-        &self,
+    fn callback_unlock_collateral2(&mut self, borrower: AccountId, collaterals: Tokens);
+
+    fn callback_update_borrower_category(
+        &mut self,
         borrower: AccountId,
-        cur_collaterals: Tokens,
+        category: Option<String>,
         borrow_limit: u128,
     );
 
-    fn callback_liquidate_collateral2(
+    fn callback_liquidate_collateral(
         &mut self,
-        sender: AccountId,
+        liquidator: AccountId,
         borrower: AccountId,
         cur_collaterals: Tokens,
-        prev_balance: Balance,
+        liquidation_limit: u128,
+        collateral_prices: Vec<(AccountId, D128)>,
     );
 
-    fn callback_execute_epoch_operations(&mut self, blocks: BlockHeight, interest_buffer: Balance);
+    fn callback_liquidate_collateral2(&mut self, liquidator: AccountId, borrower: AccountId);
+
+    fn callback_get_borrower_position(&self, borrower: AccountId) -> BorrowerPosition;
+
+    fn callback_liquidate_collaterals(
+        &mut self,
+        liquidator: AccountId,
+        positions: Vec<(AccountId, Tokens, u128)>,
+        collateral_prices: Vec<(AccountId, D128)>,
+    );
+
+    fn callback_get_liquidation_candidates(&self, min_ltv: D128) -> Vec<BorrowerPosition>;
+
+    fn callback_execute_epoch_operations(&mut self, stable_asset: AccountId, blocks: BlockHeight);
 
     fn callback_update_epoch_state(
         &mut self,
+        stable_asset: AccountId,
         intereset_buffer: U128,
         distributed_intereset: U128,
+        anc_purchase_amount: U128,
         block_height: BlockHeight,
         blocks: BlockHeight,
     );
+
+    fn callback_record_epoch(&mut self, stable_asset: AccountId, record: EpochRecord);
 }
 
 #[ext_contract(ext_market)]
 pub trait MarketContract {
-    fn get_borrower_info(
-        &mut self,
-        borrower: AccountId,
-        block_height: Option<BlockHeight>,
-    ) -> BorrowerInfo;
+    fn get_borrower_loans(&self, borrower: AccountId) -> Tokens;
 
-    fn get_balance(&self) -> Balance;
+    fn get_borrowers_loans(&self, borrowers: Vec<AccountId>) -> Vec<(AccountId, Tokens)>;
 
-    fn repay_stable_from_liquidation(&mut self, borrower: AccountId, prev_balance: Balance);
+    fn borrow_stable_for(
+        &mut self,
+        borrower: AccountId,
+        stable_asset: AccountId,
+        borrow_amount: Balance,
+        borrow_limit: U128,
+    );
 
     fn get_epoch_state(
         &mut self,
+        stable_asset: AccountId,
         block_height: Option<BlockHeight>,
         distributed_intereset: Option<U128>,
     ) -> Promise;
 
     fn execute_epoch_operations(
         &mut self,
+        stable_asset: AccountId,
         deposit_rate: D128,
         target_deposit_rate: D128,
         threshold_deposit_rate: D128,
-        distributed_intereset: U128,
-    );
+    ) -> D128;
 }
 
-#[ext_contract(ext_custody_bnear)]
-pub trait CustodyBnearContract {
+#[ext_contract(ext_custody)]
+pub trait CustodyContract {
     fn lock_collateral(&mut self, borrower: AccountId, amount: Balance);
 
     fn unlock_collateral(&mut self, borrower: AccountId, amount: Balance);
@@ -107,16 +141,15 @@ pub trait CustodyBnearContract {
 
 #[ext_contract(ext_liquidation)]
 pub trait LiquidationContract {
-    fn get_liquidation_amount();
-}
-
-#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(crate = "near_sdk::serde")]
-pub struct BorrowerInfo {
-    pub interest_index: D128,
-    pub reward_index: D128,
-    pub loan_amount: Balance,
-    pub pending_rewards: D128,
+    fn get_liquidation_amount(
+        &self,
+        borrow_amount: U128,
+        borrow_limit: U128,
+        loan_values: Vec<(AccountId, U128)>,
+        collaterals: Tokens,
+        collateral_prices: Vec<(AccountId, D128)>,
+        max_ltvs: Vec<(AccountId, D128)>,
+    ) -> Tokens;
 }
 
 pub fn fungible_token_transfer_call(
@@ -140,8 +173,19 @@ pub fn fungible_token_transfer_call(
 #[near_bindgen]
 impl Contract {
     #[private]
-    pub fn callback_get_price_response(&mut self, #[callback] result: Option<DataRequestDetails>) {
-        let result: DataRequestDetails = result.expect("ERR: There is no response.");
+    pub fn callback_get_price_response(
+        &mut self,
+        collateral_token: AccountId,
+        #[callback] result: Option<DataRequestDetails>,
+    ) {
+        // the first request, or one the requester stored before requests were kept per asset
+        let result: DataRequestDetails = match result {
+            Some(result) => result,
+            None => {
+                self.internal_create_new_price_request(&collateral_token);
+                return;
+            }
+        };
 
         let status: RequestStatus = result.status;
 
@@ -149,22 +193,78 @@ impl Contract {
             if let Outcome::Answer(answer_type) = outcome {
                 if let AnswerType::Number(number) = answer_type {
                     // store latest price response
-                    self.state.last_price_response = PriceResponse {
-                        price: D128::new(number.value.0),
-                        last_updated_at: env::block_timestamp(),
-                    };
+                    self.collection.price_map.insert(
+                        &collateral_token,
+                        &PriceResponse {
+                            price: D128::new(number.value.0),
+                            last_updated_at: env::block_timestamp(),
+                        },
+                    );
                     // create new price request
-                    self.internal_create_new_price_request();
+                    self.internal_create_new_price_request(&collateral_token);
                 }
             }
         }
     }
 
+    /// Rolls back the whole lock if any custody rejected its part, releasing
+    /// the collaterals the other custodies did lock. Borrows once every lock succeeded
+    #[private]
+    pub fn callback_lock_collateral(
+        &mut self,
+        borrower: AccountId,
+        collaterals: Tokens,
+        borrow: Option<(AccountId, U128)>,
+    ) {
+        assert_eq!(
+            env::promise_results_count(),
+            collaterals.len() as u64,
+            "This is a callback method"
+        );
+
+        let locked: Vec<bool> = (0..collaterals.len())
+            .map(|index| match env::promise_result(index as u64) {
+                PromiseResult::NotReady => unreachable!(),
+                PromiseResult::Failed => false,
+                PromiseResult::Successful(_) => true,
+            })
+            .collect();
+        if locked.iter().all(|locked| *locked) {
+            if let Some((stable_asset, borrow_amount)) = borrow {
+                // the market values the loans against the limit of the collaterals just locked
+                let (_, borrow_limit) =
+                    self.get_borrow_limit(borrower.clone(), Some(env::block_timestamp()));
+                ext_market::borrow_stable_for(
+                    borrower,
+                    stable_asset,
+                    borrow_amount.0,
+                    borrow_limit,
+                    &self.config.market_contract,
+                    NO_DEPOSIT,
+                    GAS_FOR_MARKET_BORROW,
+                );
+            }
+            return;
+        }
+
+        let mut cur_collaterals: Tokens = self.get_collateral_map(&borrower);
+        cur_collaterals.sub(collaterals.clone());
+        self.add_collateral_map(&borrower, &cur_collaterals);
+
+        for (collateral, locked) in collaterals.into_iter().zip(locked) {
+            if locked {
+                self.internal_custody_unlock(&borrower, &vec![collateral]);
+            } else {
+                env::log(format!("Custody rejected the lock of {}", collateral.0).as_bytes());
+            }
+        }
+    }
+
     #[private]
     pub fn callback_unlock_collateral(
         &mut self,
         borrower: AccountId,
-        cur_collaterals: Tokens,
+        collaterals: Tokens,
         borrow_limit: u128,
     ) {
         assert_eq!(env::promise_results_count(), 1, "This is a callback method");
@@ -175,38 +275,67 @@ impl Contract {
                 env::panic("fail".as_bytes());
             }
             PromiseResult::Successful(result) => {
-                let borrower_info: BorrowerInfo =
-                    near_sdk::serde_json::from_slice::<BorrowerInfo>(&result).unwrap();
-                if borrow_limit < borrower_info.loan_amount {
+                let loans: Tokens = near_sdk::serde_json::from_slice::<Tokens>(&result).unwrap();
+                if borrow_limit < self.compute_loan_value(&loans) {
                     env::panic("UnlockTooLarge".as_bytes());
                 }
 
+                let mut cur_collaterals: Tokens = self.get_collateral_map(&borrower);
+                cur_collaterals.sub(collaterals.clone());
                 self.add_collateral_map(&borrower, &cur_collaterals);
 
-                for collateral in cur_collaterals.clone() {
-                    let white_list_elem: WhitelistElem =
-                        self.get_white_list_elem_map(&collateral.0);
-
-                    ext_custody_bnear::unlock_collateral(
-                        borrower.clone(),
-                        collateral.1,
-                        &white_list_elem.custody_contract,
+                self.internal_custody_unlock(&borrower, &collaterals).then(
+                    ext_self::callback_unlock_collateral2(
+                        borrower,
+                        collaterals,
+                        &env::current_account_id(),
                         NO_DEPOSIT,
-                        SINGLE_CALL_GAS,
-                    );
-                }
+                        GAS_FOR_CUSTODY_LOCK,
+                    ),
+                );
             }
         }
     }
 
+    /// Gives the borrower back the collaterals whose custody rejected the unlock,
+    /// they are still locked there
     #[private]
-    pub fn callback_liquidate_collateral(
+    pub fn callback_unlock_collateral2(&mut self, borrower: AccountId, collaterals: Tokens) {
+        assert_eq!(
+            env::promise_results_count(),
+            collaterals.len() as u64,
+            "This is a callback method"
+        );
+
+        let rejected: Tokens = collaterals
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                matches!(env::promise_result(*index as u64), PromiseResult::Failed)
+            })
+            .map(|(_, collateral)| collateral)
+            .collect();
+        if rejected.is_empty() {
+            return;
+        }
+
+        for collateral in rejected.iter() {
+            env::log(format!("Custody rejected the unlock of {}", collateral.0).as_bytes());
+        }
+
+        let mut cur_collaterals: Tokens = self.get_collateral_map(&borrower);
+        cur_collaterals.add(rejected);
+        self.add_collateral_map(&borrower, &cur_collaterals);
+    }
+
+    #[private]
+    pub fn callback_update_borrower_category(
         &mut self,
         borrower: AccountId,
-        cur_collaterals: Tokens,
+        category: Option<String>,
         borrow_limit: u128,
     ) {
-        assert_eq!(env::promise_results_count(), 2, "This is a callback method");
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
         match env::promise_result(0) {
             PromiseResult::NotReady => unreachable!(),
@@ -214,36 +343,19 @@ impl Contract {
                 env::panic("fail".as_bytes());
             }
             PromiseResult::Successful(result) => {
-                let borrower_info: BorrowerInfo =
-                    near_sdk::serde_json::from_slice::<BorrowerInfo>(&result).unwrap();
+                let loans: Tokens = near_sdk::serde_json::from_slice::<Tokens>(&result).unwrap();
+                if borrow_limit < self.compute_loan_value(&loans) {
+                    env::panic("The loans exceed the borrow limit of the category".as_bytes());
+                }
 
-                match env::promise_result(1) {
-                    PromiseResult::NotReady => unreachable!(),
-                    PromiseResult::Failed => {
-                        env::panic("fail".as_bytes());
+                match category {
+                    Some(category) => {
+                        self.collection
+                            .borrower_category_map
+                            .insert(&borrower, &category);
                     }
-                    PromiseResult::Successful(result) => {
-                        let prev_balance: Balance =
-                            near_sdk::serde_json::from_slice::<Balance>(&result).unwrap();
-                        let borrow_amount = borrower_info.loan_amount;
-                        if borrow_limit >= borrow_amount {
-                            env::panic("CannotLiquidationSafeLoan".as_bytes());
-                        }
-
-                        ext_liquidation::get_liquidation_amount(
-                            &self.config.liquidation_contract,
-                            NO_DEPOSIT,
-                            SINGLE_CALL_GAS,
-                        )
-                        .then(ext_self::callback_liquidate_collateral2(
-                            env::predecessor_account_id(),
-                            borrower,
-                            cur_collaterals,
-                            prev_balance,
-                            &env::current_account_id(),
-                            NO_DEPOSIT,
-                            SINGLE_CALL_GAS,
-                        ));
+                    None => {
+                        self.collection.borrower_category_map.remove(&borrower);
                     }
                 }
             }
@@ -251,14 +363,15 @@ impl Contract {
     }
 
     #[private]
-    pub fn callback_liquidate_collateral2(
+    pub fn callback_liquidate_collateral(
         &mut self,
-        sender: AccountId,
+        liquidator: AccountId,
         borrower: AccountId,
         cur_collaterals: Tokens,
-        prev_balance: Balance,
+        liquidation_limit: u128,
+        collateral_prices: Vec<(AccountId, D128)>,
     ) {
-        assert_eq!(env::promise_results_count(), 2, "This is a callback method");
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
         match env::promise_result(0) {
             PromiseResult::NotReady => unreachable!(),
@@ -266,45 +379,90 @@ impl Contract {
                 env::panic("fail".as_bytes());
             }
             PromiseResult::Successful(result) => {
-                // let liquidation_amount: Tokens =
-                //     near_sdk::serde_json::from_slice::<Tokens>(&result).unwrap();
-                let liquidation_amount: Tokens = vec![(String::from(""), 0)]; // TODO: need to cross-contract call to liquidation contract
+                let loans: Tokens = near_sdk::serde_json::from_slice::<Tokens>(&result).unwrap();
+                let loan_value = self.compute_loan_value(&loans);
+                if liquidation_limit >= loan_value {
+                    env::panic("CannotLiquidationSafeLoan".as_bytes());
+                }
 
-                let mut latest_collarterals = cur_collaterals.clone();
+                let gas =
+                    env::prepaid_gas().saturating_sub(env::used_gas() + GAS_FOR_LIQUIDATION_STEP);
+                self.internal_start_liquidation(
+                    liquidator,
+                    borrower,
+                    cur_collaterals,
+                    liquidation_limit,
+                    &loans,
+                    collateral_prices,
+                    gas,
+                );
+            }
+        }
+    }
 
-                latest_collarterals.sub(liquidation_amount.clone());
-                self.add_collateral_map(&borrower, &latest_collarterals);
+    #[private]
+    pub fn callback_liquidate_collateral2(&mut self, liquidator: AccountId, borrower: AccountId) {
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
-                let liquidations = liquidation_amount.iter().map(|collateral| {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => {
+                env::panic("fail".as_bytes());
+            }
+            PromiseResult::Successful(result) => {
+                let mut liquidation_amount: Tokens =
+                    near_sdk::serde_json::from_slice::<Tokens>(&result).unwrap();
+                liquidation_amount.retain(|collateral| collateral.1 > 0);
+
+                // collaterals are read again, they may have changed since the liquidation began
+                let mut latest_collaterals: Tokens = self.get_collateral_map(&borrower);
+                latest_collaterals.sub(liquidation_amount.clone());
+                self.add_collateral_map(&borrower, &latest_collaterals);
+
+                // every custody sells its collateral to the liquidation contract, whose
+                // proceeds repay the loan of the borrower in the market
+                let custody_gas = env::prepaid_gas()
+                    .saturating_sub(env::used_gas() + GAS_FOR_LIQUIDATION_STEP)
+                    / std::cmp::max(liquidation_amount.len(), 1) as Gas;
+                for collateral in liquidation_amount {
                     let white_list_elem: WhitelistElem =
                         self.get_white_list_elem_map(&collateral.0);
 
-                    ext_custody_bnear::liquidate_collateral(
-                        sender.clone(),
+                    ext_custody::liquidate_collateral(
+                        liquidator.clone(),
                         borrower.clone(),
                         collateral.1,
                         &white_list_elem.custody_contract,
                         NO_DEPOSIT,
-                        SINGLE_CALL_GAS,
+                        custody_gas,
                     );
-                });
+                }
+            }
+        }
+    }
 
-                ext_market::repay_stable_from_liquidation(
-                    borrower.clone(),
-                    prev_balance,
-                    &self.config.market_contract,
-                    NO_DEPOSIT,
-                    SINGLE_CALL_GAS,
-                );
+    #[private]
+    pub fn callback_get_borrower_position(&self, borrower: AccountId) -> BorrowerPosition {
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => {
+                env::panic("fail".as_bytes());
+            }
+            PromiseResult::Successful(result) => {
+                let loans: Tokens = near_sdk::serde_json::from_slice::<Tokens>(&result).unwrap();
+                self.compute_borrower_position(borrower, loans)
             }
         }
     }
 
     #[private]
-    pub fn callback_execute_epoch_operations(
+    pub fn callback_liquidate_collaterals(
         &mut self,
-        blocks: BlockHeight,
-        interest_buffer: Balance,
+        liquidator: AccountId,
+        positions: Vec<(AccountId, Tokens, u128)>,
+        collateral_prices: Vec<(AccountId, D128)>,
     ) {
         assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
@@ -314,78 +472,177 @@ impl Contract {
                 env::panic("fail".as_bytes());
             }
             PromiseResult::Successful(result) => {
-                let (exchange_rate, raw_stable_coin_total_supply): (D128, U128) =
-                    near_sdk::serde_json::from_slice::<(D128, U128)>(&result).unwrap();
-                let stable_coin_total_supply = raw_stable_coin_total_supply.0;
+                let borrowers_loans: Vec<(AccountId, Tokens)> =
+                    near_sdk::serde_json::from_slice::<Vec<(AccountId, Tokens)>>(&result).unwrap();
+
+                for ((borrower, collaterals, liquidation_limit), (_, loans)) in
+                    positions.into_iter().zip(borrowers_loans)
+                {
+                    let loan_value = self.compute_loan_value(&loans);
+                    let liquidation_gas = Self::liquidation_gas(&collaterals);
+                    let status = if liquidation_limit >= loan_value {
+                        LiquidationStatus::Safe
+                    } else if env::prepaid_gas()
+                        .saturating_sub(env::used_gas() + GAS_FOR_LIQUIDATION_STEP)
+                        < liquidation_gas
+                    {
+                        LiquidationStatus::Skipped
+                    } else {
+                        let prices = collateral_prices
+                            .iter()
+                            .filter(|collateral_price| {
+                                collaterals
+                                    .iter()
+                                    .any(|collateral| collateral.0 == collateral_price.0)
+                            })
+                            .cloned()
+                            .collect();
+                        self.internal_start_liquidation(
+                            liquidator.clone(),
+                            borrower.clone(),
+                            collaterals,
+                            liquidation_limit,
+                            &loans,
+                            prices,
+                            liquidation_gas,
+                        );
+                        LiquidationStatus::Started
+                    };
 
-                let effective_deposit_rate = exchange_rate / self.state.prev_exchange_rate;
-                let deposit_rate = (effective_deposit_rate - D128::one()) / blocks as u128;
+                    self.internal_log_liquidation(&borrower, status);
+                }
+            }
+        }
+    }
 
-                let accrued_buffer: u128 = interest_buffer - self.state.prev_interest_buffer;
-                let anc_purchase_amount: Balance =
-                    (accrued_buffer * self.config.anc_purchase_factor).as_u128();
+    #[private]
+    pub fn callback_get_liquidation_candidates(&self, min_ltv: D128) -> Vec<BorrowerPosition> {
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
 
-                if anc_purchase_amount != 0 {
-                    fungible_token::ft_transfer(
-                        self.config.collector_contract.clone(),
-                        anc_purchase_amount.into(),
-                        None,
-                        &self.config.stable_coin_contract,
-                        NO_DEPOSIT,
-                        SINGLE_CALL_GAS,
-                    );
-                }
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => {
+                env::panic("fail".as_bytes());
+            }
+            PromiseResult::Successful(result) => {
+                let borrowers_loans: Vec<(AccountId, Tokens)> =
+                    near_sdk::serde_json::from_slice::<Vec<(AccountId, Tokens)>>(&result).unwrap();
+
+                borrowers_loans
+                    .into_iter()
+                    .map(|(borrower, loans)| self.compute_borrower_position(borrower, loans))
+                    .filter(|position| position.ltv > min_ltv)
+                    .collect()
+            }
+        }
+    }
 
-                let mut new_interest_buffer = interest_buffer - anc_purchase_amount;
-
-                let mut distributed_intereset: u128 = 0;
-
-                if deposit_rate < self.config.threshold_deposit_rate {
-                    let missing_deposit_rate = self.config.threshold_deposit_rate - deposit_rate;
-                    let prev_deposits =
-                        self.state.prev_stable_coin_total_supply * self.state.prev_exchange_rate;
-
-                    let missing_deposits =
-                        (prev_deposits * missing_deposit_rate).mul_int(blocks as u128);
-                    let distribution_buffer = self
-                        .config
-                        .buffer_distribution_factor
-                        .mul_int(interest_buffer);
-
-                    distributed_intereset = std::cmp::min(missing_deposits, distribution_buffer);
-                    new_interest_buffer = interest_buffer - distributed_intereset;
-
-                    if distributed_intereset != 0 {
-                        // TODO should be deduct_tax?
-                        fungible_token::ft_transfer(
-                            self.config.market_contract.clone(),
-                            distributed_intereset.into(),
-                            None,
-                            &self.config.stable_coin_contract,
-                            NO_DEPOSIT,
-                            SINGLE_CALL_GAS,
-                        );
-                    }
-                }
+    #[private]
+    pub fn callback_execute_epoch_operations(
+        &mut self,
+        stable_asset: AccountId,
+        blocks: BlockHeight,
+    ) {
+        assert_eq!(env::promise_results_count(), 2, "This is a callback method");
 
-                for elem in self.collection.white_list_elem_map.iter() {
-                    ext_custody_bnear::distribute_rewards(
-                        &elem.1.custody_contract,
-                        NO_DEPOSIT,
-                        SINGLE_CALL_GAS,
-                    );
-                }
+        let (exchange_rate, _) = match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => {
+                env::panic("fail".as_bytes());
+            }
+            PromiseResult::Successful(result) => {
+                near_sdk::serde_json::from_slice::<(D128, U128)>(&result).unwrap()
+            }
+        };
 
-                self.update_epoch_state(new_interest_buffer.into(), distributed_intereset.into());
+        let interest_buffer: Balance = match env::promise_result(1) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => {
+                env::panic("fail".as_bytes());
+            }
+            PromiseResult::Successful(result) => {
+                near_sdk::serde_json::from_slice::<U128>(&result).unwrap().0
+            }
+        };
+
+        let reserve_elem: ReserveElem = self.get_reserve_elem_map(&stable_asset);
+
+        let effective_deposit_rate = exchange_rate / reserve_elem.prev_exchange_rate;
+        let deposit_rate = (effective_deposit_rate - D128::one()) / blocks as u128;
+
+        // a balance below the previous buffer accrued nothing
+        let accrued_buffer: u128 =
+            interest_buffer.saturating_sub(reserve_elem.prev_interest_buffer);
+        let anc_purchase_amount: Balance =
+            (accrued_buffer * self.config.anc_purchase_factor).as_u128();
+
+        if anc_purchase_amount != 0 {
+            fungible_token::ft_transfer(
+                self.config.collector_contract.clone(),
+                anc_purchase_amount.into(),
+                None,
+                &stable_asset,
+                1,
+                GAS_FOR_FT_TRANSFER,
+            );
+        }
+
+        let mut new_interest_buffer = interest_buffer - anc_purchase_amount;
+
+        let mut distributed_intereset: u128 = 0;
+
+        if deposit_rate < self.config.threshold_deposit_rate {
+            let missing_deposit_rate = self.config.threshold_deposit_rate - deposit_rate;
+            let prev_deposits =
+                reserve_elem.prev_stable_coin_total_supply * reserve_elem.prev_exchange_rate;
+
+            let missing_deposits = (prev_deposits * missing_deposit_rate).mul_int(blocks as u128);
+            let distribution_buffer = self
+                .config
+                .buffer_distribution_factor
+                .mul_int(interest_buffer);
+
+            distributed_intereset = std::cmp::min(missing_deposits, distribution_buffer);
+            new_interest_buffer = interest_buffer - distributed_intereset;
+
+            if distributed_intereset != 0 {
+                // the market credits its reserve once the interest arrives
+                fungible_token::ft_transfer_call(
+                    self.config.market_contract.clone(),
+                    distributed_intereset.into(),
+                    None,
+                    String::from("\"distribute_interest\""),
+                    &stable_asset,
+                    1,
+                    GAS_FOR_INTEREST_DISTRIBUTION,
+                );
             }
         }
+
+        self.internal_adjust_target_deposit_rate(
+            &stable_asset,
+            reserve_elem,
+            deposit_rate,
+            accrued_buffer,
+            new_interest_buffer,
+            blocks,
+        );
+
+        self.internal_update_epoch_state(
+            stable_asset,
+            new_interest_buffer.into(),
+            distributed_intereset.into(),
+            anc_purchase_amount.into(),
+        );
     }
 
     #[private]
     pub fn callback_update_epoch_state(
         &mut self,
+        stable_asset: AccountId,
         intereset_buffer: U128,
         distributed_intereset: U128,
+        anc_purchase_amount: U128,
         block_height: BlockHeight,
         blocks: BlockHeight,
     ) {
@@ -400,23 +657,67 @@ impl Contract {
                 let (exchange_rate, stable_coin_total_supply): (D128, U128) =
                     near_sdk::serde_json::from_slice::<(D128, U128)>(&result).unwrap();
 
-                let effective_deposit_rate = exchange_rate / self.state.prev_exchange_rate;
+                let mut reserve_elem: ReserveElem = self.get_reserve_elem_map(&stable_asset);
+
+                let effective_deposit_rate = exchange_rate / reserve_elem.prev_exchange_rate;
                 let deposit_rate = (effective_deposit_rate - D128::one()) / blocks as u128;
 
                 self.state.last_executed_height = block_height;
-                self.state.prev_stable_coin_total_supply = stable_coin_total_supply.0;
-                self.state.prev_exchange_rate = exchange_rate;
-                self.state.prev_interest_buffer = intereset_buffer.0;
-                self.state.deposit_rate = deposit_rate;
+                reserve_elem.last_executed_height = block_height;
+                reserve_elem.prev_stable_coin_total_supply = stable_coin_total_supply.0;
+                reserve_elem.prev_exchange_rate = exchange_rate;
+                reserve_elem.prev_interest_buffer = intereset_buffer.0;
+                reserve_elem.deposit_rate = deposit_rate;
+                self.add_reserve_elem_map(&stable_asset, &reserve_elem);
 
                 ext_market::execute_epoch_operations(
+                    stable_asset.clone(),
                     deposit_rate,
-                    self.config.target_deposit_rate,
+                    reserve_elem.target_deposit_rate,
                     self.config.threshold_deposit_rate,
-                    distributed_intereset,
                     &self.config.market_contract,
                     NO_DEPOSIT,
-                    SINGLE_CALL_GAS,
+                    GAS_FOR_MARKET_EPOCH,
+                )
+                .then(ext_self::callback_record_epoch(
+                    stable_asset,
+                    EpochRecord {
+                        block_height,
+                        exchange_rate,
+                        deposit_rate,
+                        interest_buffer: intereset_buffer,
+                        anc_purchase_amount,
+                        distributed_interest: distributed_intereset,
+                        // known once the market executed the epoch
+                        anc_emission_rate: D128::zero(),
+                    },
+                    &env::current_account_id(),
+                    NO_DEPOSIT,
+                    GAS_FOR_RECORD_EPOCH,
+                ));
+            }
+        }
+    }
+
+    #[private]
+    pub fn callback_record_epoch(&mut self, stable_asset: AccountId, record: EpochRecord) {
+        assert_eq!(env::promise_results_count(), 1, "This is a callback method");
+
+        match env::promise_result(0) {
+            PromiseResult::NotReady => unreachable!(),
+            PromiseResult::Failed => {
+                env::panic("fail".as_bytes());
+            }
+            PromiseResult::Successful(result) => {
+                let anc_emission_rate: D128 =
+                    near_sdk::serde_json::from_slice::<D128>(&result).unwrap();
+
+                self.internal_push_epoch_record(
+                    &stable_asset,
+                    &EpochRecord {
+                        anc_emission_rate,
+                        ..record
+                    },
                 );
             }
         }
@@ -1,5 +1,8 @@
 use crate::*;
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 #[near_bindgen]
 impl Contract {
     pub fn get_config(&self) -> Config {
@@ -10,8 +13,35 @@ impl Contract {
         self.state.clone()
     }
 
-    pub fn get_target_deposit_rate(&self) -> D128 {
-        self.config.target_deposit_rate
+    pub fn get_target_deposit_rate(&self, stable_asset: AccountId) -> D128 {
+        self.get_reserve_elem_map(&stable_asset).target_deposit_rate
+    }
+
+    /// Latest target deposit rate adjustments with their number, oldest first
+    pub fn get_target_deposit_rate_history(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<(u64, TargetDepositRateAdjustment)> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+        let count = self.state.target_deposit_rate_adjustments;
+
+        let oldest = count.saturating_sub(TARGET_DEPOSIT_RATE_HISTORY_SIZE);
+        let start = match start_after {
+            Some(start_after) => std::cmp::max(start_after + 1, oldest),
+            None => oldest,
+        };
+
+        (start..std::cmp::min(start + limit, count))
+            .map(|index| {
+                let adjustment = self
+                    .collection
+                    .target_deposit_rate_history
+                    .get(&(index % TARGET_DEPOSIT_RATE_HISTORY_SIZE))
+                    .unwrap();
+                (index, adjustment)
+            })
+            .collect()
     }
 
     pub fn get_borrow_limit(
@@ -21,8 +51,177 @@ impl Contract {
     ) -> (AccountId, U128) {
         let collaterals = self.get_collateral_map(&borrower);
 
-        let (borrow_limit, _) = self.compute_borrow_limit(&collaterals, block_time);
+        let category = self.get_borrower_category(&borrower);
+        let (borrow_limit, _) = self.compute_borrow_limit(&collaterals, &category, block_time);
+        let borrow_limit = self.cap_isolated_borrow_limit(&borrower, &collaterals, borrow_limit);
 
         (borrower, U128::from(borrow_limit))
     }
+
+    pub fn get_loan_value(&self, loans: Tokens) -> U128 {
+        U128::from(self.compute_loan_value(&loans))
+    }
+
+    pub fn get_reserve(&self, stable_asset: AccountId) -> ReserveElem {
+        self.get_reserve_elem_map(&stable_asset)
+    }
+
+    pub fn get_reserves(&self) -> Vec<(AccountId, ReserveElem)> {
+        self.collection.reserve_elem_map.to_vec()
+    }
+
+    pub fn get_categories(&self) -> Vec<(String, AssetCategory)> {
+        self.collection.category_map.to_vec()
+    }
+
+    pub fn get_price(&self, asset: AccountId) -> PriceResponse {
+        self.get_price_map(&asset)
+    }
+
+    /// prices the borrow limits are computed with, per whitelisted collateral
+    pub fn get_collateral_prices(&self) -> Vec<(AccountId, Option<PriceResponse>)> {
+        self.collection
+            .white_list_elem_map
+            .keys()
+            .map(|collateral_token| {
+                let price = self.collection.price_map.get(&collateral_token);
+                (collateral_token, price)
+            })
+            .collect()
+    }
+
+    pub fn get_price_status(&self, asset: AccountId) -> PriceStatus {
+        let price_response = self.get_price_map(&asset);
+        let age = env::block_timestamp().saturating_sub(price_response.last_updated_at);
+
+        PriceStatus {
+            price: price_response.price,
+            last_updated_at: price_response.last_updated_at,
+            age: age / SECOND_TO_NANO,
+            is_fresh: age <= self.config.price_timeframe * SECOND_TO_NANO,
+        }
+    }
+
+    /// Same as `get_borrower_position`, with the loans read from the market beforehand
+    pub fn get_borrower_position_with_loans(
+        &self,
+        borrower: AccountId,
+        loans: Tokens,
+    ) -> BorrowerPosition {
+        self.compute_borrower_position(borrower, loans)
+    }
+
+    /// Collaterals of every borrower, a page at a time
+    pub fn get_all_collaterals(
+        &self,
+        start_after: Option<AccountId>,
+        limit: Option<u32>,
+    ) -> Vec<(AccountId, Tokens)> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let collaterals = &self.collection.collateral_map;
+
+        match start_after {
+            Some(start_after) => collaterals.iter_from(start_after).take(limit).collect(),
+            None => collaterals.iter().take(limit).collect(),
+        }
+    }
+
+    /// Latest epoch records of the reserve with their number, oldest first
+    pub fn get_epoch_history(
+        &self,
+        stable_asset: AccountId,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> Vec<(u64, EpochRecord)> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as u64;
+        let epoch_count = self.get_reserve_elem_map(&stable_asset).epoch_count;
+
+        let oldest = epoch_count.saturating_sub(EPOCH_HISTORY_SIZE);
+        let start = match start_after {
+            Some(start_after) => std::cmp::max(start_after + 1, oldest),
+            None => oldest,
+        };
+
+        (start..std::cmp::min(start + limit, epoch_count))
+            .map(|index| (index, self.get_epoch_record(&stable_asset, index)))
+            .collect()
+    }
+
+    /// Averages over the latest `epochs` epochs of the reserve
+    pub fn get_epoch_averages(&self, stable_asset: AccountId, epochs: u64) -> EpochAverages {
+        let epoch_count = self.get_reserve_elem_map(&stable_asset).epoch_count;
+        let epochs = epochs.min(epoch_count).min(EPOCH_HISTORY_SIZE);
+
+        let mut deposit_rate = D128::zero();
+        let mut interest_buffer: u128 = 0;
+        let mut anc_purchase_amount: u128 = 0;
+        let mut distributed_interest: u128 = 0;
+        let mut anc_emission_rate = D128::zero();
+
+        for index in epoch_count - epochs..epoch_count {
+            let record = self.get_epoch_record(&stable_asset, index);
+            deposit_rate = deposit_rate + record.deposit_rate;
+            interest_buffer += record.interest_buffer.0;
+            anc_purchase_amount += record.anc_purchase_amount.0;
+            distributed_interest += record.distributed_interest.0;
+            anc_emission_rate = anc_emission_rate + record.anc_emission_rate;
+        }
+
+        // an empty history averages to zero
+        let divisor = std::cmp::max(epochs, 1) as u128;
+
+        EpochAverages {
+            epochs,
+            deposit_rate: deposit_rate / divisor,
+            interest_buffer: U128::from(interest_buffer / divisor),
+            anc_purchase_amount: U128::from(anc_purchase_amount / divisor),
+            distributed_interest: U128::from(distributed_interest / divisor),
+            anc_emission_rate: anc_emission_rate / divisor,
+        }
+    }
+
+    /// Whitelisted collaterals with their status and the amount locked by every borrower
+    pub fn get_whitelist(
+        &self,
+        start_after: Option<AccountId>,
+        limit: Option<u32>,
+    ) -> Vec<WhitelistResponseElem> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let collateral_tokens = self.collection.white_list_elem_map.keys_as_vector();
+
+        let start = match start_after {
+            Some(start_after) => collateral_tokens
+                .iter()
+                .position(|collateral_token| collateral_token == start_after)
+                .map(|index| index + 1)
+                .unwrap_or(collateral_tokens.len() as usize),
+            None => 0,
+        };
+
+        (start..std::cmp::min(start + limit, collateral_tokens.len() as usize))
+            .map(|index| {
+                let collateral_token = collateral_tokens.get(index as u64).unwrap();
+                let elem = self
+                    .collection
+                    .white_list_elem_map
+                    .get(&collateral_token)
+                    .unwrap();
+
+                WhitelistResponseElem {
+                    total_locked: U128::from(self.get_total_locked(&collateral_token)),
+                    isolated_debt: U128::from(self.get_isolated_debt(&collateral_token)),
+                    collateral_token,
+                    name: elem.name,
+                    symbol: elem.symbol,
+                    decimals: elem.decimals,
+                    max_ltv: elem.max_ltv,
+                    liquidation_ltv: elem.liquidation_ltv,
+                    custody_contract: elem.custody_contract,
+                    status: elem.status,
+                    debt_ceiling: elem.debt_ceiling,
+                    category: elem.category,
+                }
+            })
+            .collect()
+    }
 }